@@ -35,13 +35,6 @@ fn main() -> Result<()> {
     }
 
     let include_dir = &protobuf_dir.join("include");
-    let conformance_include_dir = include_dir.join("conformance");
-    prost_build::compile_protos(
-        &[conformance_include_dir.join("conformance.proto")],
-        &[conformance_include_dir],
-    )
-    .unwrap();
-
     let test_includes = &include_dir.join("google").join("protobuf");
     prost_build::Config::new()
         .btree_map(&["."])
@@ -161,15 +154,5 @@ fn install_protos(src_dir: &Path, prefix_dir: &Path) -> Result<()> {
         .with_context(|| format!("failed to move {}", proto))?;
     }
 
-    // Move conformance.proto to the install directory.
-    let conformance_include_dir = include_dir.join("conformance");
-    fs::create_dir_all(&conformance_include_dir)
-        .expect("failed to create conformance include directory");
-    fs::rename(
-        src_dir.join("conformance").join("conformance.proto"),
-        conformance_include_dir.join("conformance.proto"),
-    )
-    .expect("failed to move conformance.proto");
-
     Ok(())
 }
@@ -4,8 +4,6 @@ pub mod conformance {
     pub fn test_runner() -> &'static Path {
         Path::new(concat!(env!("PROTOBUF"), "/bin/conformance-test-runner"))
     }
-
-    include!(concat!(env!("OUT_DIR"), "/conformance.rs"));
 }
 
 pub mod test_messages {
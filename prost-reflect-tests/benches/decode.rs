@@ -2,7 +2,7 @@ use std::{collections::BTreeMap, iter::FromIterator};
 
 use criterion::{criterion_group, criterion_main, Criterion};
 use prost::Message;
-use prost_reflect::{DynamicMessage, ReflectMessage};
+use prost_reflect::{DescriptorPool, DynamicMessage, ReflectMessage};
 use prost_reflect_tests::WellKnownTypes;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
@@ -76,6 +76,18 @@ fn encode_wkt(c: &mut Criterion) {
     });
 }
 
+fn clear_and_merge_wkt(c: &mut Criterion) {
+    let value = sample_wkt().encode_to_vec();
+    let desc = prost_reflect_tests::test_file_descriptor()
+        .get_message_by_name("test.WellKnownTypes")
+        .unwrap();
+    let mut message = DynamicMessage::new(desc);
+
+    c.bench_function("clear_and_merge_wkt", |b| {
+        b.iter(|| message.clear_and_merge(value.as_slice()))
+    });
+}
+
 fn decode_wkt_multithread(c: &mut Criterion) {
     let value = sample_wkt().transcode_to_dynamic();
 
@@ -106,9 +118,60 @@ fn encode_wkt_multithread(c: &mut Criterion) {
     });
 }
 
+fn field_descriptor_proto(c: &mut Criterion) {
+    let message_proto = prost_types::DescriptorProto {
+        name: Some("Large".to_owned()),
+        field: (1..=1000)
+            .map(|number| prost_types::FieldDescriptorProto {
+                name: Some(format!("field{}", number)),
+                number: Some(number),
+                label: Some(prost_types::field_descriptor_proto::Label::Optional as i32),
+                r#type: Some(prost_types::field_descriptor_proto::Type::Int32 as i32),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let pool = DescriptorPool::from_file_descriptor_set(prost_types::FileDescriptorSet {
+        file: vec![prost_types::FileDescriptorProto {
+            name: Some("large.proto".to_owned()),
+            message_type: vec![message_proto],
+            ..Default::default()
+        }],
+    })
+    .unwrap();
+    let desc = pool.get_message_by_name("Large").unwrap();
+
+    c.bench_function("field_descriptor_proto", |b| {
+        b.iter(|| {
+            for field in desc.fields() {
+                criterion::black_box(field.field_descriptor_proto());
+            }
+        })
+    });
+}
+
+fn decode_repeated_message(c: &mut Criterion) {
+    let value = prost_types::ListValue {
+        values: (0..100_000)
+            .map(|i| prost_types::Value {
+                kind: Some(prost_types::value::Kind::NumberValue(i as f64)),
+            })
+            .collect(),
+    }
+    .encode_to_vec();
+    let desc = prost_reflect_tests::test_file_descriptor()
+        .get_message_by_name("google.protobuf.ListValue")
+        .unwrap();
+
+    c.bench_function("decode_repeated_message", |b| {
+        b.iter(|| DynamicMessage::decode(desc.clone(), value.as_slice()))
+    });
+}
+
 criterion_group! {
     name = benches;
     config = Criterion::default().sample_size(500);
-    targets = decode_wkt, encode_wkt, decode_wkt_multithread, encode_wkt_multithread
+    targets = decode_wkt, encode_wkt, clear_and_merge_wkt, decode_wkt_multithread, encode_wkt_multithread, field_descriptor_proto, decode_repeated_message
 }
 criterion_main!(benches);
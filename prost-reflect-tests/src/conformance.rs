@@ -0,0 +1,122 @@
+use prost::Message;
+use prost_reflect::conformance::{
+    conformance_request::Payload, conformance_response::Result, handle_request, run,
+    ConformanceRequest, ConformanceResponse, TestCategory, WireFormat,
+};
+
+use crate::{test_file_descriptor, Point};
+
+fn request(payload: Payload, output: WireFormat) -> ConformanceRequest {
+    ConformanceRequest {
+        payload: Some(payload),
+        requested_output_format: output as i32,
+        message_type: "test.Point".to_owned(),
+        ..Default::default()
+    }
+}
+
+fn result(request: ConformanceRequest) -> Result {
+    handle_request(&test_file_descriptor(), request)
+        .result
+        .unwrap()
+}
+
+#[test]
+fn protobuf_to_json() {
+    let payload = Point {
+        latitude: 1,
+        longitude: 2,
+    }
+    .encode_to_vec();
+    assert_eq!(
+        result(request(Payload::ProtobufPayload(payload), WireFormat::Json)),
+        Result::JsonPayload(r#"{"latitude":1,"longitude":2}"#.to_owned())
+    );
+}
+
+#[test]
+fn json_to_protobuf() {
+    let payload = r#"{"latitude":1,"longitude":2}"#.to_owned();
+    let expected = Point {
+        latitude: 1,
+        longitude: 2,
+    }
+    .encode_to_vec();
+    assert_eq!(
+        result(request(Payload::JsonPayload(payload), WireFormat::Protobuf)),
+        Result::ProtobufPayload(expected)
+    );
+}
+
+#[test]
+fn json_ignore_unknown_fields() {
+    let payload = r#"{"latitude":1,"unknown":2}"#.to_owned();
+    let mut request = request(Payload::JsonPayload(payload), WireFormat::Json);
+    assert!(matches!(result(request.clone()), Result::ParseError(_)));
+
+    request.test_category = TestCategory::JsonIgnoreUnknownParsingTest as i32;
+    assert_eq!(
+        result(request),
+        Result::JsonPayload(r#"{"latitude":1}"#.to_owned())
+    );
+}
+
+#[test]
+fn invalid_requests() {
+    let mut unknown_type = request(Payload::ProtobufPayload(vec![]), WireFormat::Json);
+    unknown_type.message_type = "test.Unknown".to_owned();
+    assert_eq!(
+        result(unknown_type),
+        Result::ParseError("unknown message type: test.Unknown".to_owned())
+    );
+
+    assert!(matches!(
+        result(request(
+            Payload::ProtobufPayload(b"\x08".to_vec()),
+            WireFormat::Json
+        )),
+        Result::ParseError(_)
+    ));
+    assert!(matches!(
+        result(request(
+            Payload::TextPayload("latitude: 1".to_owned()),
+            WireFormat::Json
+        )),
+        Result::Skipped(_)
+    ));
+    assert!(matches!(
+        result(request(Payload::ProtobufPayload(vec![]), WireFormat::Jspb)),
+        Result::Skipped(_)
+    ));
+}
+
+#[test]
+fn run_length_delimited() {
+    let mut input = Vec::new();
+    for latitude in 1..=2 {
+        let payload = Point {
+            latitude,
+            longitude: 0,
+        }
+        .encode_to_vec();
+        let request =
+            request(Payload::ProtobufPayload(payload), WireFormat::TextFormat).encode_to_vec();
+        input.extend_from_slice(&(request.len() as u32).to_le_bytes());
+        input.extend_from_slice(&request);
+    }
+
+    let mut output = Vec::new();
+    run(&test_file_descriptor(), input.as_slice(), &mut output).unwrap();
+
+    let mut output = output.as_slice();
+    for latitude in 1..=2 {
+        let len = u32::from_le_bytes([output[0], output[1], output[2], output[3]]) as usize;
+        let response = ConformanceResponse::decode(&output[4..][..len]).unwrap();
+        assert_eq!(
+            response.result,
+            Some(Result::TextPayload(format!("latitude:{}", latitude)))
+        );
+        output = &output[4 + len..];
+    }
+    assert!(output.is_empty());
+}
@@ -1,19 +1,24 @@
 #![cfg(test)]
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet},
     fmt::Debug,
+    hash::{Hash, Hasher},
     iter::FromIterator,
 };
 
 use proptest::{prelude::*, test_runner::TestCaseError};
 use prost::{bytes::Bytes, Message};
-use prost_reflect::{DynamicMessage, MapKey, ReflectMessage, Value};
+use prost_reflect::{
+    BuildMessageError, DecodeOptions, DynReflect, DynamicMessage, MapKey, ReflectMessage,
+    SchemaRegistry, SetFieldError, TranscodeIssue, Value,
+};
 use prost_types::FileDescriptorSet;
 
 use crate::{
     contains_group, message_with_oneof, test_file_descriptor, ComplexType, ContainsGroup,
-    MessageWithOneof, ScalarArrays, Scalars, WellKnownTypes,
+    DefaultValues, MessageWithOneof, Point, Proto3Optional, ScalarArrays, Scalars, WellKnownTypes,
 };
 
 #[test]
@@ -36,8 +41,10 @@ fn clear_message() {
         bytes: b"6".to_vec(),
     }
     .transcode_to_dynamic();
+    assert!(!dynamic.is_empty());
 
     dynamic.clear();
+    assert!(dynamic.is_empty());
 
     assert!(!dynamic.has_field_by_name("double"));
     assert!(!dynamic.has_field_by_name("float"));
@@ -59,6 +66,56 @@ fn clear_message() {
     assert!(encoded_bytes.is_empty());
 }
 
+#[test]
+fn is_empty() {
+    let mut dynamic = DynamicMessage::new(Scalars::default().descriptor());
+    assert!(dynamic.is_empty());
+
+    dynamic.set_field_by_name("int32", Value::I32(0));
+    assert!(dynamic.is_empty());
+
+    dynamic.set_field_by_name("int32", Value::I32(1));
+    assert!(!dynamic.is_empty());
+
+    let unknown =
+        DynamicMessage::decode(Point::default().descriptor(), b"\x18\x01".as_ref()).unwrap();
+    assert!(!unknown.is_empty());
+    assert!(!unknown.encode_to_vec().is_empty());
+
+    let mut nested = DynamicMessage::new(ComplexType::default().descriptor());
+    nested.set_field_by_name(
+        "nested",
+        Value::Message(DynamicMessage::new(Scalars::default().descriptor())),
+    );
+    assert!(!nested.is_empty());
+}
+
+#[test]
+fn iter_fields_in_order() {
+    let desc = Scalars::default().descriptor();
+    let numbers: Vec<u32> = desc.fields().map(|f| f.number()).collect();
+    assert!(numbers.windows(2).all(|w| w[0] < w[1]));
+
+    let mut dynamic = DynamicMessage::new(desc);
+    dynamic.set_field_by_name("string", Value::String("hello".to_owned()));
+    dynamic.set_field_by_name("int64", Value::I64(0));
+    dynamic.set_field_by_name("int32", Value::I32(5));
+    dynamic.set_field_by_name("double", Value::F64(1.5));
+
+    let fields: Vec<_> = dynamic
+        .iter_fields_in_order()
+        .map(|(field, value)| (field.name().to_owned(), value.clone()))
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            ("double".to_owned(), Value::F64(1.5)),
+            ("int32".to_owned(), Value::I32(5)),
+            ("string".to_owned(), Value::String("hello".to_owned())),
+        ]
+    );
+}
+
 #[test]
 #[should_panic(expected = "nvalid value U32(5) for field")]
 fn set_field_validates_type() {
@@ -70,6 +127,50 @@ fn set_field_validates_type() {
     dynamic.set_field_by_name("double", Value::U32(5));
 }
 
+#[test]
+fn value_enum_value() {
+    let enum_desc = test_file_descriptor()
+        .get_enum_by_name("test.ComplexType.MyEnum")
+        .unwrap();
+
+    assert_eq!(
+        Value::enum_value(&enum_desc, -4, true),
+        Ok(Value::EnumNumber(-4))
+    );
+    assert_eq!(
+        Value::enum_value(&enum_desc, 2, false),
+        Ok(Value::EnumNumber(2))
+    );
+
+    let err = Value::enum_value(&enum_desc, 2, true).unwrap_err();
+    assert_eq!(err.enum_descriptor(), &enum_desc);
+    assert_eq!(err.number(), 2);
+    assert_eq!(
+        err.to_string(),
+        "invalid value 2 for enum test.ComplexType.MyEnum"
+    );
+}
+
+#[test]
+fn value_type_name() {
+    let dynamic = {
+        let message = &ComplexType::default();
+        message.transcode_to_dynamic()
+    };
+
+    assert_eq!(Value::Bool(true).type_name(), "bool");
+    assert_eq!(Value::String(String::new()).type_name(), "string");
+    assert_eq!(Value::EnumNumber(1).type_name(), "enum");
+    assert_eq!(
+        dynamic.get_field_by_name("string_map").unwrap().type_name(),
+        "map"
+    );
+    assert_eq!(
+        dynamic.get_field_by_name("nested").unwrap().type_name(),
+        "message"
+    );
+}
+
 #[test]
 fn decode_scalars() {
     let dynamic = Scalars {
@@ -153,6 +254,64 @@ fn decode_scalars() {
     );
 }
 
+#[test]
+fn get_field_by_name_as() {
+    let dynamic = Scalars {
+        double: 1.1,
+        float: 2.2,
+        int32: 3,
+        int64: 4,
+        uint32: 5,
+        uint64: 6,
+        r#bool: true,
+        string: "5".to_owned(),
+        bytes: b"6".to_vec(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert_eq!(dynamic.get_field_by_name_as::<f64>("double"), Some(Ok(1.1)));
+    assert_eq!(dynamic.get_field_by_name_as::<f32>("float"), Some(Ok(2.2)));
+    assert_eq!(dynamic.get_field_by_name_as::<i32>("int32"), Some(Ok(3)));
+    assert_eq!(dynamic.get_field_by_name_as::<i64>("int64"), Some(Ok(4)));
+    assert_eq!(dynamic.get_field_by_name_as::<u32>("uint32"), Some(Ok(5)));
+    assert_eq!(dynamic.get_field_by_name_as::<u64>("uint64"), Some(Ok(6)));
+    assert_eq!(dynamic.get_field_by_name_as::<bool>("bool"), Some(Ok(true)));
+    assert_eq!(
+        dynamic.get_field_by_name_as::<String>("string"),
+        Some(Ok("5".to_owned()))
+    );
+    assert_eq!(
+        dynamic.get_field_by_name_as::<Bytes>("bytes"),
+        Some(Ok(Bytes::from_static(b"6")))
+    );
+    assert_eq!(dynamic.get_field_by_name_as::<i32>("not_found"), None);
+
+    let err = dynamic
+        .get_field_by_name_as::<i64>("int32")
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.value(), &Value::I32(3));
+    assert_eq!(
+        err.to_string(),
+        "expected a value of type 'i64', but found a value of type 'i32'"
+    );
+
+    let complex = ComplexType {
+        nested: Some(Scalars {
+            int32: 3,
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let nested = complex
+        .get_field_by_name_as::<DynamicMessage>("nested")
+        .unwrap()
+        .unwrap();
+    assert_eq!(nested.get_field_by_name_as::<i32>("int32"), Some(Ok(3)));
+}
+
 #[test]
 fn decode_scalar_arrays() {
     let dynamic = ScalarArrays {
@@ -467,6 +626,171 @@ fn decode_default_values() {
     );
 }
 
+#[test]
+fn get_field_ref_does_not_use_defaults() {
+    let mut dynamic = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name(".test2.DefaultValues")
+            .unwrap(),
+    );
+    let field = dynamic.descriptor().get_field_by_name("int32").unwrap();
+
+    assert_eq!(dynamic.get_field_ref(&field), None);
+    assert_eq!(
+        dynamic.get_field_or_default(&field).into_owned(),
+        Value::I32(-3)
+    );
+
+    dynamic.set_field(&field, Value::I32(7));
+    assert_eq!(dynamic.get_field_ref(&field), Some(&Value::I32(7)));
+    assert_eq!(
+        dynamic.get_field_or_default(&field).into_owned(),
+        Value::I32(7)
+    );
+}
+
+#[test]
+fn enum_value_descriptor() {
+    let mut dynamic = DynamicMessage::new(ComplexType::default().descriptor());
+    let field = dynamic
+        .descriptor()
+        .get_field_by_name("optional_enum")
+        .unwrap();
+
+    dynamic.set_field(&field, Value::EnumNumber(3));
+    let value = dynamic.get_field(&field).as_enum_value(&field).unwrap();
+    assert_eq!(value.name(), "BAR");
+    assert_eq!(value.number(), 3);
+
+    dynamic.set_field(&field, Value::EnumNumber(42));
+    assert!(dynamic.get_field(&field).as_enum_value(&field).is_none());
+
+    let scalars = Scalars::default().descriptor();
+    let int_field = scalars.get_field_by_name("int32").unwrap();
+    assert!(Value::EnumNumber(3).as_enum_value(&int_field).is_none());
+    assert!(Value::I32(3).as_enum_value(&field).is_none());
+}
+
+#[test]
+fn default_valued_fields_compare_equal_to_unset() {
+    let mut explicit_default = DynamicMessage::new(Scalars::default().descriptor());
+    explicit_default.set_field_by_name("int32", Value::I32(0));
+    explicit_default.set_field_by_name("string", Value::String(String::new()));
+    let unset = DynamicMessage::new(Scalars::default().descriptor());
+    assert_eq!(explicit_default, unset);
+
+    explicit_default.set_field_by_name("int32", Value::I32(1));
+    assert_ne!(explicit_default, unset);
+
+    let mut nested_default = DynamicMessage::new(ComplexType::default().descriptor());
+    nested_default.set_field_by_name("my_enum", Value::List(vec![]));
+    nested_default
+        .get_field_by_name_mut("nested")
+        .unwrap()
+        .as_message_mut()
+        .unwrap()
+        .set_field_by_name("int32", Value::I32(0));
+    let mut nested_unset = DynamicMessage::new(ComplexType::default().descriptor());
+    nested_unset.get_field_by_name_mut("nested").unwrap();
+    assert_eq!(nested_default, nested_unset);
+}
+
+#[test]
+fn hash_consistent_with_eq() {
+    fn hash(message: &DynamicMessage) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        message.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let mut explicit_default = DynamicMessage::new(Scalars::default().descriptor());
+    explicit_default.set_field_by_name("int32", Value::I32(0));
+    explicit_default.set_field_by_name("double", Value::F64(-0.0));
+    let unset = DynamicMessage::new(Scalars::default().descriptor());
+    assert_eq!(hash(&explicit_default), hash(&unset));
+
+    let mut nan = DynamicMessage::new(Scalars::default().descriptor());
+    nan.set_field_by_name("double", Value::F64(f64::NAN));
+    nan.set_field_by_name("float", Value::F32(f32::NAN));
+    assert_eq!(nan, nan.clone());
+    assert_eq!(hash(&nan), hash(&nan.clone()));
+    assert_ne!(nan, unset);
+
+    let keys = (0..32).map(|i| i.to_string()).collect::<Vec<_>>();
+    let a = ComplexType {
+        string_map: keys
+            .iter()
+            .map(|key| (key.clone(), Scalars::default()))
+            .collect(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let b = ComplexType {
+        string_map: keys
+            .iter()
+            .rev()
+            .map(|key| (key.clone(), Scalars::default()))
+            .collect(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    assert_eq!(a, b);
+    assert_eq!(hash(&a), hash(&b));
+
+    let messages: HashSet<DynamicMessage> =
+        vec![a, b, unset, nan.clone(), nan].into_iter().collect();
+    assert_eq!(messages.len(), 3);
+}
+
+#[test]
+fn value_partial_cmp() {
+    assert!(Value::Bool(false) < Value::Bool(true));
+    assert!(Value::I32(-1) < Value::I32(1));
+    assert!(Value::U64(u64::MAX) > Value::U64(0));
+    assert!(Value::EnumNumber(2) > Value::EnumNumber(1));
+    assert!(Value::String("a".to_owned()) < Value::String("b".to_owned()));
+    assert!(
+        Value::Bytes(Bytes::from_static(b"\x00\xff")) < Value::Bytes(Bytes::from_static(b"\x01"))
+    );
+
+    assert!(Value::F64(f64::NEG_INFINITY) < Value::F64(-1.0));
+    assert!(Value::F32(1.5) < Value::F32(f32::INFINITY));
+    assert!(Value::F64(f64::INFINITY) < Value::F64(f64::NAN));
+    assert!(Value::F64(-f64::NAN) < Value::F64(f64::NEG_INFINITY));
+    assert!(Value::F32(1.5) < Value::F32(f32::NAN));
+    assert_eq!(Value::F64(f64::NAN), Value::F64(f64::NAN));
+    assert_eq!(
+        Value::F64(f64::NAN).partial_cmp(&Value::F64(f64::NAN)),
+        Some(Ordering::Equal)
+    );
+    assert_ne!(Value::F64(f64::NAN), Value::F64(-f64::NAN));
+    assert_eq!(
+        Value::F64(-0.0).partial_cmp(&Value::F64(0.0)),
+        Some(Ordering::Equal)
+    );
+
+    assert_eq!(Value::I32(1).partial_cmp(&Value::I64(1)), None);
+    assert_eq!(Value::EnumNumber(1).partial_cmp(&Value::I32(1)), None);
+    assert_eq!(
+        Value::List(vec![Value::I32(1)]).partial_cmp(&Value::List(vec![Value::I32(1)])),
+        Some(Ordering::Equal)
+    );
+    assert_eq!(
+        Value::List(vec![Value::I32(1)]).partial_cmp(&Value::List(vec![Value::I32(2)])),
+        None
+    );
+
+    let mut values = vec![Value::U32(3), Value::U32(1), Value::U32(2)];
+    values.sort_by(Value::total_cmp);
+    assert_eq!(values, vec![Value::U32(1), Value::U32(2), Value::U32(3)]);
+}
+
+#[test]
+#[should_panic(expected = "cannot compare values")]
+fn value_total_cmp_different_types() {
+    Value::I32(1).total_cmp(&Value::I64(1));
+}
+
 #[test]
 fn set_oneof() {
     let mut dynamic = DynamicMessage::new(
@@ -492,6 +816,221 @@ fn set_oneof() {
     assert!(!dynamic.has_field_by_name("oneof_field_1"));
 }
 
+#[test]
+fn merge_from_dynamic() {
+    let mut dynamic = MessageWithOneof {
+        test_oneof: Some(message_with_oneof::TestOneof::OneofField1(
+            "hello".to_owned(),
+        )),
+    }
+    .transcode_to_dynamic();
+    let other = MessageWithOneof {
+        test_oneof: Some(message_with_oneof::TestOneof::OneofField2(5)),
+    }
+    .transcode_to_dynamic();
+
+    dynamic.merge_from(&other).unwrap();
+    assert!(!dynamic.has_field_by_name("oneof_field_1"));
+    assert_eq!(dynamic, other);
+
+    // Unknown fields are appended
+    let mut unknown =
+        DynamicMessage::decode(Point::default().descriptor(), b"\x18\x01".as_ref()).unwrap();
+    let other_unknown =
+        DynamicMessage::decode(Point::default().descriptor(), b"\x18\x02".as_ref()).unwrap();
+    unknown.merge_from(&other_unknown).unwrap();
+    assert_eq!(unknown.encode_to_vec(), b"\x18\x01\x18\x02");
+
+    // Unknown fields replace a known value with the same number
+    let mut known = Point {
+        latitude: 1,
+        longitude: 2,
+    }
+    .transcode_to_dynamic();
+    let mismatched = DynamicMessage::decode_with_options(
+        Point::default().descriptor(),
+        b"\x0d\x03\x00\x00\x00".as_ref(),
+        &DecodeOptions::new().allow_wire_type_mismatch(true),
+    )
+    .unwrap();
+    known.merge_from(&mismatched).unwrap();
+    assert!(!known.has_field_by_name("latitude"));
+    assert_eq!(
+        known.get_field_by_name("longitude").unwrap().as_ref(),
+        &Value::I32(2)
+    );
+    assert_eq!(known.encode_to_vec(), b"\x0d\x03\x00\x00\x00\x10\x02");
+
+    let err = dynamic.merge_from(&unknown).unwrap_err();
+    assert_eq!(err.expected(), &MessageWithOneof::default().descriptor());
+    assert_eq!(err.actual(), &Point::default().descriptor());
+    assert_eq!(
+        err.to_string(),
+        "cannot merge a message of type 'test.Point' into a message of type 'test.MessageWithOneof'"
+    );
+}
+
+#[test]
+fn to_flat_map() {
+    let dynamic = ComplexType {
+        string_map: HashMap::from_iter([(
+            "a".to_owned(),
+            Scalars {
+                int32: 1,
+                string: "x".to_owned(),
+                ..Default::default()
+            },
+        )]),
+        int_map: HashMap::from_iter([(
+            5,
+            Scalars {
+                bool: true,
+                ..Default::default()
+            },
+        )]),
+        nested: Some(Scalars::default()),
+        my_enum: vec![2, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert_eq!(
+        dynamic.to_flat_map(),
+        BTreeMap::from_iter([
+            ("int_map.5.bool".to_owned(), Value::Bool(true)),
+            ("my_enum.0".to_owned(), Value::EnumNumber(2)),
+            ("my_enum.1".to_owned(), Value::EnumNumber(3)),
+            ("string_map.a.int32".to_owned(), Value::I32(1)),
+            (
+                "string_map.a.string".to_owned(),
+                Value::String("x".to_owned())
+            ),
+        ])
+    );
+}
+
+#[test]
+fn from_flat_map() {
+    let message = ComplexType {
+        string_map: HashMap::from_iter([(
+            "a".to_owned(),
+            Scalars {
+                int32: 1,
+                ..Default::default()
+            },
+        )]),
+        int_map: HashMap::from_iter([(5, Scalars::default())]),
+        my_enum: (0..12).collect(),
+        ..Default::default()
+    };
+    let desc = message.descriptor();
+    let flat_map = message.transcode_to_dynamic().to_flat_map();
+    let dynamic = DynamicMessage::from_flat_map(desc.clone(), &flat_map).unwrap();
+    assert_eq!(
+        dynamic.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            int_map: HashMap::new(),
+            ..message
+        }
+    );
+
+    let err = DynamicMessage::from_flat_map(
+        desc.clone(),
+        &BTreeMap::from_iter([
+            ("my_enum.0".to_owned(), Value::EnumNumber(1)),
+            ("my_enum.2".to_owned(), Value::EnumNumber(1)),
+        ]),
+    )
+    .unwrap_err();
+    assert_eq!(err.key(), "my_enum.2");
+    assert_eq!(
+        err.to_string(),
+        "invalid key 'my_enum.2': list index 2 is out of order, expected 1"
+    );
+
+    let err = DynamicMessage::from_flat_map(
+        desc,
+        &BTreeMap::from_iter([("int_map.x.int32".to_owned(), Value::I32(1))]),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid key 'int_map.x.int32': 'x' is not a valid map key"
+    );
+}
+
+#[test]
+fn clear_oneof() {
+    let mut dynamic = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name(".test.MessageWithOneof")
+            .unwrap(),
+    );
+    let oneof = dynamic
+        .descriptor()
+        .get_oneof_by_name("test_oneof")
+        .unwrap();
+    assert_eq!(oneof.field_count(), 4);
+
+    assert!(!dynamic.clear_oneof(&oneof));
+
+    dynamic.set_field_by_name("oneof_field_2", Value::I32(0));
+    assert!(dynamic.clear_oneof(&oneof));
+    assert!(!dynamic.has_field_by_name("oneof_field_2"));
+    assert_eq!(dynamic.oneof_field(&oneof), None);
+    assert!(!dynamic.clear_oneof(&oneof));
+}
+
+#[test]
+fn which_oneof() {
+    let desc = test_file_descriptor()
+        .get_message_by_name(".test.MessageWithOneof")
+        .unwrap();
+    let oneof = desc.get_oneof_by_name("test_oneof").unwrap();
+    assert_eq!(oneof.name(), "test_oneof");
+    assert!(desc.get_oneof_by_name("not_found").is_none());
+
+    let mut dynamic = DynamicMessage::new(desc);
+    assert_eq!(dynamic.which_oneof("test_oneof"), None);
+
+    dynamic.set_field_by_name("oneof_field_1", Value::String("hello".to_owned()));
+    assert_eq!(
+        dynamic.which_oneof("test_oneof").unwrap().name(),
+        "oneof_field_1"
+    );
+
+    dynamic.set_field_by_name("oneof_field_2", Value::I32(0));
+    assert_eq!(
+        dynamic.which_oneof("test_oneof").unwrap().name(),
+        "oneof_field_2"
+    );
+    assert_eq!(dynamic.which_oneof("not_found"), None);
+}
+
+#[test]
+fn oneof_field() {
+    let desc = test_file_descriptor()
+        .get_message_by_name(".test.MessageWithOneof")
+        .unwrap();
+    let oneof = desc.get_oneof_by_name("test_oneof").unwrap();
+
+    let mut dynamic = DynamicMessage::new(desc);
+    assert_eq!(dynamic.oneof_field(&oneof), None);
+
+    dynamic.set_field_by_name("oneof_field_1", Value::String("hello".to_owned()));
+    let (field, value) = dynamic.oneof_field(&oneof).unwrap();
+    assert_eq!(field.name(), "oneof_field_1");
+    assert_eq!(value, &Value::String("hello".to_owned()));
+
+    dynamic.set_field_by_name("oneof_field_2", Value::I32(5));
+    let (field, value) = dynamic.oneof_field(&oneof).unwrap();
+    assert_eq!(field.name(), "oneof_field_2");
+    assert_eq!(value, &Value::I32(5));
+
+    dynamic.clear_field_by_name("oneof_field_2");
+    assert_eq!(dynamic.oneof_field(&oneof), None);
+}
+
 #[test]
 fn set_oneof_to_default() {
     let mut dynamic = DynamicMessage::new(
@@ -518,28 +1057,162 @@ fn set_oneof_to_default() {
 }
 
 #[test]
-fn roundtrip_scalars() {
-    roundtrip(&Scalars {
-        double: 1.1,
-        float: 2.2,
-        int32: 3,
-        int64: 4,
-        uint32: 5,
-        uint64: 6,
-        sint32: 7,
-        sint64: 8,
-        fixed32: 9,
-        fixed64: 10,
-        sfixed32: 11,
-        sfixed64: 12,
-        r#bool: true,
-        string: "5".to_owned(),
-        bytes: b"6".to_vec(),
-    })
-    .unwrap();
-}
+fn set_fields() {
+    let mut dynamic = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name(".test.MessageWithOneof")
+            .unwrap(),
+    );
 
-#[test]
+    dynamic
+        .set_fields(vec![
+            ("oneof_field_1", Value::String("hello".to_owned())),
+            ("oneof_field_2", Value::I32(5)),
+        ])
+        .unwrap();
+    assert!(!dynamic.has_field_by_name("oneof_field_1"));
+    assert_eq!(
+        dynamic.get_field_by_name("oneof_field_2").unwrap().as_i32(),
+        Some(5)
+    );
+
+    assert_eq!(
+        dynamic.set_fields(vec![
+            ("oneof_field_1", Value::String("hello".to_owned())),
+            ("not_found", Value::I32(1)),
+        ]),
+        Err(SetFieldError::NotFound {
+            name: "not_found".to_owned()
+        })
+    );
+    let err = dynamic
+        .set_fields(vec![
+            (
+                "oneof_field_1".to_owned(),
+                Value::String("hello".to_owned()),
+            ),
+            ("oneof_field_2".to_owned(), Value::U64(1)),
+        ])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the value '1' is not valid for field 'test.MessageWithOneof.oneof_field_2'"
+    );
+    assert!(!dynamic.has_field_by_name("oneof_field_1"));
+    assert_eq!(
+        dynamic.get_field_by_name("oneof_field_2").unwrap().as_i32(),
+        Some(5)
+    );
+
+    assert!(dynamic
+        .try_set_field_by_name("oneof_field_1", Value::I32(1))
+        .is_err());
+    dynamic
+        .try_set_field_by_name("oneof_field_1", Value::String("hello".to_owned()))
+        .unwrap();
+    assert!(!dynamic.has_field_by_name("oneof_field_2"));
+}
+
+#[test]
+fn copy_fields_by_name_from() {
+    let src = Scalars {
+        int32: 3,
+        string: "hello".to_owned(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let mut dst = DynamicMessage::new(Scalars::default().descriptor());
+    assert_eq!(dst.copy_fields_by_name_from(&src), vec!["int32", "string"]);
+    assert_eq!(dst, src);
+
+    let mut dst = DynamicMessage::new(ScalarArrays::default().descriptor());
+    assert!(dst.copy_fields_by_name_from(&src).is_empty());
+    assert!(!dst.has_field_by_name("int32"));
+
+    let mut dst = DynamicMessage::new(WellKnownTypes::default().descriptor());
+    assert!(dst.copy_fields_by_name_from(&src).is_empty());
+    assert!(!dst.has_field_by_name("string"));
+
+    let src = ScalarArrays {
+        int32: vec![1, 2],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let mut dst = DynamicMessage::new(ScalarArrays::default().descriptor());
+    assert_eq!(dst.copy_fields_by_name_from(&src), vec!["int32"]);
+    assert_eq!(
+        dst.transcode_to::<ScalarArrays>().unwrap().int32,
+        vec![1, 2]
+    );
+}
+
+#[test]
+fn set_field_packing_any() {
+    let desc = test_file_descriptor().get_message_by_name("Dummy").unwrap();
+    let field = desc.get_field_by_name("dummy").unwrap();
+    let point = Point {
+        latitude: 1,
+        longitude: 2,
+    };
+
+    let mut dynamic = DynamicMessage::new(desc.clone());
+    dynamic.set_field_packing_any(&field, Value::Message(point.transcode_to_dynamic()));
+    let any = dynamic
+        .get_field(&field)
+        .as_message()
+        .unwrap()
+        .transcode_to::<prost_types::Any>()
+        .unwrap();
+    assert_eq!(any.type_url, "type.googleapis.com/test.Point");
+    assert_eq!(any.value, point.encode_to_vec());
+
+    let mut repacked = DynamicMessage::new(desc);
+    repacked.set_field_packing_any(&field, dynamic.get_field(&field).into_owned());
+    assert_eq!(repacked, dynamic);
+}
+
+#[test]
+fn from_msg_and_reflect() {
+    let point = Point {
+        latitude: 1,
+        longitude: 2,
+    };
+
+    let dynamic = DynamicMessage::from_msg(&point);
+    assert_eq!(dynamic, point.transcode_to_dynamic());
+    assert_eq!(dynamic.reflect::<Point>(), point);
+}
+
+#[test]
+#[should_panic(expected = "mismatched message type")]
+fn reflect_mismatched_type() {
+    DynamicMessage::from_msg(&Point::default()).reflect::<Scalars>();
+}
+
+#[test]
+fn roundtrip_scalars() {
+    roundtrip(&Scalars {
+        double: 1.1,
+        float: 2.2,
+        int32: 3,
+        int64: 4,
+        uint32: 5,
+        uint64: 6,
+        sint32: 7,
+        sint64: 8,
+        fixed32: 9,
+        fixed64: 10,
+        sfixed32: 11,
+        sfixed64: 12,
+        r#bool: true,
+        string: "5".to_owned(),
+        bytes: b"6".to_vec(),
+    })
+    .unwrap();
+}
+
+#[test]
 fn roundtrip_scalar_arrays() {
     roundtrip(&ScalarArrays {
         double: vec![1.1, 2.2],
@@ -698,6 +1371,26 @@ proptest! {
     fn roundtrip_arb_well_known_types(message: WellKnownTypes) {
         roundtrip(&message)?;
     }
+
+    #[test]
+    fn merge_from_arb_complex_type(message: ComplexType, other: ComplexType) {
+        merge_from(&message, &other)?;
+    }
+
+    #[test]
+    fn merge_from_arb_well_known_types(message: WellKnownTypes, other: WellKnownTypes) {
+        merge_from(&message, &other)?;
+    }
+
+    #[test]
+    fn clear_and_merge_arb_complex_type(message: ComplexType, other: ComplexType) {
+        clear_and_merge(&message, &other)?;
+    }
+
+    #[test]
+    fn clear_and_merge_arb_well_known_types(message: WellKnownTypes, other: WellKnownTypes) {
+        clear_and_merge(&message, &other)?;
+    }
 }
 
 #[test]
@@ -817,6 +1510,44 @@ fn roundtrip_extension() {
     );
 }
 
+#[test]
+fn reparse_extensions() {
+    let message_desc = test_file_descriptor()
+        .get_message_by_name("my.package2.MyMessage")
+        .unwrap();
+    assert!(message_desc.get_extension(100).is_none());
+
+    let mut dynamic_message =
+        DynamicMessage::decode(message_desc, b"\xa0\x06\x05".as_ref()).unwrap();
+    assert!(!dynamic_message.is_empty());
+
+    let mut pool = test_file_descriptor();
+    pool.add_file_descriptor_proto(prost_types::FileDescriptorProto {
+        name: Some("reparse_extensions.proto".to_owned()),
+        package: Some("my.package3".to_owned()),
+        dependency: vec!["desc2.proto".to_owned()],
+        extension: vec![prost_types::FieldDescriptorProto {
+            name: Some("late".to_owned()),
+            number: Some(100),
+            label: Some(prost_types::field_descriptor_proto::Label::Optional as i32),
+            r#type: Some(prost_types::field_descriptor_proto::Type::Int32 as i32),
+            extendee: Some(".my.package2.MyMessage".to_owned()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    })
+    .unwrap();
+
+    dynamic_message.reparse_extensions(&pool).unwrap();
+    let extension_desc = pool.get_extension_by_name("my.package3.late").unwrap();
+    assert!(dynamic_message.has_extension(&extension_desc));
+    assert_eq!(
+        dynamic_message.get_extension(&extension_desc).as_ref(),
+        &Value::I32(5)
+    );
+    assert_eq!(dynamic_message.encode_to_vec().as_slice(), b"\xa0\x06\x05");
+}
+
 #[test]
 fn roundtrip_file_descriptor_set() {
     let file: Vec<_> = test_file_descriptor()
@@ -870,32 +1601,1070 @@ fn roundtrip_group() {
     .unwrap();
 }
 
-fn roundtrip<T>(message: &T) -> Result<(), TestCaseError>
-where
-    T: PartialEq + Debug + ReflectMessage + Default,
-{
-    let dynamic_message = message.transcode_to_dynamic();
-    let roundtripped_message: T = dynamic_message.transcode_to().unwrap();
-    prop_assert_eq!(message, &roundtripped_message);
+#[test]
+fn encode_group_wire_type() {
+    let message = ContainsGroup {
+        requiredgroup: Some(contains_group::RequiredGroup {
+            a: "x".to_string(),
+            b: None,
+        }),
+        ..Default::default()
+    };
+    let dynamic = message.transcode_to_dynamic();
 
-    // Check roundtripping through unknown fields works
-    let mut empty_message = DynamicMessage::new(
-        test_file_descriptor()
-            .get_message_by_name("google.protobuf.Empty")
-            .unwrap(),
+    // The group is delimited by start-group and end-group tags, rather than a length prefix.
+    let bytes = dynamic.encode_to_vec();
+    assert_eq!(bytes.as_slice(), b"\x0b\x0a\x01x\x0c");
+
+    let decoded = DynamicMessage::decode(dynamic.descriptor(), bytes.as_slice()).unwrap();
+    assert_eq!(
+        decoded
+            .get_field_by_name("requiredgroup")
+            .unwrap()
+            .as_message()
+            .unwrap()
+            .get_field_by_name("a")
+            .unwrap()
+            .as_str(),
+        Some("x")
     );
-    empty_message.transcode_from(message).unwrap();
-    let unknown_roundtripped_message: T = empty_message.transcode_to().unwrap();
-    prop_assert_eq!(
-        message,
-        &unknown_roundtripped_message,
-        "roundtrip through unknown fields failed"
+
+    assert!(DynamicMessage::decode(dynamic.descriptor(), b"\x0a\x03\x0a\x01x".as_ref()).is_err());
+}
+
+#[test]
+fn decode_single_field() {
+    fn check<T>(message: &T)
+    where
+        T: ReflectMessage,
+    {
+        let bytes = message.encode_to_vec();
+        let dynamic = DynamicMessage::decode(message.descriptor(), bytes.as_slice()).unwrap();
+        for field in message.descriptor().fields() {
+            let value = DynamicMessage::decode_field(
+                &message.descriptor(),
+                field.number(),
+                bytes.as_slice(),
+            )
+            .unwrap();
+            if dynamic.has_field(&field) {
+                assert_eq!(value.as_ref(), Some(dynamic.get_field(&field).as_ref()));
+            } else {
+                assert_eq!(value, None);
+            }
+        }
+    }
+
+    check(&ComplexType {
+        string_map: HashMap::from_iter([(
+            "foo".to_owned(),
+            Scalars {
+                int32: 5,
+                ..Default::default()
+            },
+        )]),
+        nested: Some(Scalars {
+            string: "hello".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![0, 1, 3, -4],
+        optional_enum: 1,
+        ..Default::default()
+    });
+    check(&ContainsGroup {
+        requiredgroup: Some(contains_group::RequiredGroup {
+            a: "bar".to_string(),
+            b: None,
+        }),
+        optionalgroup: None,
+        repeatedgroup: vec![
+            contains_group::RepeatedGroup {
+                ..Default::default()
+            },
+            contains_group::RepeatedGroup {
+                e: "hello".to_string(),
+                f: Some(10),
+            },
+        ],
+    });
+
+    let desc = ComplexType::default().descriptor();
+    assert_eq!(
+        DynamicMessage::decode_field(&desc, 100, b"\x08\x96\x01".as_ref()).unwrap(),
+        None
     );
+    assert!(DynamicMessage::decode_field(&desc, 5, b"\x28".as_ref()).is_err());
+}
 
-    // Check that transcoding to a new dynamic message is equivalent to just cloning it.
-    let mut duplicate_message = DynamicMessage::new(dynamic_message.descriptor());
-    duplicate_message.transcode_from(&dynamic_message).unwrap();
-    assert_eq!(dynamic_message, duplicate_message);
+#[test]
+fn retain_fields() {
+    let message = ComplexType {
+        string_map: HashMap::from_iter([(
+            "foo".to_owned(),
+            Scalars {
+                int32: 5,
+                string: "secret".to_owned(),
+                ..Default::default()
+            },
+        )]),
+        nested: Some(Scalars {
+            int32: 6,
+            string: "secret".to_owned(),
+            ..Default::default()
+        }),
+        optional_enum: 1,
+        ..Default::default()
+    };
 
-    Ok(())
+    let mut shallow = message.transcode_to_dynamic();
+    shallow.retain_fields(|field, _| field.name() != "optional_enum" && field.name() != "string");
+    assert!(!shallow.has_field_by_name("optional_enum"));
+    assert_eq!(
+        shallow
+            .get_field_by_name("nested")
+            .unwrap()
+            .as_message()
+            .unwrap()
+            .get_field_by_name("string")
+            .unwrap()
+            .as_str(),
+        Some("secret")
+    );
+
+    let mut deep = message.transcode_to_dynamic();
+    deep.retain_fields_deep(|field, _| field.name() != "string");
+    assert_eq!(
+        deep.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            string_map: HashMap::from_iter([(
+                "foo".to_owned(),
+                Scalars {
+                    int32: 5,
+                    ..Default::default()
+                },
+            )]),
+            nested: Some(Scalars {
+                int32: 6,
+                ..Default::default()
+            }),
+            optional_enum: 1,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn diff_first() {
+    let a = ComplexType {
+        string_map: HashMap::from_iter([(
+            "foo".to_owned(),
+            Scalars {
+                int32: 5,
+                ..Default::default()
+            },
+        )]),
+        nested: Some(Scalars {
+            int32: 3,
+            ..Default::default()
+        }),
+        my_enum: vec![0, 1],
+        ..Default::default()
+    };
+
+    let mut b = a.clone();
+    b.nested.as_mut().unwrap().int32 = 4;
+    let mut c = a.clone();
+    c.my_enum.push(3);
+    let mut d = a.clone();
+    d.string_map.get_mut("foo").unwrap().string = "bar".to_owned();
+
+    let a = a.transcode_to_dynamic();
+    assert_eq!(a.diff_first(&a), None);
+    assert_eq!(
+        a.diff_first(&b.transcode_to_dynamic()).as_deref(),
+        Some(r#"field "nested.int32": 3 != 4"#)
+    );
+    assert_eq!(
+        a.diff_first(&c.transcode_to_dynamic()).as_deref(),
+        Some(r#"field "my_enum": list length 2 != 3"#)
+    );
+    assert_eq!(
+        a.diff_first(&d.transcode_to_dynamic()).as_deref(),
+        Some(r#"field "string_map["foo"].string": "" != "bar""#)
+    );
+}
+
+#[test]
+fn eq_ignoring() {
+    let a = ComplexType {
+        string_map: HashMap::from_iter([(
+            "foo".to_owned(),
+            Scalars {
+                int32: 5,
+                ..Default::default()
+            },
+        )]),
+        nested: Some(Scalars {
+            int32: 3,
+            ..Default::default()
+        }),
+        my_enum: vec![0, 1],
+        ..Default::default()
+    };
+
+    let mut b = a.clone();
+    b.nested.as_mut().unwrap().int32 = 4;
+    b.string_map.get_mut("foo").unwrap().string = "bar".to_owned();
+    let b = b.transcode_to_dynamic();
+    let a = a.transcode_to_dynamic();
+
+    assert!(!a.eq_ignoring(&b, &[]));
+    assert!(a.eq_ignoring(&b, &["nested", "string_map"]));
+    assert!(a.eq_ignoring(&b, &["nested.int32", "string_map.string"]));
+    assert!(a.eq_ignoring(&b, &["*.int32", "*.string"]));
+    assert_eq!(
+        a.diff_first_ignoring(&b, &["nested.int32"]).as_deref(),
+        Some(r#"field "string_map["foo"].string": "" != "bar""#)
+    );
+    assert_eq!(
+        a.diff_first_ignoring(&b, &["*.string"]).as_deref(),
+        Some(r#"field "nested.int32": 3 != 4"#)
+    );
+}
+
+#[test]
+fn similarity() {
+    let a = ComplexType {
+        string_map: HashMap::from_iter([(
+            "foo".to_owned(),
+            Scalars {
+                int32: 5,
+                ..Default::default()
+            },
+        )]),
+        nested: Some(Scalars {
+            int32: 3,
+            ..Default::default()
+        }),
+        my_enum: vec![0, 1],
+        ..Default::default()
+    };
+
+    let mut b = a.clone();
+    b.nested.as_mut().unwrap().int32 = 4;
+    let mut c = a.clone();
+    c.my_enum.push(3);
+    let mut d = a.clone();
+    d.string_map.get_mut("foo").unwrap().string = "bar".to_owned();
+
+    let a = a.transcode_to_dynamic();
+    let assert_similarity = |other: &ComplexType, expected: f64| {
+        let actual = a.similarity(&other.transcode_to_dynamic());
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    };
+
+    assert_eq!(a.similarity(&a), 1.0);
+    assert_eq!(
+        a.similarity(&Scalars::default().transcode_to_dynamic()),
+        0.0
+    );
+    assert_similarity(&b, (1.0 + 0.5 + 1.0) / 3.0);
+    assert_similarity(&c, (1.0 + 1.0 + 2.0 / 3.0) / 3.0);
+    assert_similarity(&d, (0.75 + 1.0 + 1.0) / 3.0);
+    assert_similarity(&ComplexType::default(), (0.0 + 0.5 + 0.0) / 3.0);
+}
+
+#[test]
+fn decode_allow_wire_type_mismatch() {
+    let desc = ComplexType::default().descriptor();
+    // `nested.int32` encoded with the 32-bit wire type, and `optional_enum` encoded as length-delimited.
+    let bytes: &[u8] = b"\x1a\x05\x1d\x01\x00\x00\x00\x2a\x01\x00";
+
+    assert!(DynamicMessage::decode(desc.clone(), bytes).is_err());
+
+    let options = DecodeOptions::new().allow_wire_type_mismatch(true);
+    let dynamic = DynamicMessage::decode_with_options(desc, bytes, &options).unwrap();
+    assert!(!dynamic.has_field_by_name("optional_enum"));
+    let nested = dynamic.get_field_by_name("nested").unwrap();
+    assert!(!nested.as_message().unwrap().has_field_by_name("int32"));
+    assert_eq!(dynamic.encode_to_vec(), bytes);
+}
+
+#[test]
+fn decode_strict_enums() {
+    let desc = ComplexType::default().descriptor();
+    let options = DecodeOptions::new().strict_enums(true);
+
+    // `optional_enum: 99`
+    let bytes: &[u8] = b"\x28\x63";
+    let dynamic = DynamicMessage::decode(desc.clone(), bytes).unwrap();
+    assert_eq!(
+        dynamic
+            .get_field_by_name("optional_enum")
+            .unwrap()
+            .as_enum_number(),
+        Some(99)
+    );
+    assert!(DynamicMessage::decode_with_options(desc.clone(), bytes, &options).is_err());
+
+    // `my_enum: [FOO, 99]`, packed
+    let bytes: &[u8] = b"\x22\x02\x01\x63";
+    assert!(DynamicMessage::decode(desc.clone(), bytes).is_ok());
+    assert!(DynamicMessage::decode_with_options(desc.clone(), bytes, &options).is_err());
+
+    // `my_enum: [FOO, BAR]`, `optional_enum: NEG`
+    let bytes: &[u8] = b"\x22\x02\x01\x03\x28\xfc\xff\xff\xff\xff\xff\xff\xff\xff\x01";
+    let dynamic = DynamicMessage::decode_with_options(desc, bytes, &options).unwrap();
+    assert_eq!(
+        dynamic
+            .get_field_by_name("optional_enum")
+            .unwrap()
+            .as_enum_number(),
+        Some(-4)
+    );
+}
+
+#[test]
+fn decode_invalid_utf8() {
+    let desc = Scalars::default().descriptor();
+
+    // `string: "a\xc3("`, where `(` is not a valid continuation byte
+    let bytes: &[u8] = b"\x72\x03a\xc3\x28";
+    let err = DynamicMessage::decode(desc.clone(), bytes).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "failed to decode Protobuf message: invalid string value: data is not UTF-8 encoded (invalid byte at offset 1)"
+    );
+
+    let options = DecodeOptions::new().validate_utf8(false);
+    let dynamic = DynamicMessage::decode_with_options(desc, bytes, &options).unwrap();
+    assert_eq!(
+        dynamic.get_field_by_name("string").unwrap().as_str(),
+        Some("a\u{fffd}(")
+    );
+
+    // `string_map: { "\xff": {} }`
+    let desc = ComplexType::default().descriptor();
+    let bytes: &[u8] = b"\x0a\x05\x0a\x01\xff\x12\x00";
+    assert!(DynamicMessage::decode(desc.clone(), bytes).is_err());
+    let dynamic = DynamicMessage::decode_with_options(desc, bytes, &options).unwrap();
+    assert!(dynamic
+        .get_field_by_name("string_map")
+        .unwrap()
+        .as_map()
+        .unwrap()
+        .contains_key(&MapKey::String("\u{fffd}".to_owned())));
+}
+
+#[test]
+fn decode_recursion_limit() {
+    fn nested_list_value(depth: usize) -> prost_types::Value {
+        let mut value = prost_types::Value::default();
+        for _ in 0..depth {
+            value = prost_types::Value {
+                kind: Some(prost_types::value::Kind::ListValue(
+                    prost_types::ListValue {
+                        values: vec![value],
+                    },
+                )),
+            };
+        }
+        value
+    }
+
+    let desc = test_file_descriptor()
+        .get_message_by_name("google.protobuf.Value")
+        .unwrap();
+    let options = DecodeOptions::new().recursion_limit(10);
+
+    // Each level contains a `ListValue` and a `Value` message.
+    let bytes = nested_list_value(5).encode_to_vec();
+    DynamicMessage::decode_with_options(desc.clone(), bytes.as_slice(), &options).unwrap();
+
+    let bytes = nested_list_value(6).encode_to_vec();
+    let err =
+        DynamicMessage::decode_with_options(desc.clone(), bytes.as_slice(), &options).unwrap_err();
+    assert!(
+        err.to_string().contains("recursion limit reached"),
+        "{}",
+        err
+    );
+
+    let bytes = nested_list_value(200).encode_to_vec();
+    let err = DynamicMessage::decode(desc.clone(), bytes.as_slice()).unwrap_err();
+    assert!(
+        err.to_string().contains("recursion limit reached"),
+        "{}",
+        err
+    );
+
+    // Limits above 100 are treated as 100.
+    let options = DecodeOptions::new().recursion_limit(1000);
+    let bytes = nested_list_value(50).encode_to_vec();
+    DynamicMessage::decode_with_options(desc.clone(), bytes.as_slice(), &options).unwrap();
+
+    let bytes = nested_list_value(51).encode_to_vec();
+    let err = DynamicMessage::decode_with_options(desc, bytes.as_slice(), &options).unwrap_err();
+    assert!(
+        err.to_string().contains("recursion limit reached"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn schema_registry() {
+    let mut registry = SchemaRegistry::new();
+    assert_eq!(registry.get_latest("scalars"), None);
+    assert_eq!(registry.versions("scalars").count(), 0);
+
+    assert_eq!(
+        registry.register("scalars", 2, Point::default().descriptor()),
+        None
+    );
+    assert_eq!(
+        registry.register("scalars", 2, Scalars::default().descriptor()),
+        Some(Point::default().descriptor())
+    );
+    registry.register("scalars", 1, ScalarArrays::default().descriptor());
+
+    assert_eq!(registry.versions("scalars").collect::<Vec<_>>(), vec![1, 2]);
+    assert_eq!(
+        registry.get_latest("scalars"),
+        Some((2, Scalars::default().descriptor()))
+    );
+
+    let dynamic = registry.decode("scalars", 2, b"\x18\x05".as_ref()).unwrap();
+    assert_eq!(
+        dynamic.transcode_to::<Scalars>().unwrap(),
+        Scalars {
+            int32: 5,
+            ..Default::default()
+        }
+    );
+
+    let err = registry.decode("scalars", 3, b"".as_ref()).unwrap_err();
+    assert!(err.to_string().contains("no schema registered"), "{}", err);
+}
+
+#[test]
+fn decode_trailing_bytes() {
+    let desc = Scalars::default().descriptor();
+
+    // `int32: 5`, `int64: 7`, followed by an unmatched end-group tag
+    let bytes: &[u8] = b"\x18\x05\x20\x07\x0c\x18\x09";
+    assert!(DynamicMessage::decode(desc.clone(), bytes).is_err());
+
+    let (dynamic, len) = DynamicMessage::decode_prefix(desc.clone(), bytes).unwrap();
+    assert_eq!(len, 4);
+    assert_eq!(
+        dynamic.transcode_to::<Scalars>().unwrap(),
+        Scalars {
+            int32: 5,
+            int64: 7,
+            ..Default::default()
+        }
+    );
+
+    let options = DecodeOptions::new().allow_trailing_bytes(true);
+    assert_eq!(
+        DynamicMessage::decode_with_options(desc.clone(), bytes, &options).unwrap(),
+        dynamic
+    );
+
+    // `int32: 5`, followed by a truncated length-delimited field
+    let (_, len) = DynamicMessage::decode_prefix(desc.clone(), b"\x18\x05\x2a\x05ab").unwrap();
+    assert_eq!(len, 2);
+
+    // Complete fields are still validated
+    assert!(DynamicMessage::decode_prefix(desc, b"\x18\x05\x1a\x00").is_err());
+}
+
+#[test]
+fn take_field_value() {
+    let mut dynamic = ComplexType {
+        nested: Some(Scalars {
+            int32: 3,
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let nested = dynamic.get_field_by_name_mut("nested").unwrap().take();
+    assert_eq!(
+        nested
+            .as_message()
+            .unwrap()
+            .get_field_by_name("int32")
+            .unwrap()
+            .as_i32(),
+        Some(3)
+    );
+
+    let placeholder = dynamic.get_field_by_name("nested").unwrap();
+    let placeholder = placeholder.as_message().unwrap();
+    assert_eq!(
+        placeholder.descriptor(),
+        nested.as_message().unwrap().descriptor()
+    );
+    assert!(!placeholder.has_field_by_name("int32"));
+}
+
+#[test]
+fn set_field_packed() {
+    let mut dynamic = ScalarArrays {
+        int32: vec![1, 2],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let field = dynamic.descriptor().get_field_by_name("int32").unwrap();
+    assert!(field.is_packed());
+    assert_eq!(dynamic.encode_to_vec(), b"\x1a\x02\x01\x02");
+
+    dynamic.set_field_packed(&field, false);
+    assert_eq!(dynamic.encoded_len(), 4);
+    assert_eq!(dynamic.encode_to_vec(), b"\x18\x01\x18\x02");
+
+    let decoded =
+        DynamicMessage::decode(dynamic.descriptor(), b"\x18\x01\x18\x02".as_ref()).unwrap();
+    assert_eq!(decoded.get_field(&field), dynamic.get_field(&field));
+    assert_ne!(decoded, dynamic);
+
+    dynamic.set_field(&field, Value::List(vec![Value::I32(3)]));
+    assert_eq!(dynamic.encode_to_vec(), b"\x18\x03");
+    dynamic
+        .clear_and_merge(b"\x1a\x02\x01\x02".as_ref())
+        .unwrap();
+    assert_eq!(dynamic.encode_to_vec(), b"\x18\x01\x18\x02");
+
+    dynamic.set_field_packed(&field, true);
+    assert_eq!(dynamic.encode_to_vec(), b"\x1a\x02\x01\x02");
+    assert_eq!(decoded, dynamic);
+
+    dynamic.set_field_packed(&field, false);
+    dynamic.clear_field(&field);
+    dynamic.set_field(&field, Value::List(vec![Value::I32(3)]));
+    assert_eq!(dynamic.encode_to_vec(), b"\x1a\x01\x03");
+
+    let mut unset = ScalarArrays::default().transcode_to_dynamic();
+    unset.set_field_packed(&field, false);
+    assert!(!unset.has_field(&field));
+    assert!(unset.encode_to_vec().is_empty());
+}
+
+#[test]
+fn transcode_to_with_report() {
+    let dynamic = 5_000_000_000i64.transcode_to_dynamic();
+    let (value, report) = dynamic.transcode_to_with_report::<i32>().unwrap();
+    assert_eq!(value, 705032704);
+    assert_eq!(
+        report.issues(),
+        &[TranscodeIssue::ValueOutOfRange {
+            path: "value".to_owned()
+        }]
+    );
+
+    let (_, report) = dynamic.transcode_to_with_report::<()>().unwrap();
+    assert_eq!(
+        report.issues(),
+        &[TranscodeIssue::FieldAbsent {
+            path: "value".to_owned()
+        }]
+    );
+
+    let mut dynamic = ComplexType {
+        nested: Some(Scalars {
+            int32: 3,
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let (_, report) = dynamic.transcode_to_with_report::<ComplexType>().unwrap();
+    assert!(report.is_empty());
+
+    dynamic.set_field_by_name("optional_enum", Value::EnumNumber(7));
+    let (value, report) = dynamic.transcode_to_with_report::<ComplexType>().unwrap();
+    assert_eq!(value.optional_enum, 7);
+    assert!(report.is_empty());
+
+    // Undefined values of closed enums are also preserved by prost
+    let mut dynamic = DefaultValues::default().transcode_to_dynamic();
+    dynamic.set_field_by_name("enum", Value::EnumNumber(7));
+    let (value, report) = dynamic.transcode_to_with_report::<DefaultValues>().unwrap();
+    assert_eq!(value.r#enum, Some(7));
+    assert!(report.is_empty());
+}
+
+#[test]
+fn encode_to_writer() {
+    let dynamic = ComplexType {
+        string_map: HashMap::from_iter([
+            (
+                "1".to_owned(),
+                Scalars {
+                    int32: 5,
+                    ..Default::default()
+                },
+            ),
+            ("2".to_owned(), Scalars::default()),
+        ]),
+        nested: Some(Scalars {
+            string: "foo".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![0, 1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let mut writer = Vec::new();
+    dynamic.encode_to_writer(&mut writer).unwrap();
+    assert_eq!(writer, dynamic.encode_to_vec());
+
+    let mut writer = Vec::new();
+    dynamic
+        .encode_length_delimited_to_writer(&mut writer)
+        .unwrap();
+    assert_eq!(writer, dynamic.encode_length_delimited_to_vec());
+}
+
+fn merge_from<T>(message: &T, other: &T) -> Result<(), TestCaseError>
+where
+    T: PartialEq + Debug + ReflectMessage + Clone + Default,
+{
+    let mut expected = message.clone();
+    expected.merge(other.encode_to_vec().as_slice()).unwrap();
+
+    let mut dynamic_message = message.transcode_to_dynamic();
+    dynamic_message
+        .merge_from(&other.transcode_to_dynamic())
+        .unwrap();
+    prop_assert_eq!(dynamic_message.transcode_to::<T>().unwrap(), expected);
+    Ok(())
+}
+
+fn clear_and_merge<T>(message: &T, other: &T) -> Result<(), TestCaseError>
+where
+    T: PartialEq + Debug + ReflectMessage + Default,
+{
+    let mut dynamic_message = message.transcode_to_dynamic();
+    dynamic_message
+        .clear_and_merge(other.encode_to_vec().as_slice())
+        .unwrap();
+    prop_assert_eq!(&dynamic_message.transcode_to::<T>().unwrap(), other);
+    prop_assert_eq!(&dynamic_message, &other.transcode_to_dynamic());
+
+    dynamic_message
+        .clear_and_merge(message.encode_to_vec().as_slice())
+        .unwrap();
+    prop_assert_eq!(&dynamic_message.transcode_to::<T>().unwrap(), message);
+    Ok(())
+}
+
+fn roundtrip<T>(message: &T) -> Result<(), TestCaseError>
+where
+    T: PartialEq + Debug + ReflectMessage + Default,
+{
+    let dynamic_message = message.transcode_to_dynamic();
+    let roundtripped_message: T = dynamic_message.transcode_to().unwrap();
+    prop_assert_eq!(message, &roundtripped_message);
+
+    // Check roundtripping through unknown fields works
+    let mut empty_message = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name("google.protobuf.Empty")
+            .unwrap(),
+    );
+    empty_message.transcode_from(message).unwrap();
+    let unknown_roundtripped_message: T = empty_message.transcode_to().unwrap();
+    prop_assert_eq!(
+        message,
+        &unknown_roundtripped_message,
+        "roundtrip through unknown fields failed"
+    );
+
+    // Check that transcoding to a new dynamic message is equivalent to just cloning it.
+    let mut duplicate_message = DynamicMessage::new(dynamic_message.descriptor());
+    duplicate_message.transcode_from(&dynamic_message).unwrap();
+    assert_eq!(dynamic_message, duplicate_message);
+
+    Ok(())
+}
+
+#[test]
+fn value_as_wrapper() {
+    let pool = test_file_descriptor();
+
+    let mut int32 = DynamicMessage::new(
+        pool.get_message_by_name("google.protobuf.Int32Value")
+            .unwrap(),
+    );
+    let value = Value::Message(int32.clone());
+    assert_eq!(value.as_wrapper_i32(), Some(0));
+
+    int32.set_field_by_name("value", Value::I32(5));
+    let value = Value::Message(int32);
+    assert_eq!(value.as_wrapper_i32(), Some(5));
+    assert_eq!(value.as_wrapper_i64(), None);
+    assert_eq!(value.as_wrapper_str(), None);
+
+    let mut string = DynamicMessage::new(
+        pool.get_message_by_name("google.protobuf.StringValue")
+            .unwrap(),
+    );
+    assert_eq!(Value::Message(string.clone()).as_wrapper_str(), Some(""));
+    string.set_field_by_name("value", Value::String("hello".to_owned()));
+    assert_eq!(Value::Message(string).as_wrapper_str(), Some("hello"));
+
+    let bytes = DynamicMessage::new(
+        pool.get_message_by_name("google.protobuf.BytesValue")
+            .unwrap(),
+    );
+    assert_eq!(
+        Value::Message(bytes).as_wrapper_bytes(),
+        Some(&Bytes::new())
+    );
+
+    assert_eq!(Value::I32(5).as_wrapper_i32(), None);
+}
+
+#[test]
+fn modify_list_in_place() {
+    let mut dynamic = DynamicMessage::new(ScalarArrays::default().descriptor());
+
+    dynamic
+        .get_field_by_name_mut("int32")
+        .and_then(Value::as_list_mut)
+        .unwrap()
+        .extend([Value::I32(1), Value::I32(2)]);
+    dynamic
+        .get_field_by_name_mut("int32")
+        .and_then(Value::as_list_mut)
+        .unwrap()
+        .push(Value::I32(3));
+
+    let field = dynamic.descriptor().get_field_by_name("int32").unwrap();
+    assert!(dynamic.get_field(&field).is_valid_for_field(&field));
+    assert_eq!(
+        dynamic.transcode_to::<ScalarArrays>().unwrap().int32,
+        vec![1, 2, 3]
+    );
+}
+
+#[test]
+fn message_builder() {
+    let nested = Scalars::default()
+        .descriptor()
+        .build()
+        .field("int32", Value::I32(3))
+        .field_by_number(14, Value::String("5".to_owned()))
+        .build()
+        .unwrap();
+    assert_eq!(
+        nested.transcode_to::<Scalars>().unwrap(),
+        Scalars {
+            int32: 3,
+            string: "5".to_owned(),
+            ..Default::default()
+        }
+    );
+
+    let oneof_desc = MessageWithOneof::default().descriptor();
+    let message = oneof_desc
+        .build()
+        .field("oneof_field_2", Value::I32(1))
+        .field("oneof_field_2", Value::I32(2))
+        .build()
+        .unwrap();
+    assert_eq!(
+        message.get_field_by_name("oneof_field_2").unwrap().as_i32(),
+        Some(2)
+    );
+    assert!(matches!(
+        oneof_desc
+            .build()
+            .field("oneof_field_1", Value::String("foo".to_owned()))
+            .field("oneof_field_2", Value::I32(2))
+            .build(),
+        Err(BuildMessageError::OneofConflict { first, second, .. })
+            if first.name() == "oneof_field_1" && second.name() == "oneof_field_2"
+    ));
+
+    let group_desc = contains_group::RequiredGroup::default().descriptor();
+    assert!(matches!(
+        group_desc.build().field_by_number(2, Value::I32(1)).build(),
+        Err(BuildMessageError::MissingRequired { field }) if field.name() == "a"
+    ));
+    assert_eq!(
+        group_desc
+            .build()
+            .field_by_number(100, Value::I32(1))
+            .build(),
+        Err(BuildMessageError::NumberNotFound { number: 100 })
+    );
+    assert_eq!(
+        group_desc.build().field("foo", Value::I32(1)).build(),
+        Err(BuildMessageError::SetField(SetFieldError::NotFound {
+            name: "foo".to_owned()
+        }))
+    );
+}
+
+#[test]
+fn push_field_and_insert_map_entry() {
+    let mut dynamic = DynamicMessage::new(ComplexType::default().descriptor());
+
+    dynamic.push_field("my_enum", Value::EnumNumber(1)).unwrap();
+    dynamic.push_field("my_enum", Value::EnumNumber(3)).unwrap();
+    assert!(matches!(
+        dynamic.push_field("my_enum", Value::I32(1)),
+        Err(SetFieldError::InvalidType { .. })
+    ));
+    assert!(matches!(
+        dynamic.push_field("optional_enum", Value::EnumNumber(1)),
+        Err(SetFieldError::InvalidType { .. })
+    ));
+    assert!(matches!(
+        dynamic.push_field("missing", Value::EnumNumber(1)),
+        Err(SetFieldError::NotFound { .. })
+    ));
+
+    let scalars = Scalars {
+        int32: 5,
+        ..Default::default()
+    };
+    dynamic
+        .insert_map_entry(
+            "int_map",
+            MapKey::I32(1),
+            Value::Message(scalars.transcode_to_dynamic()),
+        )
+        .unwrap();
+    assert!(matches!(
+        dynamic.insert_map_entry(
+            "int_map",
+            MapKey::String("1".to_owned()),
+            Value::Message(scalars.transcode_to_dynamic()),
+        ),
+        Err(SetFieldError::InvalidType {
+            value: Value::String(_),
+            ..
+        })
+    ));
+    assert!(matches!(
+        dynamic.insert_map_entry("int_map", MapKey::I32(2), Value::I32(5)),
+        Err(SetFieldError::InvalidType { .. })
+    ));
+    assert!(matches!(
+        dynamic.insert_map_entry("nested", MapKey::I32(2), Value::I32(5)),
+        Err(SetFieldError::InvalidType { .. })
+    ));
+
+    let message = dynamic.transcode_to::<ComplexType>().unwrap();
+    assert_eq!(message.my_enum, vec![1, 3]);
+    assert_eq!(message.int_map.len(), 1);
+    assert_eq!(message.int_map[&1], scalars);
+}
+
+#[test]
+fn encode_to_vec_verbose() {
+    let mut nested = DynamicMessage::new(Scalars::default().descriptor());
+    nested.set_field_by_name("int32", Value::I32(0));
+    nested.set_field_by_name("int64", Value::I64(5));
+
+    let mut dynamic = DynamicMessage::new(ComplexType::default().descriptor());
+    dynamic.set_field_by_name("nested", Value::Message(nested));
+    dynamic.set_field_by_name("optional_enum", Value::EnumNumber(0));
+    dynamic.set_field_by_name("my_enum", Value::List(vec![]));
+    dynamic.get_field_by_name_mut("string_map").unwrap();
+    let my_enum = dynamic.descriptor().get_field_by_name("my_enum").unwrap();
+    dynamic.set_field_packed(&my_enum, false);
+
+    let (bytes, report) = dynamic.encode_to_vec_verbose();
+    assert_eq!(bytes, dynamic.encode_to_vec());
+    assert_eq!(
+        report.skipped_default_fields(),
+        ["nested.int32", "optional_enum"]
+    );
+
+    let (_, report) =
+        DynamicMessage::new(ComplexType::default().descriptor()).encode_to_vec_verbose();
+    assert!(report.is_empty());
+}
+
+#[test]
+fn interpolate() {
+    let vars = HashMap::from_iter([
+        ("host".to_owned(), "localhost".to_owned()),
+        ("port".to_owned(), "8080".to_owned()),
+    ]);
+    let message = ComplexType {
+        nested: Some(Scalars {
+            string: "${host}:${port}".to_owned(),
+            ..Default::default()
+        }),
+        string_map: HashMap::from_iter([(
+            "${host}".to_owned(),
+            Scalars {
+                string: "$${host}, ${missing}, ${port".to_owned(),
+                ..Default::default()
+            },
+        )]),
+        ..Default::default()
+    };
+
+    let mut dynamic = message.transcode_to_dynamic();
+    dynamic.interpolate(&vars);
+    assert_eq!(
+        dynamic.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            nested: Some(Scalars {
+                string: "localhost:8080".to_owned(),
+                ..Default::default()
+            }),
+            string_map: HashMap::from_iter([(
+                "${host}".to_owned(),
+                Scalars {
+                    string: "$localhost, ${missing}, ${port".to_owned(),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        }
+    );
+
+    let err = message
+        .transcode_to_dynamic()
+        .try_interpolate(&vars)
+        .unwrap_err();
+    assert_eq!(err.variable(), "missing");
+    assert_eq!(err.to_string(), "variable 'missing' is not defined");
+}
+
+#[test]
+fn encode_forcing_presence() {
+    let mut dynamic = DynamicMessage::new(Scalars::default().descriptor());
+    dynamic.set_field_by_name("int32", Value::I32(0));
+    dynamic.set_field_by_name("string", Value::String("x".to_owned()));
+    assert_eq!(
+        dynamic.encode_forcing_presence(&[]),
+        dynamic.encode_to_vec()
+    );
+
+    // `double: 0`, `int32: 0`, `string: "x"`
+    let bytes = dynamic.encode_forcing_presence(&[3, 1, 99, 3, 14]);
+    assert_eq!(bytes, b"\x09\0\0\0\0\0\0\0\0\x18\x00\x72\x01x");
+    assert_eq!(
+        DynamicMessage::decode(Scalars::default().descriptor(), bytes.as_slice()).unwrap(),
+        dynamic
+    );
+
+    // Fields with presence and repeated fields are not affected
+    let dynamic = DynamicMessage::new(ComplexType::default().descriptor());
+    assert!(dynamic.encode_forcing_presence(&[1, 3, 4]).is_empty());
+}
+
+#[test]
+fn encode_proto3_optional_default_value() {
+    let desc = Proto3Optional::default().descriptor();
+    let explicit = desc.get_field_by_name("explicit").unwrap();
+    let implicit = desc.get_field_by_name("implicit").unwrap();
+    assert!(explicit.supports_presence());
+    assert!(!implicit.supports_presence());
+
+    let mut dynamic = DynamicMessage::new(desc.clone());
+    assert_eq!(dynamic.encode_to_vec(), b"");
+
+    dynamic.set_field(&explicit, Value::I32(0));
+    dynamic.set_field(&implicit, Value::I32(0));
+    assert!(dynamic.has_field(&explicit));
+    assert!(!dynamic.has_field(&implicit));
+    assert_eq!(dynamic.encode_to_vec(), b"\x08\x00");
+    assert_eq!(
+        dynamic.transcode_to::<Proto3Optional>().unwrap(),
+        Proto3Optional {
+            explicit: Some(0),
+            implicit: 0,
+        }
+    );
+
+    let decoded = DynamicMessage::decode(desc, b"\x08\x00".as_ref()).unwrap();
+    assert!(decoded.has_field(&explicit));
+    assert_eq!(decoded.get_field(&explicit).as_ref(), &Value::I32(0));
+
+    dynamic.clear_field(&explicit);
+    assert_eq!(dynamic.encode_to_vec(), b"");
+}
+
+#[test]
+fn value_map_sorted() {
+    let value = Value::Map(HashMap::from_iter([
+        (MapKey::I32(3), Value::I32(1)),
+        (MapKey::I32(-1), Value::I32(2)),
+        (MapKey::I32(2), Value::I32(3)),
+    ]));
+    assert_eq!(
+        value.map_sorted().unwrap(),
+        [
+            (&MapKey::I32(-1), &Value::I32(2)),
+            (&MapKey::I32(2), &Value::I32(3)),
+            (&MapKey::I32(3), &Value::I32(1)),
+        ]
+    );
+
+    let value = Value::Map(HashMap::from_iter([
+        (MapKey::String("b".to_owned()), Value::I32(1)),
+        (MapKey::String("B".to_owned()), Value::I32(2)),
+        (MapKey::String("a".to_owned()), Value::I32(3)),
+    ]));
+    let keys: Vec<_> = value
+        .map_sorted()
+        .unwrap()
+        .into_iter()
+        .map(|(key, _)| key.clone())
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            MapKey::String("B".to_owned()),
+            MapKey::String("a".to_owned()),
+            MapKey::String("b".to_owned()),
+        ]
+    );
+
+    assert!(MapKey::Bool(false) < MapKey::Bool(true));
+    assert_eq!(Value::I32(1).map_sorted(), None);
+}
+
+#[test]
+fn dyn_reflect_trait_object() {
+    let messages: Vec<Box<dyn DynReflect>> = vec![
+        Box::new(Point {
+            latitude: 1,
+            longitude: 2,
+        }),
+        Box::new(Scalars {
+            int32: 3,
+            ..Default::default()
+        }),
+    ];
+
+    let names: Vec<_> = messages
+        .iter()
+        .map(|message| message.descriptor().full_name().to_owned())
+        .collect();
+    assert_eq!(names, ["test.Point", "test.Scalars"]);
+
+    for message in &messages {
+        let dynamic = message.dyn_transcode_to_dynamic();
+        assert_eq!(dynamic.descriptor(), message.descriptor());
+        assert_eq!(dynamic.encode_to_vec(), message.dyn_encode_to_vec());
+    }
 }
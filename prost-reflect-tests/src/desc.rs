@@ -1,5 +1,8 @@
-use prost::Message;
-use prost_reflect::{DescriptorPool, Syntax, Value};
+use prost::{bytes::Bytes, Message};
+use prost_reflect::{
+    Cardinality, DescriptorPool, DynamicMessage, Kind, ReflectMessage, Syntax, Value,
+};
+use prost_types::FieldMask;
 
 use crate::{test_file_descriptor, DESCRIPTOR_POOL_BYTES};
 
@@ -28,8 +31,20 @@ fn test_descriptor_methods() {
         message_desc.reserved_names().collect::<Vec<_>>(),
         vec!["foo", "bar"]
     );
+    assert!(message_desc.is_reserved_number(2));
+    assert!(message_desc.is_reserved_number(11));
+    assert!(!message_desc.is_reserved_number(12));
+    assert!(!message_desc.is_reserved_number(1));
+    assert!(message_desc.is_reserved_name("foo"));
+    assert!(!message_desc.is_reserved_name("my_field"));
     assert_eq!(message_desc.extension_ranges().count(), 0,);
 
+    assert_eq!(message_desc.field_count(), 1);
+    assert!(message_desc.has_field(1));
+    assert!(!message_desc.has_field(2));
+    assert!(message_desc.has_field_by_name("my_field"));
+    assert!(!message_desc.has_field_by_name("foo"));
+
     let field_desc = message_desc.get_field_by_name("my_field").unwrap();
     assert_eq!(field_desc.name(), "my_field");
     assert_eq!(field_desc.full_name(), "my.package.MyMessage.my_field");
@@ -67,6 +82,14 @@ fn test_descriptor_methods() {
     let enum_value_desc = enum_desc.get_value_by_name("MY_VALUE").unwrap();
     assert_eq!(enum_value_desc.name(), "MY_VALUE");
     assert_eq!(enum_value_desc.full_name(), "my.package.MY_VALUE");
+    assert_eq!(enum_value_desc.to_string(), "MY_VALUE");
+    assert!(format!("{:?}", enum_value_desc).contains(r#"name: "MY_VALUE""#));
+    assert!(enum_desc.contains_number(0));
+    assert!(!enum_desc.contains_number(1));
+    assert_eq!(
+        test_file_descriptor().get_enum_value_by_name("my.package.MY_VALUE"),
+        Some(enum_value_desc)
+    );
 
     let nested_enum_desc = test_file_descriptor()
         .get_enum_by_name("my.package.MyMessage.MyNestedEnum")
@@ -86,6 +109,14 @@ fn test_descriptor_methods() {
     assert_eq!(service_desc.name(), "MyService");
     assert_eq!(service_desc.full_name(), "my.package.MyService");
     assert_eq!(service_desc.package_name(), "my.package");
+    assert_eq!(
+        test_file_descriptor().get_service(service_desc.index()),
+        Some(service_desc.clone())
+    );
+    assert_eq!(
+        test_file_descriptor().get_service(test_file_descriptor().service_count()),
+        None
+    );
 
     let method_desc = service_desc
         .methods()
@@ -93,6 +124,12 @@ fn test_descriptor_methods() {
         .unwrap();
     assert_eq!(method_desc.name(), "MyMethod");
     assert_eq!(method_desc.full_name(), "my.package.MyService.MyMethod");
+    assert_eq!(method_desc.full_path(), "/my.package.MyService/MyMethod");
+    assert_eq!(method_desc.new_request().descriptor(), method_desc.input());
+    assert_eq!(
+        method_desc.new_response().descriptor(),
+        method_desc.output()
+    );
 }
 
 #[test]
@@ -213,6 +250,10 @@ fn test_descriptor_names_no_package() {
     let enum_value_desc = enum_desc.get_value_by_name("MY_VALUE").unwrap();
     assert_eq!(enum_value_desc.name(), "MY_VALUE");
     assert_eq!(enum_value_desc.full_name(), "MY_VALUE");
+    assert_eq!(
+        test_file_descriptor().get_enum_value_by_name("MY_VALUE"),
+        Some(enum_value_desc)
+    );
 
     let nested_enum_desc = test_file_descriptor()
         .get_enum_by_name("MyMessage.MyNestedEnum")
@@ -236,6 +277,12 @@ fn test_descriptor_names_no_package() {
         .unwrap();
     assert_eq!(method_desc.name(), "MyMethod");
     assert_eq!(method_desc.full_name(), "MyService.MyMethod");
+    assert_eq!(method_desc.full_path(), "/MyService/MyMethod");
+    assert_eq!(method_desc.new_request().descriptor(), method_desc.input());
+    assert_eq!(
+        method_desc.new_response().descriptor(),
+        method_desc.output()
+    );
 }
 
 #[test]
@@ -447,3 +494,176 @@ fn test_get_extension() {
         &Value::U32(1)
     );
 }
+
+#[test]
+fn test_validate_field_mask() {
+    let message_desc = test_file_descriptor()
+        .get_message_by_name("test.ComplexType")
+        .unwrap();
+
+    let mask = FieldMask {
+        paths: vec!["nested.int32".to_owned(), "my_enum".to_owned()],
+    };
+    assert_eq!(message_desc.validate_field_mask(&mask), Ok(()));
+
+    let mask = FieldMask {
+        paths: vec![
+            "nested".to_owned(),
+            "nested.nope".to_owned(),
+            "nope".to_owned(),
+            "nested.int32.foo".to_owned(),
+            "string_map.int32".to_owned(),
+            "".to_owned(),
+        ],
+    };
+    assert_eq!(
+        message_desc.validate_field_mask(&mask),
+        Err(vec![
+            "nested.nope".to_owned(),
+            "nope".to_owned(),
+            "nested.int32.foo".to_owned(),
+            "string_map.int32".to_owned(),
+            "".to_owned(),
+        ])
+    );
+}
+
+#[test]
+fn test_map_kinds() {
+    let message_desc = test_file_descriptor()
+        .get_message_by_name("test.ComplexType")
+        .unwrap();
+    let scalars_desc = test_file_descriptor()
+        .get_message_by_name("test.Scalars")
+        .unwrap();
+
+    let int_map = message_desc.get_field_by_name("int_map").unwrap();
+    assert_eq!(int_map.map_key_kind(), Some(Kind::Int32));
+    assert_eq!(
+        int_map.map_value_kind(),
+        Some(Kind::Message(scalars_desc.clone()))
+    );
+    assert!(int_map.is_map_value_message());
+    let int_map_entry = int_map.map_entry_descriptor().unwrap();
+    assert!(int_map_entry.is_map_entry());
+    assert_eq!(int_map_entry.map_entry_key_field().kind(), Kind::Int32);
+
+    let string_map = message_desc.get_field_by_name("string_map").unwrap();
+    assert_eq!(string_map.map_key_kind(), Some(Kind::String));
+    assert_eq!(
+        string_map.map_value_kind(),
+        Some(Kind::Message(scalars_desc))
+    );
+
+    let nested = message_desc.get_field_by_name("nested").unwrap();
+    assert_eq!(nested.map_key_kind(), None);
+    assert_eq!(nested.map_value_kind(), None);
+    assert!(!nested.is_map_value_message());
+    assert_eq!(nested.map_entry_descriptor(), None);
+
+    let my_enum = message_desc.get_field_by_name("my_enum").unwrap();
+    assert_eq!(my_enum.map_key_kind(), None);
+    assert_eq!(my_enum.map_value_kind(), None);
+}
+
+#[test]
+fn test_kind_default_value() {
+    let message_desc = test_file_descriptor()
+        .get_message_by_name("test.ComplexType")
+        .unwrap();
+
+    assert_eq!(Kind::Int32.default_value(), Value::I32(0));
+    assert_eq!(Kind::Fixed64.default_value(), Value::U64(0));
+    assert_eq!(Kind::Double.default_value(), Value::F64(0.0));
+    assert_eq!(Kind::String.default_value(), Value::String(String::new()));
+    assert_eq!(Kind::Bool.default_value(), Value::Bool(false));
+
+    let optional_enum = message_desc.get_field_by_name("optional_enum").unwrap();
+    assert_eq!(optional_enum.kind().default_value(), Value::EnumNumber(0));
+
+    let my_enum = message_desc.get_field_by_name("my_enum").unwrap();
+    assert_eq!(my_enum.kind().default_value(), Value::EnumNumber(0));
+
+    let string_map = message_desc.get_field_by_name("string_map").unwrap();
+    let scalars_kind = string_map.map_value_kind().unwrap();
+    assert_eq!(
+        scalars_kind.default_value(),
+        Value::Message(DynamicMessage::new(
+            scalars_kind.as_message().unwrap().clone()
+        ))
+    );
+}
+
+#[test]
+fn test_field_default_value() {
+    let message = test_file_descriptor()
+        .get_message_by_name("test2.DefaultValues")
+        .unwrap();
+    let default_value = |name: &str| {
+        message
+            .get_field_by_name(name)
+            .unwrap()
+            .default_value()
+            .cloned()
+    };
+
+    assert_eq!(default_value("int32"), Some(Value::I32(-3)));
+    assert_eq!(default_value("bool"), Some(Value::Bool(true)));
+    assert_eq!(
+        default_value("string"),
+        Some(Value::String("hello".to_owned()))
+    );
+    assert_eq!(
+        default_value("bytes"),
+        Some(Value::Bytes(Bytes::from_static(
+            b"\0\x01\x07\x08\x0C\n\r\t\x0B\\\'\"\xFE"
+        )))
+    );
+    assert_eq!(default_value("defaulted_enum"), Some(Value::EnumNumber(3)));
+    assert_eq!(default_value("enum"), None);
+
+    let message = test_file_descriptor()
+        .get_message_by_name("test.Scalars")
+        .unwrap();
+    assert_eq!(
+        message.get_field_by_name("int32").unwrap().default_value(),
+        None
+    );
+}
+
+#[test]
+fn test_kind_and_cardinality_names() {
+    let pool = test_file_descriptor();
+    let message_desc = pool.get_message_by_name("my.package.MyMessage").unwrap();
+    let field_desc = message_desc.get_field_by_name("my_field").unwrap();
+    assert_eq!(field_desc.cardinality().to_string(), "optional");
+
+    for kind in [
+        Kind::Int32,
+        Kind::Bytes,
+        Kind::Message(message_desc.clone()),
+    ] {
+        assert_eq!(pool.get_kind_by_name(&kind.to_string()), Some(kind));
+    }
+    assert_eq!(
+        Kind::Message(message_desc).to_string(),
+        "my.package.MyMessage"
+    );
+    assert!(matches!(
+        pool.get_kind_by_name("test.EnumWithAlias"),
+        Some(Kind::Enum(_))
+    ));
+    assert_eq!(pool.get_kind_by_name("int33"), None);
+
+    for cardinality in [
+        Cardinality::Optional,
+        Cardinality::Required,
+        Cardinality::Repeated,
+    ] {
+        assert_eq!(cardinality.to_string().parse(), Ok(cardinality));
+    }
+    assert_eq!(
+        "singular".parse::<Cardinality>().unwrap_err().to_string(),
+        "'singular' is not a valid cardinality, expected 'optional', 'required' or 'repeated'"
+    );
+}
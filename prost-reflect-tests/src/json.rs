@@ -6,14 +6,16 @@ use std::{
 
 use proptest::{prelude::*, test_runner::TestCaseError};
 use prost::Message;
-use prost_reflect::{DeserializeOptions, DynamicMessage, ReflectMessage, SerializeOptions};
+use prost_reflect::{
+    DescriptorPool, DeserializeOptions, DynamicMessage, ReflectMessage, SerializeOptions,
+};
 use prost_types::FileDescriptorSet;
 use serde_json::json;
 
 use crate::{
     arbitrary, contains_group, message_with_oneof, test_file_descriptor, ComplexType,
-    ContainsGroup, MessageWithAliasedEnum, MessageWithOneof, Point, ScalarArrays, Scalars,
-    WellKnownTypes,
+    ContainsGroup, MessageWithAliasedEnum, MessageWithOneof, Point, Proto3Optional, ScalarArrays,
+    Scalars, WellKnownTypes,
 };
 
 #[test]
@@ -330,6 +332,43 @@ fn serialize_no_stringify_64_bit_integers() {
     );
 }
 
+#[test]
+fn serialize_int64_as_number() {
+    let value = to_json_with_options(
+        &Scalars {
+            int64: -(1 << 53) + 1,
+            uint64: 1 << 53,
+            sint64: -(1 << 53),
+            fixed64: 10,
+            sfixed64: i64::MIN,
+            ..Default::default()
+        },
+        &SerializeOptions::new().int64_as_number(true),
+    );
+
+    assert_eq!(
+        value,
+        json!({
+            "int64": -9007199254740991i64,
+            "uint64": "9007199254740992",
+            "sint64": "-9007199254740992",
+            "fixed64": 10,
+            "sfixed64": "-9223372036854775808",
+        })
+    );
+
+    let value = to_json_with_options(
+        &WellKnownTypes {
+            int64: Some(-2),
+            uint64: Some(u64::MAX),
+            ..Default::default()
+        },
+        &SerializeOptions::new().int64_as_number(true),
+    );
+    assert_eq!(value["int64"], json!(-2));
+    assert_eq!(value["uint64"], json!("18446744073709551615"));
+}
+
 #[test]
 fn serialize_use_proto_field_name() {
     let value = to_json_with_options(
@@ -995,6 +1034,48 @@ fn deserialize_any_allow_unknown_fields() {
     );
 }
 
+#[test]
+#[should_panic(expected = "expected '@type' field")]
+fn deserialize_any_missing_type() {
+    from_json::<prost_types::Any>(
+        json!({
+            "latitude": 1,
+        }),
+        "google.protobuf.Any",
+    );
+}
+
+#[test]
+#[should_panic(expected = "expected 'value' field")]
+fn deserialize_any_wkt_missing_value() {
+    from_json::<prost_types::Any>(
+        json!({
+            "@type": "type.googleapis.com/google.protobuf.Int32Value",
+        }),
+        "google.protobuf.Any",
+    );
+}
+
+#[test]
+fn deserialize_any_type_resolver() {
+    let json = r#"{ "@type": "type.googleapis.com/test.Point", "latitude": 1 }"#;
+
+    let value: prost_types::Any = from_json_string_with_options(
+        json,
+        "google.protobuf.Any",
+        &DeserializeOptions::new().type_resolver(test_file_descriptor()),
+    );
+    assert_eq!(value.type_url, "type.googleapis.com/test.Point");
+
+    let err = try_from_json_string_with_options(
+        json,
+        "google.protobuf.Any",
+        &DeserializeOptions::new().type_resolver(DescriptorPool::new()),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("message 'test.Point' not found"));
+}
+
 #[test]
 fn deserialize_duration_fraction_digits() {
     let value: prost_types::Duration = from_json(json!("1.00034s"), "google.protobuf.Duration");
@@ -1127,6 +1208,26 @@ fn value_null_in_oneof() {
     );
 }
 
+#[test]
+fn null_unsets_field() {
+    let json = json!({ "oneofField2": null });
+    let value: MessageWithOneof = from_json(json, "test.MessageWithOneof");
+    assert_eq!(value.test_oneof, None);
+
+    let json = json!({ "explicit": null, "implicit": null });
+    let value: Proto3Optional = from_json(json, "test.Proto3Optional");
+    assert_eq!(value, Proto3Optional::default());
+
+    let json = json!({
+        "struct": null,
+        "int32": null,
+        "list": null,
+        "null": null,
+    });
+    let value: WellKnownTypes = from_json(json, "test.WellKnownTypes");
+    assert_eq!(value, WellKnownTypes::default());
+}
+
 #[test]
 fn null_old_format() {
     let json = json!({ "null": "NULL_VALUE" });
@@ -1149,6 +1250,41 @@ fn float_out_of_range() {
     let _: Scalars = from_json(json, "test.Scalars");
 }
 
+#[test]
+fn roundtrip_float_extrema() {
+    for (float, double) in [
+        (f32::MAX, f64::MAX),
+        (f32::MIN, f64::MIN),
+        (f32::MIN_POSITIVE, f64::MIN_POSITIVE),
+        (1e-45, 5e-324),
+        (2.2, 1.1),
+    ] {
+        roundtrip_json(&Scalars {
+            float,
+            double,
+            ..Default::default()
+        })
+        .unwrap();
+    }
+}
+
+#[test]
+fn descriptor_pool_json_roundtrip() {
+    let pool = test_file_descriptor();
+
+    let json = pool.encode_json(serde_json::value::Serializer).unwrap();
+    assert_eq!(json["file"][0]["name"], pool.files().next().unwrap().name());
+
+    let decoded = DescriptorPool::decode_json(json).unwrap();
+    assert_eq!(decoded.file_descriptor_set(), pool.file_descriptor_set());
+
+    let err = DescriptorPool::decode_json(
+        json!({ "file": [{ "name": "a.proto", "dependency": ["b.proto"] }] }),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("b.proto"), "{}", err);
+}
+
 #[test]
 fn bytes_forgiving_decode() {
     let json = json!({ "bytes": "-_" });
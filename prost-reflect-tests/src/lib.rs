@@ -4,6 +4,8 @@ use prost_reflect::DescriptorPool;
 #[cfg(test)]
 mod arbitrary;
 #[cfg(test)]
+mod conformance;
+#[cfg(test)]
 mod decode;
 #[cfg(test)]
 mod desc;
@@ -4,7 +4,7 @@ use std::{
 };
 
 use prost::Message;
-use prost_reflect::{DynamicMessage, ReflectMessage};
+use prost_reflect::{DynamicMessage, ReflectMessage, Value};
 
 use crate::{
     contains_group, test_file_descriptor, ComplexType, ContainsGroup, Point, ScalarArrays, Scalars,
@@ -297,6 +297,26 @@ fn any_invalid_bytes() {
     );
 }
 
+#[test]
+fn extension() {
+    let message_desc = test_file_descriptor()
+        .get_message_by_name("my.package2.MyMessage")
+        .unwrap();
+
+    let mut value = DynamicMessage::new(message_desc.clone());
+    value.set_extension(&message_desc.get_extension(111).unwrap(), Value::I32(5));
+    value.set_extension(&message_desc.get_extension(113).unwrap(), Value::F64(1.5));
+
+    assert_eq!(
+        value.to_string(),
+        "[my.package2.MyMessage.in_extendee]:5,[my.package2.OtherMessage.in_other]:1.5"
+    );
+    assert_eq!(
+        value.to_string_pretty(),
+        "[my.package2.MyMessage.in_extendee]: 5\n[my.package2.OtherMessage.in_other]: 1.5"
+    );
+}
+
 #[test]
 fn group() {
     let value = ContainsGroup {
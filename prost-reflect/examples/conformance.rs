@@ -0,0 +1,43 @@
+//! A testee program for the protobuf conformance test runner.
+//!
+//! The program takes the path of a file descriptor set containing the conformance test messages
+//! as its only argument. This can be generated with
+//!
+//! ```text
+//! protoc --include_imports -o test_messages.bin \
+//!     google/protobuf/test_messages_proto2.proto google/protobuf/test_messages_proto3.proto
+//! ```
+//!
+//! Since the conformance test runner starts the testee without any arguments, it should be passed
+//! a wrapper script such as
+//!
+//! ```text
+//! #!/bin/sh
+//! exec target/debug/examples/conformance test_messages.bin
+//! ```
+
+use std::{env, fs, io, process};
+
+use prost_reflect::DescriptorPool;
+
+fn main() -> io::Result<()> {
+    let path = match env::args_os().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: conformance <file descriptor set>");
+            process::exit(2);
+        }
+    };
+
+    let pool = match DescriptorPool::decode(fs::read(path)?.as_slice()) {
+        Ok(pool) => pool,
+        Err(error) => {
+            eprintln!("invalid file descriptor set: {}", error);
+            process::exit(1);
+        }
+    };
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    prost_reflect::conformance::run(&pool, stdin.lock(), stdout.lock())
+}
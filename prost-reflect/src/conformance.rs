@@ -0,0 +1,277 @@
+//! Support for running the protobuf [conformance test suite] against [`DynamicMessage`].
+//!
+//! The conformance test runner starts a testee program and sends it a sequence of
+//! [`ConformanceRequest`]s on stdin, each prefixed with its length as a little-endian `u32`. The
+//! testee must write a length-prefixed [`ConformanceResponse`] to stdout for each request. The
+//! [`run`] function implements this protocol, using the message types of a [`DescriptorPool`]
+//! which should contain the test messages from `google/protobuf/test_messages_proto2.proto` and
+//! `google/protobuf/test_messages_proto3.proto`.
+//!
+//! See `examples/conformance.rs` for a complete testee program.
+//!
+//! [conformance test suite]: https://github.com/protocolbuffers/protobuf/tree/main/conformance
+
+use std::io::{self, Read, Write};
+
+use prost::{Enumeration, Message};
+
+use crate::{DescriptorPool, DeserializeOptions, DynamicMessage};
+
+/// The format of a payload in a conformance request or response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+#[repr(i32)]
+pub enum WireFormat {
+    /// No format was specified.
+    Unspecified = 0,
+    /// The protobuf binary format.
+    Protobuf = 1,
+    /// The protobuf JSON mapping.
+    Json = 2,
+    /// The JSPB format, which is not supported.
+    Jspb = 3,
+    /// The protobuf text format.
+    TextFormat = 4,
+}
+
+/// The category of a conformance test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+#[repr(i32)]
+pub enum TestCategory {
+    /// No category was specified.
+    UnspecifiedTest = 0,
+    /// A test of the protobuf binary format.
+    BinaryTest = 1,
+    /// A test of the protobuf JSON mapping.
+    JsonTest = 2,
+    /// A test of the protobuf JSON mapping, in which unknown fields should be ignored when parsing.
+    JsonIgnoreUnknownParsingTest = 3,
+    /// A test of the JSPB format, which is not supported.
+    JspbTest = 4,
+    /// A test of the protobuf text format.
+    TextFormatTest = 5,
+}
+
+/// The list of tests which the testee is expected to fail.
+#[derive(Clone, PartialEq, Message)]
+pub struct FailureSet {
+    /// The names of the failing tests.
+    #[prost(string, repeated, tag = "1")]
+    pub failure: Vec<String>,
+}
+
+/// A request from the conformance test runner to decode a payload and re-encode it in another
+/// format.
+#[derive(Clone, PartialEq, Message)]
+pub struct ConformanceRequest {
+    /// The payload to decode.
+    #[prost(oneof = "conformance_request::Payload", tags = "1, 2, 7, 8")]
+    pub payload: Option<conformance_request::Payload>,
+    /// The format in which the decoded message should be returned.
+    #[prost(enumeration = "WireFormat", tag = "3")]
+    pub requested_output_format: i32,
+    /// The full name of the message type of the payload.
+    #[prost(string, tag = "4")]
+    pub message_type: String,
+    /// The category of the test, which may affect how the payload should be decoded.
+    #[prost(enumeration = "TestCategory", tag = "5")]
+    pub test_category: i32,
+    /// Options for the JSPB format, which is not supported.
+    #[prost(message, optional, tag = "6")]
+    pub jspb_encoding_options: Option<JspbEncodingConfig>,
+    /// Whether unknown fields should be included in text format output.
+    #[prost(bool, tag = "9")]
+    pub print_unknown_fields: bool,
+}
+
+/// Nested types of [`ConformanceRequest`].
+pub mod conformance_request {
+    use prost::Oneof;
+
+    /// The payload of a conformance request.
+    #[derive(Clone, PartialEq, Oneof)]
+    pub enum Payload {
+        /// A payload in the protobuf binary format.
+        #[prost(bytes, tag = "1")]
+        ProtobufPayload(Vec<u8>),
+        /// A payload in the protobuf JSON mapping.
+        #[prost(string, tag = "2")]
+        JsonPayload(String),
+        /// A payload in the JSPB format, which is not supported.
+        #[prost(string, tag = "7")]
+        JspbPayload(String),
+        /// A payload in the protobuf text format.
+        #[prost(string, tag = "8")]
+        TextPayload(String),
+    }
+}
+
+/// The response sent to the conformance test runner for a [`ConformanceRequest`].
+#[derive(Clone, PartialEq, Message)]
+pub struct ConformanceResponse {
+    /// The outcome of the request.
+    #[prost(
+        oneof = "conformance_response::Result",
+        tags = "1, 6, 2, 3, 4, 5, 7, 8"
+    )]
+    pub result: Option<conformance_response::Result>,
+}
+
+/// Nested types of [`ConformanceResponse`].
+pub mod conformance_response {
+    use prost::Oneof;
+
+    /// The outcome of a conformance request.
+    #[derive(Clone, PartialEq, Oneof)]
+    pub enum Result {
+        /// The payload could not be decoded.
+        #[prost(string, tag = "1")]
+        ParseError(String),
+        /// The payload was decoded, but could not be encoded in the requested output format.
+        #[prost(string, tag = "6")]
+        SerializeError(String),
+        /// An unexpected error occurred.
+        #[prost(string, tag = "2")]
+        RuntimeError(String),
+        /// The message, encoded in the protobuf binary format.
+        #[prost(bytes, tag = "3")]
+        ProtobufPayload(Vec<u8>),
+        /// The message, encoded in the protobuf JSON mapping.
+        #[prost(string, tag = "4")]
+        JsonPayload(String),
+        /// The test was skipped because the testee does not support it.
+        #[prost(string, tag = "5")]
+        Skipped(String),
+        /// The message, encoded in the JSPB format.
+        #[prost(string, tag = "7")]
+        JspbPayload(String),
+        /// The message, encoded in the protobuf text format.
+        #[prost(string, tag = "8")]
+        TextPayload(String),
+    }
+}
+
+/// Options for the JSPB format, which is not supported.
+#[derive(Clone, PartialEq, Message)]
+pub struct JspbEncodingConfig {
+    /// Whether `Any` messages should use the array format.
+    #[prost(bool, tag = "1")]
+    pub use_jspb_array_any_format: bool,
+}
+
+/// Reads conformance requests from `input` and writes the responses to `output`, until the end of
+/// the input is reached.
+///
+/// Each request and response is prefixed with its length as a little-endian `u32`, as expected
+/// by the conformance test runner. Message types are looked up in `pool`.
+pub fn run<R, W>(pool: &DescriptorPool, mut input: R, mut output: W) -> io::Result<()>
+where
+    R: Read,
+    W: Write,
+{
+    loop {
+        let mut len = [0; 4];
+        if input.read_exact(&mut len).is_err() {
+            // No more test cases.
+            return Ok(());
+        }
+
+        let mut bytes = vec![0; u32::from_le_bytes(len) as usize];
+        input.read_exact(&mut bytes)?;
+
+        let response = handle_request_bytes(pool, &bytes).encode_to_vec();
+        output.write_all(&(response.len() as u32).to_le_bytes())?;
+        output.write_all(&response)?;
+        output.flush()?;
+    }
+}
+
+/// Decodes a conformance request from its protobuf byte representation, and returns the response
+/// to send back to the conformance test runner.
+///
+/// See [`handle_request`].
+pub fn handle_request_bytes(pool: &DescriptorPool, bytes: &[u8]) -> ConformanceResponse {
+    match ConformanceRequest::decode(bytes) {
+        Ok(request) => handle_request(pool, request),
+        Err(error) => ConformanceResponse {
+            result: Some(conformance_response::Result::ParseError(error.to_string())),
+        },
+    }
+}
+
+/// Performs the decoding and encoding described by a conformance request, using the message types
+/// defined in `pool`.
+///
+/// Payloads in the protobuf binary format and the JSON mapping are supported. Messages may also be
+/// output in the text format, but text format and JSPB payloads are reported as skipped.
+pub fn handle_request(pool: &DescriptorPool, request: ConformanceRequest) -> ConformanceResponse {
+    ConformanceResponse {
+        result: Some(request_result(pool, request)),
+    }
+}
+
+fn request_result(
+    pool: &DescriptorPool,
+    request: ConformanceRequest,
+) -> conformance_response::Result {
+    use conformance_request::Payload;
+    use conformance_response::Result;
+
+    let message_desc = match pool.get_message_by_name(&request.message_type) {
+        Some(message_desc) => message_desc,
+        None => {
+            return Result::ParseError(format!("unknown message type: {}", request.message_type))
+        }
+    };
+
+    let mut json_deserialize_options = DeserializeOptions::new();
+    match request.test_category() {
+        TestCategory::UnspecifiedTest
+        | TestCategory::BinaryTest
+        | TestCategory::JsonTest
+        | TestCategory::TextFormatTest => (),
+        TestCategory::JsonIgnoreUnknownParsingTest => {
+            json_deserialize_options = json_deserialize_options.deny_unknown_fields(false);
+        }
+        TestCategory::JspbTest => return Result::Skipped("unsupported test category".to_owned()),
+    }
+
+    let output = request.requested_output_format();
+    let dynamic_message = match request.payload {
+        None => return Result::ParseError("no payload".to_owned()),
+        Some(Payload::ProtobufPayload(buf)) => {
+            let mut dynamic_message = DynamicMessage::new(message_desc);
+            if let Err(error) = dynamic_message.merge(buf.as_slice()) {
+                return Result::ParseError(error.to_string());
+            }
+            dynamic_message
+        }
+        Some(Payload::JsonPayload(json)) => {
+            let mut deserializer = serde_json::de::Deserializer::from_str(&json);
+            match DynamicMessage::deserialize_with_options(
+                message_desc,
+                &mut deserializer,
+                &json_deserialize_options,
+            ) {
+                Ok(message) => message,
+                Err(error) => return Result::ParseError(error.to_string()),
+            }
+        }
+        Some(Payload::JspbPayload(_)) => {
+            return Result::Skipped("JSPB payload is not supported".to_owned())
+        }
+        Some(Payload::TextPayload(_)) => {
+            return Result::Skipped("text format payload is not supported".to_owned())
+        }
+    };
+
+    match output {
+        WireFormat::Unspecified => Result::ParseError("output format unspecified".to_owned()),
+        WireFormat::Jspb => Result::Skipped("JSPB output is not supported".to_owned()),
+        WireFormat::TextFormat => Result::TextPayload(dynamic_message.to_string()),
+        WireFormat::Json => match serde_json::to_string(&dynamic_message) {
+            Ok(json) => Result::JsonPayload(json),
+            Err(error) => Result::SerializeError(error.to_string()),
+        },
+        WireFormat::Protobuf => Result::ProtobufPayload(dynamic_message.encode_to_vec()),
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use prost_types::{source_code_info::Location, FileDescriptorProto};
+
+use crate::descriptor::{
+    EnumDescriptor, EnumValueDescriptor, ExtensionDescriptor, FieldDescriptor, FileDescriptor,
+    MessageDescriptor, MethodDescriptor, OneofDescriptor, ServiceDescriptor,
+};
+
+// Field numbers of the definitions in `FileDescriptorProto` and nested messages, used to build
+// source code info paths.
+const FILE_MESSAGE_TYPE: i32 = 4;
+const FILE_ENUM_TYPE: i32 = 5;
+const FILE_SERVICE: i32 = 6;
+const FILE_EXTENSION: i32 = 7;
+const MESSAGE_FIELD: i32 = 2;
+const MESSAGE_NESTED_TYPE: i32 = 3;
+const MESSAGE_ENUM_TYPE: i32 = 4;
+const MESSAGE_EXTENSION: i32 = 6;
+const MESSAGE_ONEOF_DECL: i32 = 8;
+const ENUM_VALUE: i32 = 2;
+const SERVICE_METHOD: i32 = 2;
+
+/// The comments attached to a definition in a `.proto` source file.
+///
+/// Comments are only available if the file descriptor was generated with source code info, for
+/// example by passing `--include_source_info` to the protobuf compiler.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Comments {
+    leading: Option<String>,
+    trailing: Option<String>,
+    leading_detached: Vec<String>,
+}
+
+impl Comments {
+    fn from_location(location: &Location) -> Self {
+        Comments {
+            leading: location.leading_comments.clone(),
+            trailing: location.trailing_comments.clone(),
+            leading_detached: location.leading_detached_comments.clone(),
+        }
+    }
+
+    /// Gets the comment immediately preceding the definition, if any.
+    pub fn leading(&self) -> Option<&str> {
+        self.leading.as_deref()
+    }
+
+    /// Gets the comment immediately following the definition, if any.
+    pub fn trailing(&self) -> Option<&str> {
+        self.trailing.as_deref()
+    }
+
+    /// Gets the comments preceding the definition which are separated from it by a blank line.
+    pub fn leading_detached(&self) -> &[String] {
+        &self.leading_detached
+    }
+}
+
+impl MessageDescriptor {
+    /// Gets the comments attached to this message in its source file.
+    ///
+    /// Returns `None` if the file has no source code info for this message.
+    pub fn comments(&self) -> Option<Comments> {
+        find_comments(&self.parent_file(), &message_path(self))
+    }
+}
+
+impl FieldDescriptor {
+    /// Gets the comments attached to this field in its source file.
+    ///
+    /// Returns `None` if the file has no source code info for this field.
+    pub fn comments(&self) -> Option<Comments> {
+        let message = self.parent_message();
+        let mut path = message_path(message);
+        path.push(MESSAGE_FIELD);
+        path.push(self.proto_index() as i32);
+        find_comments(&message.parent_file(), &path)
+    }
+}
+
+impl OneofDescriptor {
+    /// Gets the comments attached to this oneof in its source file.
+    ///
+    /// Returns `None` if the file has no source code info for this oneof.
+    pub fn comments(&self) -> Option<Comments> {
+        let message = self.parent_message();
+        let mut path = message_path(message);
+        path.push(MESSAGE_ONEOF_DECL);
+        path.push(self.proto_index() as i32);
+        find_comments(&message.parent_file(), &path)
+    }
+}
+
+impl ExtensionDescriptor {
+    /// Gets the comments attached to this extension in its source file.
+    ///
+    /// Returns `None` if the file has no source code info for this extension.
+    pub fn comments(&self) -> Option<Comments> {
+        let mut path = match self.parent_message() {
+            Some(message) => {
+                let mut path = message_path(&message);
+                path.push(MESSAGE_EXTENSION);
+                path
+            }
+            None => vec![FILE_EXTENSION],
+        };
+        path.push(self.proto_index() as i32);
+        find_comments(&self.parent_file(), &path)
+    }
+}
+
+impl EnumDescriptor {
+    /// Gets the comments attached to this enum in its source file.
+    ///
+    /// Returns `None` if the file has no source code info for this enum.
+    pub fn comments(&self) -> Option<Comments> {
+        find_comments(&self.parent_file(), &enum_path(self))
+    }
+}
+
+impl EnumValueDescriptor {
+    /// Gets the comments attached to this enum value in its source file.
+    ///
+    /// Returns `None` if the file has no source code info for this enum value.
+    pub fn comments(&self) -> Option<Comments> {
+        let enum_ty = self.parent_enum();
+        let mut path = enum_path(enum_ty);
+        path.push(ENUM_VALUE);
+        path.push(self.proto_index() as i32);
+        find_comments(&enum_ty.parent_file(), &path)
+    }
+}
+
+impl ServiceDescriptor {
+    /// Gets the comments attached to this service in its source file.
+    ///
+    /// Returns `None` if the file has no source code info for this service.
+    pub fn comments(&self) -> Option<Comments> {
+        find_comments(&self.parent_file(), &service_path(self))
+    }
+}
+
+impl MethodDescriptor {
+    /// Gets the comments attached to this method in its source file.
+    ///
+    /// Returns `None` if the file has no source code info for this method.
+    pub fn comments(&self) -> Option<Comments> {
+        let service = self.parent_service();
+        let mut path = service_path(service);
+        path.push(SERVICE_METHOD);
+        path.push(self.index() as i32);
+        find_comments(&service.parent_file(), &path)
+    }
+}
+
+fn message_path(message: &MessageDescriptor) -> Vec<i32> {
+    let mut path = match message.parent_message() {
+        Some(parent) => {
+            let mut path = message_path(&parent);
+            path.push(MESSAGE_NESTED_TYPE);
+            path
+        }
+        None => vec![FILE_MESSAGE_TYPE],
+    };
+    path.push(message.proto_index() as i32);
+    path
+}
+
+fn enum_path(enum_ty: &EnumDescriptor) -> Vec<i32> {
+    let mut path = match enum_ty.parent_message() {
+        Some(parent) => {
+            let mut path = message_path(&parent);
+            path.push(MESSAGE_ENUM_TYPE);
+            path
+        }
+        None => vec![FILE_ENUM_TYPE],
+    };
+    path.push(enum_ty.proto_index() as i32);
+    path
+}
+
+fn service_path(service: &ServiceDescriptor) -> Vec<i32> {
+    vec![FILE_SERVICE, service.proto_index() as i32]
+}
+
+/// Builds an index of the source code info locations of `file` by path, keeping the first location
+/// with each path.
+pub(super) fn index_source_locations(file: &FileDescriptorProto) -> HashMap<Box<[i32]>, usize> {
+    let mut index = HashMap::new();
+    if let Some(source_code_info) = &file.source_code_info {
+        for (location_index, location) in source_code_info.location.iter().enumerate() {
+            index
+                .entry(location.path.as_slice().into())
+                .or_insert(location_index);
+        }
+    }
+    index
+}
+
+fn find_comments(file: &FileDescriptor, path: &[i32]) -> Option<Comments> {
+    let file = file.file_inner();
+    let &index = file.source_locations.get(path)?;
+    let location = &file.raw.source_code_info.as_ref()?.location[index];
+    Some(Comments::from_location(location))
+}
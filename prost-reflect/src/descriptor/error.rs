@@ -1,9 +1,11 @@
-use std::fmt;
+use std::{fmt, ops::Range};
 
 /// An error that may occur while creating a [`DescriptorPool`][crate::DescriptorPool].
 #[derive(Debug)]
 pub struct DescriptorError {
     kind: DescriptorErrorKind,
+    file_name: Option<String>,
+    context_path: Option<String>,
 }
 
 #[derive(Debug)]
@@ -48,45 +50,62 @@ enum DescriptorErrorKind {
         name: String,
         type_name: String,
     },
+    ReservedFieldNumber {
+        name: String,
+        field: String,
+        number: u32,
+    },
+    ReservedFieldName {
+        name: String,
+        field: String,
+    },
+    FieldNumberInExtensionRange {
+        name: String,
+        field: String,
+        number: u32,
+    },
+    OverlappingExtensionRanges {
+        name: String,
+        first: Range<i32>,
+        second: Range<i32>,
+    },
 }
 
 impl DescriptorError {
-    pub(super) fn decode_file_descriptor_set(err: prost::DecodeError) -> Self {
+    fn new(kind: DescriptorErrorKind) -> Self {
         DescriptorError {
-            kind: DescriptorErrorKind::DecodeFileDescriptorSet { err },
+            kind,
+            file_name: None,
+            context_path: None,
         }
     }
 
+    pub(super) fn decode_file_descriptor_set(err: prost::DecodeError) -> Self {
+        DescriptorError::new(DescriptorErrorKind::DecodeFileDescriptorSet { err })
+    }
+
     pub(super) fn type_not_found(name: impl ToString) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::TypeNotFound {
-                name: name.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::TypeNotFound {
+            name: name.to_string(),
+        })
     }
 
     pub(super) fn type_already_exists(name: impl ToString) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::TypeAlreadyExists {
-                name: name.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::TypeAlreadyExists {
+            name: name.to_string(),
+        })
     }
 
     pub(super) fn unknown_syntax(syntax: impl ToString) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::UnknownSyntax {
-                syntax: syntax.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::UnknownSyntax {
+            syntax: syntax.to_string(),
+        })
     }
 
     pub(super) fn invalid_map_entry(name: impl ToString) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::InvalidMapEntry {
-                name: name.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::InvalidMapEntry {
+            name: name.to_string(),
+        })
     }
 
     pub(super) fn invalid_default_value(
@@ -94,69 +113,123 @@ impl DescriptorError {
         field: impl ToString,
         value: impl ToString,
     ) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::InvalidDefaultValue {
-                name: name.to_string(),
-                field: field.to_string(),
-                value: value.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::InvalidDefaultValue {
+            name: name.to_string(),
+            field: field.to_string(),
+            value: value.to_string(),
+        })
     }
 
     pub(super) fn empty_enum() -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::EmptyEnum,
-        }
+        DescriptorError::new(DescriptorErrorKind::EmptyEnum)
     }
 
     pub(crate) fn invalid_oneof_index(name: impl ToString, field: impl ToString) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::InvalidOneofIndex {
-                name: name.to_string(),
-                field: field.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::InvalidOneofIndex {
+            name: name.to_string(),
+            field: field.to_string(),
+        })
     }
 
     pub(crate) fn file_not_found(required_by: impl ToString, name: impl ToString) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::FileNotFound {
-                required_by: required_by.to_string(),
-                name: name.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::FileNotFound {
+            required_by: required_by.to_string(),
+            name: name.to_string(),
+        })
     }
 
     pub(crate) fn file_already_exists(name: impl ToString) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::FileAlreadyExists {
-                name: name.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::FileAlreadyExists {
+            name: name.to_string(),
+        })
     }
 
     pub(crate) fn invalid_method_type(
         name: impl ToString,
         type_name: impl ToString,
     ) -> DescriptorError {
-        DescriptorError {
-            kind: DescriptorErrorKind::InvalidMethodType {
-                name: name.to_string(),
-                type_name: type_name.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::InvalidMethodType {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+        })
     }
 
     pub(crate) fn invalid_extendee_type(
         name: impl ToString,
         type_name: impl ToString,
     ) -> DescriptorError {
-        DescriptorError {
-            kind: DescriptorErrorKind::InvalidExtendeeType {
-                name: name.to_string(),
-                type_name: type_name.to_string(),
-            },
+        DescriptorError::new(DescriptorErrorKind::InvalidExtendeeType {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+        })
+    }
+
+    pub(super) fn reserved_field_number(
+        name: impl ToString,
+        field: impl ToString,
+        number: u32,
+    ) -> Self {
+        DescriptorError::new(DescriptorErrorKind::ReservedFieldNumber {
+            name: name.to_string(),
+            field: field.to_string(),
+            number,
+        })
+    }
+
+    pub(super) fn reserved_field_name(name: impl ToString, field: impl ToString) -> Self {
+        DescriptorError::new(DescriptorErrorKind::ReservedFieldName {
+            name: name.to_string(),
+            field: field.to_string(),
+        })
+    }
+
+    pub(super) fn field_number_in_extension_range(
+        name: impl ToString,
+        field: impl ToString,
+        number: u32,
+    ) -> Self {
+        DescriptorError::new(DescriptorErrorKind::FieldNumberInExtensionRange {
+            name: name.to_string(),
+            field: field.to_string(),
+            number,
+        })
+    }
+
+    pub(super) fn overlapping_extension_ranges(
+        name: impl ToString,
+        first: Range<i32>,
+        second: Range<i32>,
+    ) -> Self {
+        DescriptorError::new(DescriptorErrorKind::OverlappingExtensionRanges {
+            name: name.to_string(),
+            first,
+            second,
+        })
+    }
+}
+
+impl DescriptorError {
+    /// Gets the name of the file in which the error occurred, if known.
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// Gets a description of the definition within the file where the error occurred, if known,
+    /// for example `message my.package.MyMessage, field my_field`.
+    ///
+    /// The error message includes the parts of this path which name definitions not already
+    /// mentioned in the message.
+    pub fn context_path(&self) -> Option<&str> {
+        self.context_path.as_deref()
+    }
+
+    /// Sets the location of the error, unless a more specific location has already been set.
+    pub(crate) fn with_context(mut self, file_name: &str, context_path: impl ToString) -> Self {
+        if self.file_name.is_none() {
+            self.file_name = Some(file_name.to_owned());
+            self.context_path = Some(context_path.to_string());
         }
+        self
     }
 }
 
@@ -171,7 +244,42 @@ impl std::error::Error for DescriptorError {
 
 impl fmt::Display for DescriptorError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match &self.kind {
+        let message = self.kind.to_string();
+        f.write_str(&message)?;
+
+        if let Some(file_name) = &self.file_name {
+            write!(f, " (in file '{}'", file_name)?;
+            if let Some(context_path) = &self.context_path {
+                // Omit definitions which are already named by the message.
+                for segment in context_path
+                    .split(", ")
+                    .filter(|segment| !mentions_definition(&message, segment))
+                {
+                    write!(f, ", {}", segment)?;
+                }
+            }
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns `true` if `message` quotes the name of the definition described by a segment of a
+/// context path such as `field my_field`, either in full or as part of a qualified name.
+fn mentions_definition(message: &str, segment: &str) -> bool {
+    match segment.split_once(' ') {
+        Some((_, name)) => {
+            message.contains(&format!("'{}'", name))
+                || message.contains(&format!("'{}.", name))
+                || message.contains(&format!(".{}'", name))
+        }
+        None => false,
+    }
+}
+
+impl fmt::Display for DescriptorErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
             DescriptorErrorKind::DecodeFileDescriptorSet { .. } => {
                 write!(f, "failed to decode file descriptor set")
             }
@@ -210,6 +318,10 @@ impl fmt::Display for DescriptorError {
             DescriptorErrorKind::FileAlreadyExists { name } => write!(f, "a conflicting file named '{}' is already added. Duplicate files must match exactly", name),
             DescriptorErrorKind::InvalidMethodType { name, type_name } => write!(f, "invalid type '{}' for method '{}'", type_name, name),
             DescriptorErrorKind::InvalidExtendeeType { name, type_name } => write!(f, "invalid type '{}' for extension '{}'", type_name, name),
+            DescriptorErrorKind::ReservedFieldNumber { name, field, number } => write!(f, "the field '{}' of message '{}' uses the reserved number {}", field, name, number),
+            DescriptorErrorKind::ReservedFieldName { name, field } => write!(f, "the field name '{}' is reserved in message '{}'", field, name),
+            DescriptorErrorKind::FieldNumberInExtensionRange { name, field, number } => write!(f, "the field '{}' of message '{}' uses the number {}, which is in an extension range", field, name, number),
+            DescriptorErrorKind::OverlappingExtensionRanges { name, first, second } => write!(f, "the extension ranges {} to {} and {} to {} of message '{}' overlap", first.start, first.end - 1, second.start, second.end - 1, name),
         }
     }
 }
@@ -1,16 +1,24 @@
+mod comments;
 mod error;
+#[cfg(feature = "schema-registry")]
+mod registry;
 mod service;
+mod source;
 mod ty;
 
 pub use self::{
+    comments::Comments,
     error::DescriptorError,
     service::{MethodDescriptor, ServiceDescriptor},
     ty::{
         Cardinality, EnumDescriptor, EnumValueDescriptor, ExtensionDescriptor, FieldDescriptor,
-        Kind, MessageDescriptor, OneofDescriptor,
+        Kind, MessageDescriptor, OneofDescriptor, ParseCardinalityError, WellKnownType,
     },
 };
 
+#[cfg(feature = "schema-registry")]
+pub use self::registry::SchemaRegistry;
+
 use std::{collections::HashMap, convert::TryInto, fmt, iter, ops::Range, sync::Arc};
 
 use prost::{bytes::Buf, Message};
@@ -30,6 +38,11 @@ pub(crate) const MAP_ENTRY_VALUE_NUMBER: u32 = 2;
 ///
 /// This type is uses reference counting internally so it is cheap to clone. Modifying an instance of a
 /// pool will not update any existing clones of the instance.
+///
+/// Two pools compare equal with [`PartialEq`] only if one is a clone of the other, and neither has
+/// been modified since. This is a cheap pointer comparison which does not inspect the files in the
+/// pool, so two pools built separately from the same files are not equal. Use
+/// [`DescriptorPool::content_eq`] to compare the files in two pools.
 #[derive(Clone, Default)]
 pub struct DescriptorPool {
     inner: Arc<DescriptorPoolInner>,
@@ -55,6 +68,8 @@ struct FileDescriptorInner {
     raw: FileDescriptorProto,
     syntax: Syntax,
     services: Range<ServiceIndex>,
+    /// The index of the first source code info location with each path.
+    source_locations: HashMap<Box<[i32]>, usize>,
 }
 
 /// The syntax of a proto file.
@@ -96,6 +111,21 @@ impl DescriptorPool {
         Ok(pool)
     }
 
+    /// Creates a [`DescriptorPool`] from the files in several [`FileDescriptorSet`]s.
+    ///
+    /// The files are added together, as if by a single call to
+    /// [`DescriptorPool::add_file_descriptor_protos`], so a file in one set may depend on files in
+    /// any of the others. A file which is included in more than one set is only added once, but if
+    /// two different files have the same name, an error is returned naming the file.
+    pub fn from_file_descriptor_sets<I>(file_descriptor_sets: I) -> Result<Self, DescriptorError>
+    where
+        I: IntoIterator<Item = FileDescriptorSet>,
+    {
+        let mut pool = DescriptorPool::new();
+        pool.add_file_descriptor_protos(file_descriptor_sets.into_iter().flat_map(|set| set.file))?;
+        Ok(pool)
+    }
+
     /// Decodes a [`FileDescriptorSet`] from its protobuf byte representation and
     /// creates a new [`DescriptorPool`] wrapping it.
     pub fn decode<B>(bytes: B) -> Result<Self, DescriptorError>
@@ -108,6 +138,33 @@ impl DescriptorPool {
         )
     }
 
+    /// Decodes a [`FileDescriptorSet`] from its protobuf byte representation and
+    /// creates a new [`DescriptorPool`] wrapping it, deferring work which is not needed to
+    /// validate the files.
+    ///
+    /// The maps used by [`MessageDescriptor::get_field_by_name`] and
+    /// [`MessageDescriptor::get_field_by_json_name`] are not built. Instead, these methods search
+    /// the fields of the message each time they are called. This can reduce startup time and
+    /// memory usage for large descriptor sets where only a few message types are used, at the
+    /// cost of slower lookups for messages with many fields. Files added to the pool later are
+    /// also not indexed.
+    pub fn decode_lazy<B>(bytes: B) -> Result<Self, DescriptorError>
+    where
+        B: Buf,
+    {
+        let mut pool = DescriptorPool {
+            inner: Arc::new(DescriptorPoolInner {
+                type_map: ty::TypeMap::new_lazy(),
+                ..Default::default()
+            }),
+        };
+        pool.add_file_descriptor_set(
+            FileDescriptorSet::decode(bytes)
+                .map_err(DescriptorError::decode_file_descriptor_set)?,
+        )?;
+        Ok(pool)
+    }
+
     /// Adds a new [`FileDescriptorSet`] to this [`DescriptorPool`].
     ///
     /// A file descriptor set may be generated by running the protobuf compiler with the
@@ -154,10 +211,11 @@ impl DescriptorPool {
         for file_index in file_indices {
             let file = &mut files[file_index as usize];
             let start: ServiceIndex = to_index(inner.services.len());
-            for service in &file.raw.service {
+            for (proto_index, service) in file.raw.service.iter().enumerate() {
                 inner.services.push(ServiceDescriptorInner::from_raw(
                     &file.raw,
                     file_index,
+                    proto_index,
                     service,
                     &inner.type_map,
                 )?);
@@ -184,6 +242,45 @@ impl DescriptorPool {
         self.add_file_descriptor_protos(iter::once(file))
     }
 
+    /// Modifies the raw [`FileDescriptorProto`] of the file named `name`, and rebuilds this pool
+    /// from the updated files.
+    ///
+    /// Descriptors cache information derived from the raw protos, such as the JSON name of a field
+    /// or whether it is packed, so changes to the raw protos only take effect after rebuilding the
+    /// pool. Descriptors obtained before calling this method continue to refer to the old pool.
+    ///
+    /// Returns `Ok(false)` without calling `f` if no such file has been added. If the modified
+    /// file is invalid, an error is returned and this pool is left unchanged. A pool created with
+    /// [`DescriptorPool::decode_lazy`] remains lazy after being rebuilt.
+    pub fn with_file_descriptor_proto_mut<F>(
+        &mut self,
+        name: &str,
+        f: F,
+    ) -> Result<bool, DescriptorError>
+    where
+        F: FnOnce(&mut FileDescriptorProto),
+    {
+        let mut file_descriptor_set = self.file_descriptor_set();
+        match file_descriptor_set
+            .file
+            .iter_mut()
+            .find(|file| file.name() == name)
+        {
+            Some(file) => f(file),
+            None => return Ok(false),
+        }
+
+        let mut pool = DescriptorPool {
+            inner: Arc::new(DescriptorPoolInner {
+                type_map: self.inner.type_map.new_empty(),
+                ..Default::default()
+            }),
+        };
+        pool.add_file_descriptor_set(file_descriptor_set)?;
+        *self = pool;
+        Ok(true)
+    }
+
     /// Gets an iterator over the file descriptors added to this pool.
     pub fn files(&self) -> impl ExactSizeIterator<Item = FileDescriptor> + '_ {
         FileDescriptor::iter(self)
@@ -204,11 +301,140 @@ impl DescriptorPool {
         self.inner.files.iter().map(|f| &f.raw)
     }
 
+    /// Returns `true` if this pool contains the same files as `other`.
+    ///
+    /// Unlike the [`PartialEq`] implementation, this compares the raw [`FileDescriptorProto`] of
+    /// each file, so pools built separately from the same files are equal. The order in which the
+    /// files were added is not significant.
+    pub fn content_eq(&self, other: &DescriptorPool) -> bool {
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            return true;
+        }
+
+        self.inner.files.len() == other.inner.files.len()
+            && self.inner.files.iter().all(|file| {
+                match other.inner.file_names.get(file.raw.name()) {
+                    Some(&index) => other.inner.files[index as usize].raw == file.raw,
+                    None => false,
+                }
+            })
+    }
+
+    /// Removes the [`SourceCodeInfo`](prost_types::SourceCodeInfo) from every file in this pool,
+    /// returning the updated pool.
+    ///
+    /// Source code info records the location and comments of each definition, and is often the
+    /// largest part of a file descriptor, so removing it can substantially reduce the memory used by
+    /// a long-lived pool. Afterwards, methods such as [`MessageDescriptor::comments`] return `None`.
+    ///
+    /// The rest of each raw [`FileDescriptorProto`] is kept, because methods such as
+    /// [`MessageDescriptor::descriptor_proto`] return references to it. Note that adding a file
+    /// which is also in this pool will return an error unless it has also had its source code info
+    /// removed, since duplicate files must match exactly. Existing clones of the pool are not
+    /// modified.
+    pub fn without_source_code_info(mut self) -> DescriptorPool {
+        for file in &mut Arc::make_mut(&mut self.inner).files {
+            file.raw.source_code_info = None;
+            file.source_locations = HashMap::new();
+        }
+        self
+    }
+
+    /// Gets the file which defines the symbol `name`, followed by all of its transitive dependencies.
+    ///
+    /// The symbol may be the fully qualified name of a message, field, oneof, enum, enum value,
+    /// extension, service or method. The dependencies are sorted such that every dependency appears
+    /// after all of the files it imports. Returns `None` if no such symbol exists in this pool.
+    ///
+    /// This corresponds to the `file_containing_symbol` request of the gRPC server reflection service.
+    pub fn file_containing_symbol(&self, name: &str) -> Option<Vec<FileDescriptorProto>> {
+        let file = self.find_file_containing_symbol(name)?;
+        Some(self.file_with_dependencies(file.index))
+    }
+
+    /// Gets the file with the given name, followed by all of its transitive dependencies.
+    ///
+    /// Returns `None` if no such file has been added.
+    ///
+    /// This corresponds to the `file_by_filename` request of the gRPC server reflection service.
+    pub fn file_by_filename(&self, name: &str) -> Option<Vec<FileDescriptorProto>> {
+        let &index = self.inner.file_names.get(name)?;
+        Some(self.file_with_dependencies(index))
+    }
+
+    /// Gets the file which defines the extension of the message `extendee` with field number `number`,
+    /// followed by all of its transitive dependencies.
+    ///
+    /// Returns `None` if no such extension exists in this pool.
+    ///
+    /// This corresponds to the `file_containing_extension` request of the gRPC server reflection service.
+    pub fn file_containing_extension(
+        &self,
+        extendee: &str,
+        number: u32,
+    ) -> Option<Vec<FileDescriptorProto>> {
+        let extension = self.get_message_by_name(extendee)?.get_extension(number)?;
+        Some(self.file_with_dependencies(extension.parent_file().index))
+    }
+
+    /// Gets a [`FileDescriptorSet`] containing the raw [`FileDescriptorProto`] instances wrapped by this
+    /// [`DescriptorPool`].
+    ///
+    /// Files are sorted in dependency order, so every file appears after all of the files it imports.
+    pub fn file_descriptor_set(&self) -> FileDescriptorSet {
+        FileDescriptorSet {
+            file: self
+                .sorted_file_indices(0..to_index(self.inner.files.len()))
+                .into_iter()
+                .map(|index| self.inner.files[index as usize].raw.clone())
+                .collect(),
+        }
+    }
+
+    /// Encodes the files contained within this [`DescriptorPool`] to their byte representation.
+    ///
+    /// The returned bytes are the encoded form of [`file_descriptor_set`][DescriptorPool::file_descriptor_set],
+    /// and may be passed to [`DescriptorPool::decode`] to recreate this pool.
+    pub fn encode_to_vec(&self) -> Vec<u8> {
+        self.file_descriptor_set().encode_to_vec()
+    }
+
     /// Gets an iterator over the services defined in these protobuf files.
     pub fn services(&self) -> impl ExactSizeIterator<Item = ServiceDescriptor> + '_ {
         (0..self.inner.services.len()).map(move |index| ServiceDescriptor::new(self.clone(), index))
     }
 
+    /// Gets the number of services defined in these protobuf files.
+    pub fn service_count(&self) -> usize {
+        self.inner.services.len()
+    }
+
+    /// Gets the service at `index` within this pool, or `None` if `index` is out-of-bounds.
+    ///
+    /// Unlike [`ServiceDescriptor::new`], this does not panic for an invalid index.
+    pub fn get_service(&self, index: usize) -> Option<ServiceDescriptor> {
+        if index < self.service_count() {
+            Some(ServiceDescriptor::new(self.clone(), index))
+        } else {
+            None
+        }
+    }
+
+    /// Gets an iterator over the fully-qualified names of every symbol defined in these protobuf
+    /// files.
+    ///
+    /// This includes messages, fields, oneofs, enums, enum values, extensions, services and
+    /// methods, which are the symbols accepted by [`file_containing_symbol`][Self::file_containing_symbol].
+    /// Names do not have a leading `.`, and are yielded in an unspecified order.
+    pub fn all_symbol_names(&self) -> impl Iterator<Item = &str> {
+        self.inner.type_map.symbol_names().chain(
+            self.inner
+                .services
+                .iter()
+                .flat_map(|service| service.symbol_names()),
+        )
+    }
+
     /// Gets an iterator over all message types defined in these protobuf files.
     ///
     /// The iterator includes nested messages defined in another message.
@@ -230,15 +456,142 @@ impl DescriptorPool {
         ExtensionDescriptor::iter(self)
     }
 
+    /// Gets an iterator over all extension fields in this pool which extend the message `extendee`.
+    ///
+    /// If `extendee` belongs to a different pool, the message with the same full name in this pool
+    /// is used instead.
+    pub fn extensions_for(
+        &self,
+        extendee: &MessageDescriptor,
+    ) -> impl ExactSizeIterator<Item = ExtensionDescriptor> + '_ {
+        ExtensionDescriptor::iter_for_extendee(self, extendee)
+    }
+
+    /// Gets the extension field of the message `extendee` with the given field number, or `None`
+    /// if no such extension is defined in this pool.
+    ///
+    /// If `extendee` belongs to a different pool, the message with the same full name in this pool
+    /// is used instead.
+    pub fn get_extension(
+        &self,
+        extendee: &MessageDescriptor,
+        number: u32,
+    ) -> Option<ExtensionDescriptor> {
+        ExtensionDescriptor::try_get_by_number(self, extendee, number)
+    }
+
+    /// Gets an [`ExtensionDescriptor`] by its fully qualified name, for example `my.package.my_extension`.
+    pub fn get_extension_by_name(&self, name: &str) -> Option<ExtensionDescriptor> {
+        ExtensionDescriptor::try_get_by_name(self, name)
+    }
+
     /// Gets a [`MessageDescriptor`] by its fully qualified name, for example `my.package.MessageName`.
+    ///
+    /// This is a hash map lookup. The returned descriptor is a reference-counted handle to this
+    /// pool paired with an index, so no descriptor data is copied.
     pub fn get_message_by_name(&self, name: &str) -> Option<MessageDescriptor> {
         MessageDescriptor::try_get_by_name(self, name)
     }
 
     /// Gets an [`EnumDescriptor`] by its fully qualified name, for example `my.package.EnumName`.
+    ///
+    /// As with [`get_message_by_name`][DescriptorPool::get_message_by_name], this only clones a
+    /// reference-counted handle to the pool.
     pub fn get_enum_by_name(&self, name: &str) -> Option<EnumDescriptor> {
         EnumDescriptor::try_get_by_name(self, name)
     }
+
+    /// Gets a [`Kind`] by name, which may be the name of a scalar type such as `int32`, or the
+    /// fully qualified name of a message or enum type, for example `my.package.MyMessage`.
+    ///
+    /// This is the reverse of the [`Display`](fmt::Display) implementation for [`Kind`].
+    pub fn get_kind_by_name(&self, name: &str) -> Option<Kind> {
+        Kind::try_get_by_name(self, name)
+    }
+
+    /// Gets an [`EnumValueDescriptor`] by its fully qualified name, for example `my.package.VALUE_NAME`.
+    ///
+    /// Enum values are scoped as siblings of their enum type, so the name does not include the name
+    /// of the enum.
+    pub fn get_enum_value_by_name(&self, name: &str) -> Option<EnumValueDescriptor> {
+        EnumValueDescriptor::try_get_by_name(self, name)
+    }
+}
+
+impl DescriptorPool {
+    fn find_file_containing_symbol(&self, name: &str) -> Option<FileDescriptor> {
+        let name = name.strip_prefix('.').unwrap_or(name);
+
+        if let Some(message) = self.get_message_by_name(name) {
+            return Some(message.parent_file());
+        }
+        if let Some(enum_ty) = self.get_enum_by_name(name) {
+            return Some(enum_ty.parent_file());
+        }
+        if let Some(service) = self.services().find(|s| s.full_name() == name) {
+            return Some(service.parent_file());
+        }
+        if let Some(extension) = self.all_extensions().find(|e| e.full_name() == name) {
+            return Some(extension.parent_file());
+        }
+
+        let (parent_name, child_name) = (parse_namespace(name), parse_name(name));
+        if let Some(message) = self.get_message_by_name(parent_name) {
+            if message.get_field_by_name(child_name).is_some()
+                || message.oneofs().any(|o| o.name() == child_name)
+            {
+                return Some(message.parent_file());
+            }
+        }
+        if let Some(service) = self.services().find(|s| s.full_name() == parent_name) {
+            if service.methods().any(|m| m.name() == child_name) {
+                return Some(service.parent_file());
+            }
+        }
+
+        self.get_enum_value_by_name(name)
+            .map(|v| v.parent_enum().parent_file())
+    }
+
+    fn file_with_dependencies(&self, index: FileIndex) -> Vec<FileDescriptorProto> {
+        let mut indices = self.sorted_file_indices(std::iter::once(index));
+        // Put the requested file first, followed by its dependencies.
+        indices.rotate_right(1);
+        indices
+            .into_iter()
+            .map(|index| self.inner.files[index as usize].raw.clone())
+            .collect()
+    }
+
+    /// Returns the given files and all of their transitive dependencies, sorted such that every file
+    /// appears after all of its dependencies.
+    fn sorted_file_indices(&self, roots: impl IntoIterator<Item = FileIndex>) -> Vec<FileIndex> {
+        fn visit(
+            pool: &DescriptorPoolInner,
+            index: FileIndex,
+            visited: &mut [bool],
+            result: &mut Vec<FileIndex>,
+        ) {
+            if visited[index as usize] {
+                return;
+            }
+            visited[index as usize] = true;
+
+            for dependency in &pool.files[index as usize].raw.dependency {
+                let dependency_index = pool.file_names[dependency.as_str()];
+                visit(pool, dependency_index, visited, result);
+            }
+
+            result.push(index);
+        }
+
+        let mut visited = vec![false; self.inner.files.len()];
+        let mut result = Vec::new();
+        for index in roots {
+            visit(&self.inner, index, &mut visited, &mut result);
+        }
+        result
+    }
 }
 
 impl DescriptorPoolInner {
@@ -260,6 +613,7 @@ impl DescriptorPoolInner {
                     let index = to_index(self.files.len());
                     self.file_names.insert(file.name().into(), index);
                     self.files.push(FileDescriptorInner {
+                        source_locations: comments::index_source_locations(&file),
                         raw: file,
                         syntax,
                         services: Default::default(),
@@ -0,0 +1,97 @@
+use std::collections::{BTreeMap, HashMap};
+
+use prost::{bytes::Buf, DecodeError};
+
+use crate::{DynamicMessage, MessageDescriptor};
+
+/// A collection of message types, keyed by a subject name and version number.
+///
+/// This follows the model used by schema registries for streaming platforms, where each record
+/// is tagged with the subject and version of the schema it was written with. The descriptors may
+/// come from any number of [`DescriptorPool`](crate::DescriptorPool)s, which are kept alive by the
+/// registry.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{DescriptorPool, SchemaRegistry, Value};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// let mut registry = SchemaRegistry::new();
+/// registry.register("my-topic-value", 1, message_descriptor.clone());
+///
+/// assert_eq!(registry.get("my-topic-value", 1), Some(message_descriptor));
+/// assert_eq!(registry.get("my-topic-value", 2), None);
+///
+/// let message = registry.decode("my-topic-value", 1, b"\x08\x96\x01".as_ref()).unwrap();
+/// assert_eq!(message.get_field_by_name("foo").unwrap().as_ref(), &Value::I32(150));
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "schema-registry")))]
+#[derive(Debug, Clone, Default)]
+pub struct SchemaRegistry {
+    subjects: HashMap<String, BTreeMap<u32, MessageDescriptor>>,
+}
+
+impl SchemaRegistry {
+    /// Creates a new, empty [`SchemaRegistry`].
+    pub fn new() -> Self {
+        SchemaRegistry::default()
+    }
+
+    /// Registers `descriptor` as the message type for `version` of `subject`.
+    ///
+    /// If a message type was already registered for this subject and version, it is replaced and
+    /// the previous value is returned.
+    pub fn register(
+        &mut self,
+        subject: impl Into<String>,
+        version: u32,
+        descriptor: MessageDescriptor,
+    ) -> Option<MessageDescriptor> {
+        self.subjects
+            .entry(subject.into())
+            .or_default()
+            .insert(version, descriptor)
+    }
+
+    /// Gets the message type registered for `version` of `subject`, or `None` if it is not registered.
+    pub fn get(&self, subject: &str, version: u32) -> Option<MessageDescriptor> {
+        self.subjects.get(subject)?.get(&version).cloned()
+    }
+
+    /// Gets the highest version registered for `subject` and its message type, or `None` if no
+    /// versions are registered.
+    pub fn get_latest(&self, subject: &str) -> Option<(u32, MessageDescriptor)> {
+        let (&version, descriptor) = self.subjects.get(subject)?.iter().next_back()?;
+        Some((version, descriptor.clone()))
+    }
+
+    /// Gets an iterator over the versions registered for `subject`, in ascending order.
+    pub fn versions(&self, subject: &str) -> impl Iterator<Item = u32> + '_ {
+        self.subjects
+            .get(subject)
+            .into_iter()
+            .flat_map(|versions| versions.keys().copied())
+    }
+
+    /// Decodes a message from `buf` using the message type registered for `version` of `subject`.
+    ///
+    /// Returns an error if no message type is registered, or if the message fails to decode.
+    pub fn decode<B>(
+        &self,
+        subject: &str,
+        version: u32,
+        buf: B,
+    ) -> Result<DynamicMessage, DecodeError>
+    where
+        B: Buf,
+    {
+        match self.get(subject, version) {
+            Some(descriptor) => DynamicMessage::decode(descriptor, buf),
+            None => Err(DecodeError::new(format!(
+                "no schema registered for version {} of subject '{}'",
+                version, subject
+            ))),
+        }
+    }
+}
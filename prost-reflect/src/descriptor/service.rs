@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, iter};
 
 use prost_types::{FileDescriptorProto, MethodDescriptorProto, ServiceDescriptorProto};
 
@@ -6,6 +6,7 @@ use super::{
     debug_fmt_iter, make_full_name, parse_name, parse_namespace, to_index, ty, DescriptorError,
     DescriptorPool, FileDescriptor, FileIndex, MessageDescriptor, MethodIndex, ServiceIndex,
 };
+use crate::DynamicMessage;
 
 /// A protobuf service definition.
 #[derive(Clone, PartialEq, Eq)]
@@ -17,6 +18,8 @@ pub struct ServiceDescriptor {
 #[derive(Clone)]
 pub(super) struct ServiceDescriptorInner {
     file: FileIndex,
+    /// The index of this service's proto within its file's `service` list.
+    proto_index: usize,
     full_name: Box<str>,
     methods: Box<[MethodDescriptorInner]>,
 }
@@ -85,14 +88,15 @@ impl ServiceDescriptor {
 
     /// Gets a reference to the raw [`ServiceDescriptorProto`] wrapped by this [`ServiceDescriptor`].
     pub fn service_descriptor_proto(&self) -> &ServiceDescriptorProto {
-        let name = self.name();
-        let package = self.package_name();
-        self.parent_pool()
-            .file_descriptor_protos()
-            .filter(|file| file.package() == package)
-            .flat_map(|file| file.service.iter())
-            .find(|service| service.name() == name)
-            .expect("service proto not found")
+        let inner = self.inner();
+        &self.parent_pool().inner.files[inner.file as usize]
+            .raw
+            .service[inner.proto_index]
+    }
+
+    /// Gets the index of the service's proto within its file's list of services.
+    pub(in crate::descriptor) fn proto_index(&self) -> usize {
+        self.inner().proto_index
     }
 
     /// Gets an iterator yielding a [`MethodDescriptor`] for each method defined in this service.
@@ -106,9 +110,15 @@ impl ServiceDescriptor {
 }
 
 impl ServiceDescriptorInner {
+    /// Gets the full names of this service and its methods.
+    pub(super) fn symbol_names(&self) -> impl Iterator<Item = &str> {
+        iter::once(&*self.full_name).chain(self.methods.iter().map(|method| &*method.full_name))
+    }
+
     pub(super) fn from_raw(
         raw_file: &FileDescriptorProto,
         file_index: FileIndex,
+        proto_index: usize,
         raw_service: &ServiceDescriptorProto,
         type_map: &ty::TypeMap,
     ) -> Result<ServiceDescriptorInner, DescriptorError> {
@@ -130,6 +140,7 @@ impl ServiceDescriptorInner {
             full_name,
             methods,
             file: file_index,
+            proto_index,
         })
     }
 }
@@ -204,6 +215,21 @@ impl MethodDescriptor {
         MessageDescriptor::new(self.parent_pool().clone(), self.inner().response_ty)
     }
 
+    /// Creates an empty [`DynamicMessage`] of this method's [`input`][MethodDescriptor::input] type.
+    pub fn new_request(&self) -> DynamicMessage {
+        DynamicMessage::new(self.input())
+    }
+
+    /// Creates an empty [`DynamicMessage`] of this method's [`output`][MethodDescriptor::output] type.
+    pub fn new_response(&self) -> DynamicMessage {
+        DynamicMessage::new(self.output())
+    }
+
+    /// Gets the path used to invoke this method over gRPC, e.g. `/my.package.MyService/my_method`.
+    pub fn full_path(&self) -> String {
+        format!("/{}/{}", self.service.full_name(), self.name())
+    }
+
     /// Returns `true` if the client streams multiple messages.
     pub fn is_client_streaming(&self) -> bool {
         self.inner().client_streaming
@@ -222,27 +248,37 @@ impl MethodDescriptor {
 impl MethodDescriptorInner {
     fn from_raw(
         namespace: &str,
-        _raw_file: &FileDescriptorProto,
+        raw_file: &FileDescriptorProto,
         _raw_service: &ServiceDescriptorProto,
         raw_method: &MethodDescriptorProto,
         type_map: &ty::TypeMap,
     ) -> Result<MethodDescriptorInner, DescriptorError> {
         let full_name = make_full_name(namespace, raw_method.name());
-
-        let request_ty = type_map.resolve_type_name(namespace, raw_method.input_type())?;
+        let with_context = |err: DescriptorError| {
+            err.with_context(
+                raw_file.name(),
+                format!("service {}, method {}", namespace, raw_method.name()),
+            )
+        };
+
+        let request_ty = type_map
+            .resolve_type_name(namespace, raw_method.input_type())
+            .map_err(with_context)?;
         if !request_ty.is_message() {
-            return Err(DescriptorError::invalid_method_type(
+            return Err(with_context(DescriptorError::invalid_method_type(
                 full_name,
                 raw_method.input_type(),
-            ));
+            )));
         }
 
-        let response_ty = type_map.resolve_type_name(namespace, raw_method.output_type())?;
+        let response_ty = type_map
+            .resolve_type_name(namespace, raw_method.output_type())
+            .map_err(with_context)?;
         if !response_ty.is_message() {
-            return Err(DescriptorError::invalid_method_type(
+            return Err(with_context(DescriptorError::invalid_method_type(
                 full_name,
                 raw_method.output_type(),
-            ));
+            )));
         }
 
         Ok(MethodDescriptorInner {
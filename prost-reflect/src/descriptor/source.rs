@@ -0,0 +1,462 @@
+use std::fmt::{self, Write};
+
+use prost_types::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    ServiceDescriptorProto,
+};
+
+use crate::descriptor::{parse_name, FileDescriptor};
+
+const MAX_FIELD_NUMBER: i32 = 536_870_911;
+
+impl FileDescriptor {
+    /// Gets an approximate reconstruction of the `.proto` source file this descriptor was built from.
+    ///
+    /// The output includes the syntax, package, imports, messages, enums, extensions and services
+    /// defined in the file. Map fields and groups are rendered using their dedicated syntax rather than
+    /// as synthetic nested messages, and the `packed`, `default` and `deprecated` field options are
+    /// included. Comments and most other options are not included, so the output is primarily
+    /// intended for debugging.
+    pub fn to_proto_source(&self) -> String {
+        let mut source = String::new();
+        SourceWriter {
+            out: &mut source,
+            indent: 0,
+            is_proto3: self.file_descriptor_proto().syntax() == "proto3",
+        }
+        .write_file(self.file_descriptor_proto())
+        .expect("writing to a string cannot fail");
+        source
+    }
+}
+
+struct SourceWriter<'a> {
+    out: &'a mut String,
+    indent: usize,
+    is_proto3: bool,
+}
+
+impl<'a> SourceWriter<'a> {
+    fn write_file(&mut self, file: &FileDescriptorProto) -> fmt::Result {
+        if self.is_proto3 {
+            writeln!(self.out, "syntax = \"proto3\";")?;
+        } else {
+            writeln!(self.out, "syntax = \"proto2\";")?;
+        }
+
+        if !file.package().is_empty() {
+            writeln!(self.out)?;
+            writeln!(self.out, "package {};", file.package())?;
+        }
+
+        if !file.dependency.is_empty() {
+            writeln!(self.out)?;
+            for (index, dependency) in file.dependency.iter().enumerate() {
+                let index = index as i32;
+                if file.public_dependency.contains(&index) {
+                    writeln!(self.out, "import public \"{}\";", dependency)?;
+                } else if file.weak_dependency.contains(&index) {
+                    writeln!(self.out, "import weak \"{}\";", dependency)?;
+                } else {
+                    writeln!(self.out, "import \"{}\";", dependency)?;
+                }
+            }
+        }
+
+        for message in &file.message_type {
+            if !is_group_type(&file.extension, message) {
+                writeln!(self.out)?;
+                self.write_message(message)?;
+            }
+        }
+        for enum_ty in &file.enum_type {
+            writeln!(self.out)?;
+            self.write_enum(enum_ty)?;
+        }
+        if !file.extension.is_empty() {
+            writeln!(self.out)?;
+            self.write_extensions(&file.message_type, &file.extension)?;
+        }
+        for service in &file.service {
+            writeln!(self.out)?;
+            self.write_service(service)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_message(&mut self, message: &DescriptorProto) -> fmt::Result {
+        self.write_indent()?;
+        writeln!(self.out, "message {} {{", message.name())?;
+        self.indent += 1;
+        self.write_message_body(message)?;
+        self.indent -= 1;
+        self.write_indent()?;
+        writeln!(self.out, "}}")
+    }
+
+    fn write_message_body(&mut self, message: &DescriptorProto) -> fmt::Result {
+        if message
+            .options
+            .as_ref()
+            .map_or(false, |options| options.message_set_wire_format())
+        {
+            self.write_indent()?;
+            writeln!(self.out, "option message_set_wire_format = true;")?;
+        }
+
+        let mut written_oneofs = vec![false; message.oneof_decl.len()];
+        for field in &message.field {
+            match field.oneof_index {
+                Some(index) if !field.proto3_optional() => {
+                    let index = index as usize;
+                    if !written_oneofs[index] {
+                        written_oneofs[index] = true;
+                        self.write_oneof(message, index)?;
+                    }
+                }
+                _ => self.write_field(&message.nested_type, field, true)?,
+            }
+        }
+
+        for nested in &message.nested_type {
+            let is_map_entry = nested
+                .options
+                .as_ref()
+                .map_or(false, |options| options.map_entry());
+            if !is_map_entry
+                && !is_group_type(&message.field, nested)
+                && !is_group_type(&message.extension, nested)
+            {
+                self.write_message(nested)?;
+            }
+        }
+        for enum_ty in &message.enum_type {
+            self.write_enum(enum_ty)?;
+        }
+        if !message.extension.is_empty() {
+            self.write_extensions(&message.nested_type, &message.extension)?;
+        }
+
+        if !message.extension_range.is_empty() {
+            self.write_indent()?;
+            write!(self.out, "extensions ")?;
+            for (index, range) in message.extension_range.iter().enumerate() {
+                if index != 0 {
+                    write!(self.out, ", ")?;
+                }
+                self.write_range(range.start(), range.end() - 1)?;
+            }
+            writeln!(self.out, ";")?;
+        }
+        if !message.reserved_range.is_empty() {
+            self.write_indent()?;
+            write!(self.out, "reserved ")?;
+            for (index, range) in message.reserved_range.iter().enumerate() {
+                if index != 0 {
+                    write!(self.out, ", ")?;
+                }
+                self.write_range(range.start(), range.end() - 1)?;
+            }
+            writeln!(self.out, ";")?;
+        }
+        self.write_reserved_names(&message.reserved_name)?;
+
+        Ok(())
+    }
+
+    fn write_oneof(&mut self, message: &DescriptorProto, index: usize) -> fmt::Result {
+        self.write_indent()?;
+        writeln!(self.out, "oneof {} {{", message.oneof_decl[index].name())?;
+        self.indent += 1;
+        for field in &message.field {
+            if field.oneof_index == Some(index as i32) {
+                self.write_field(&message.nested_type, field, false)?;
+            }
+        }
+        self.indent -= 1;
+        self.write_indent()?;
+        writeln!(self.out, "}}")
+    }
+
+    fn write_field(
+        &mut self,
+        nested_types: &[DescriptorProto],
+        field: &FieldDescriptorProto,
+        with_label: bool,
+    ) -> fmt::Result {
+        self.write_indent()?;
+
+        if let Some((key, value)) = map_entry_fields(nested_types, field) {
+            write!(
+                self.out,
+                "map<{}, {}> {} = {}",
+                field_type_name(key),
+                field_type_name(value),
+                field.name(),
+                field.number()
+            )?;
+            self.write_field_options(field)?;
+            return writeln!(self.out, ";");
+        }
+
+        if with_label {
+            match field.label() {
+                Label::Repeated => write!(self.out, "repeated ")?,
+                Label::Required => write!(self.out, "required ")?,
+                Label::Optional if !self.is_proto3 || field.proto3_optional() => {
+                    write!(self.out, "optional ")?
+                }
+                Label::Optional => (),
+            }
+        }
+
+        if field.r#type() == Type::Group {
+            let group = nested_types
+                .iter()
+                .find(|nested| nested.name() == parse_name(field.type_name()));
+            if let Some(group) = group {
+                write!(self.out, "group {} = {}", group.name(), field.number())?;
+                self.write_field_options(field)?;
+                writeln!(self.out, " {{")?;
+                self.indent += 1;
+                self.write_message_body(group)?;
+                self.indent -= 1;
+                self.write_indent()?;
+                return writeln!(self.out, "}}");
+            }
+        }
+
+        write!(
+            self.out,
+            "{} {} = {}",
+            field_type_name(field),
+            field.name(),
+            field.number()
+        )?;
+        self.write_field_options(field)?;
+        writeln!(self.out, ";")
+    }
+
+    fn write_field_options(&mut self, field: &FieldDescriptorProto) -> fmt::Result {
+        let mut options = Vec::new();
+        if let Some(default_value) = &field.default_value {
+            let default_value = match field.r#type() {
+                Type::String => format!("\"{}\"", escape_string(default_value)),
+                Type::Bytes => format!("\"{}\"", default_value),
+                _ => default_value.clone(),
+            };
+            options.push(format!("default = {}", default_value));
+        }
+        if let Some(field_options) = &field.options {
+            if let Some(packed) = field_options.packed {
+                options.push(format!("packed = {}", packed));
+            }
+            if field_options.deprecated() {
+                options.push("deprecated = true".to_owned());
+            }
+        }
+
+        if !options.is_empty() {
+            write!(self.out, " [{}]", options.join(", "))?;
+        }
+        Ok(())
+    }
+
+    fn write_extensions(
+        &mut self,
+        nested_types: &[DescriptorProto],
+        extensions: &[FieldDescriptorProto],
+    ) -> fmt::Result {
+        let mut extendees: Vec<&str> = Vec::new();
+        for extension in extensions {
+            if !extendees.contains(&extension.extendee()) {
+                extendees.push(extension.extendee());
+            }
+        }
+
+        for extendee in extendees {
+            self.write_indent()?;
+            writeln!(self.out, "extend {} {{", extendee)?;
+            self.indent += 1;
+            for extension in extensions {
+                if extension.extendee() == extendee {
+                    self.write_field(nested_types, extension, true)?;
+                }
+            }
+            self.indent -= 1;
+            self.write_indent()?;
+            writeln!(self.out, "}}")?;
+        }
+        Ok(())
+    }
+
+    fn write_enum(&mut self, enum_ty: &EnumDescriptorProto) -> fmt::Result {
+        self.write_indent()?;
+        writeln!(self.out, "enum {} {{", enum_ty.name())?;
+        self.indent += 1;
+
+        if enum_ty
+            .options
+            .as_ref()
+            .map_or(false, |options| options.allow_alias())
+        {
+            self.write_indent()?;
+            writeln!(self.out, "option allow_alias = true;")?;
+        }
+        for value in &enum_ty.value {
+            self.write_indent()?;
+            write!(self.out, "{} = {}", value.name(), value.number())?;
+            if value
+                .options
+                .as_ref()
+                .map_or(false, |options| options.deprecated())
+            {
+                write!(self.out, " [deprecated = true]")?;
+            }
+            writeln!(self.out, ";")?;
+        }
+        if !enum_ty.reserved_range.is_empty() {
+            self.write_indent()?;
+            write!(self.out, "reserved ")?;
+            for (index, range) in enum_ty.reserved_range.iter().enumerate() {
+                if index != 0 {
+                    write!(self.out, ", ")?;
+                }
+                self.write_range(range.start(), range.end())?;
+            }
+            writeln!(self.out, ";")?;
+        }
+        self.write_reserved_names(&enum_ty.reserved_name)?;
+
+        self.indent -= 1;
+        self.write_indent()?;
+        writeln!(self.out, "}}")
+    }
+
+    fn write_service(&mut self, service: &ServiceDescriptorProto) -> fmt::Result {
+        self.write_indent()?;
+        writeln!(self.out, "service {} {{", service.name())?;
+        self.indent += 1;
+        for method in &service.method {
+            self.write_indent()?;
+            writeln!(
+                self.out,
+                "rpc {}({}{}) returns ({}{});",
+                method.name(),
+                if method.client_streaming() {
+                    "stream "
+                } else {
+                    ""
+                },
+                method.input_type(),
+                if method.server_streaming() {
+                    "stream "
+                } else {
+                    ""
+                },
+                method.output_type(),
+            )?;
+        }
+        self.indent -= 1;
+        self.write_indent()?;
+        writeln!(self.out, "}}")
+    }
+
+    fn write_range(&mut self, start: i32, end: i32) -> fmt::Result {
+        if start == end {
+            write!(self.out, "{}", start)
+        } else if end == MAX_FIELD_NUMBER || end == i32::MAX {
+            write!(self.out, "{} to max", start)
+        } else {
+            write!(self.out, "{} to {}", start, end)
+        }
+    }
+
+    fn write_reserved_names(&mut self, names: &[String]) -> fmt::Result {
+        if !names.is_empty() {
+            self.write_indent()?;
+            write!(self.out, "reserved ")?;
+            for (index, name) in names.iter().enumerate() {
+                if index != 0 {
+                    write!(self.out, ", ")?;
+                }
+                write!(self.out, "\"{}\"", name)?;
+            }
+            writeln!(self.out, ";")?;
+        }
+        Ok(())
+    }
+
+    fn write_indent(&mut self) -> fmt::Result {
+        for _ in 0..self.indent {
+            self.out.write_str("  ")?;
+        }
+        Ok(())
+    }
+}
+
+fn is_group_type(fields: &[FieldDescriptorProto], nested: &DescriptorProto) -> bool {
+    fields.iter().any(|field| {
+        field.r#type() == Type::Group && parse_name(field.type_name()) == nested.name()
+    })
+}
+
+fn map_entry_fields<'a>(
+    nested_types: &'a [DescriptorProto],
+    field: &FieldDescriptorProto,
+) -> Option<(&'a FieldDescriptorProto, &'a FieldDescriptorProto)> {
+    if field.label() != Label::Repeated || field.r#type() != Type::Message {
+        return None;
+    }
+
+    let entry = nested_types.iter().find(|nested| {
+        nested.name() == parse_name(field.type_name())
+            && nested
+                .options
+                .as_ref()
+                .map_or(false, |options| options.map_entry())
+    })?;
+    let key = entry.field.iter().find(|f| f.number() == 1)?;
+    let value = entry.field.iter().find(|f| f.number() == 2)?;
+    Some((key, value))
+}
+
+fn field_type_name(field: &FieldDescriptorProto) -> &str {
+    match field.r#type() {
+        Type::Double => "double",
+        Type::Float => "float",
+        Type::Int64 => "int64",
+        Type::Uint64 => "uint64",
+        Type::Int32 => "int32",
+        Type::Fixed64 => "fixed64",
+        Type::Fixed32 => "fixed32",
+        Type::Bool => "bool",
+        Type::String => "string",
+        Type::Bytes => "bytes",
+        Type::Uint32 => "uint32",
+        Type::Sfixed32 => "sfixed32",
+        Type::Sfixed64 => "sfixed64",
+        Type::Sint32 => "sint32",
+        Type::Sint64 => "sint64",
+        Type::Group | Type::Message | Type::Enum => field.type_name(),
+    }
+}
+
+fn escape_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '"' => escaped.push_str("\\\""),
+            '\'' => escaped.push_str("\\'"),
+            '\\' => escaped.push_str("\\\\"),
+            ch if ch.is_ascii_control() => write!(escaped, "\\{:03o}", ch as u32).unwrap(),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
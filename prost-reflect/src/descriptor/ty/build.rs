@@ -14,8 +14,8 @@ use crate::{
         make_full_name, parse_namespace, to_index,
         ty::{
             Cardinality, EnumDescriptorInner, EnumValueDescriptorInner, ExtensionDescriptorInner,
-            FieldDescriptorInner, MessageDescriptorInner, OneofDescriptorInner, ParentKind, TypeId,
-            TypeMap,
+            FieldDescriptorInner, FieldNames, MessageDescriptorInner, OneofDescriptorInner,
+            ParentKind, TypeId, TypeMap,
         },
         EnumValueIndex, FileDescriptorInner, FileIndex, OneofIndex, Syntax, MAP_ENTRY_KEY_NUMBER,
         MAP_ENTRY_VALUE_NUMBER,
@@ -53,7 +53,9 @@ impl TypeMap {
         &mut self,
         MessageProto {
             file,
+            file_name,
             full_name,
+            proto_index,
             message_proto,
             parent,
             syntax,
@@ -64,6 +66,9 @@ impl TypeMap {
             None => false,
         };
 
+        check_extension_range_overlap(&full_name, message_proto)
+            .map_err(|err| err.with_context(file_name, format!("message {}", full_name)))?;
+
         let mut oneof_decls: Box<[_]> = message_proto
             .oneof_decl
             .iter()
@@ -71,35 +76,55 @@ impl TypeMap {
                 name: oneof.name().into(),
                 full_name: make_full_name(&full_name, oneof.name()),
                 fields: Vec::new(),
+                is_synthetic: false,
             })
             .collect();
 
         let fields = message_proto
             .field
             .iter()
-            .map(|field_proto| {
-                self.build_message_field(&full_name, field_proto, syntax, &mut oneof_decls)
+            .enumerate()
+            .map(|(proto_index, field_proto)| {
+                check_reserved(&full_name, message_proto, field_proto)
+                    .and_then(|()| check_extension_ranges(&full_name, message_proto, field_proto))
+                    .and_then(|()| {
+                        self.build_message_field(
+                            &full_name,
+                            proto_index,
+                            field_proto,
+                            syntax,
+                            &mut oneof_decls,
+                        )
+                    })
+                    .map_err(|err| {
+                        err.with_context(
+                            file_name,
+                            format!("message {}, field {}", full_name, field_proto.name()),
+                        )
+                    })
             })
             .collect::<Result<BTreeMap<_, _>, _>>()?;
 
-        oneof_decls
-            .iter_mut()
-            .for_each(|o| o.fields.shrink_to_fit());
+        for (index, oneof) in oneof_decls.iter_mut().enumerate() {
+            oneof.fields.shrink_to_fit();
+            oneof.is_synthetic = oneof.fields.len() == 1
+                && message_proto.field.iter().any(|field_proto| {
+                    field_proto.proto3_optional() && field_proto.oneof_index == Some(index as i32)
+                });
+        }
 
-        let field_names = fields
-            .iter()
-            .map(|(&number, field)| (field.name.clone(), number))
-            .collect();
-        let field_json_names = fields
-            .iter()
-            .map(|(&number, field)| (field.json_name.clone(), number))
-            .collect();
+        let field_names = if self.lazy {
+            None
+        } else {
+            Some(FieldNames::new(&fields))
+        };
 
         if is_map_entry
             && (!fields.contains_key(&MAP_ENTRY_KEY_NUMBER)
                 || !fields.contains_key(&MAP_ENTRY_VALUE_NUMBER))
         {
-            return Err(DescriptorError::invalid_map_entry(full_name));
+            return Err(DescriptorError::invalid_map_entry(&full_name)
+                .with_context(file_name, format!("message {}", full_name)));
         }
 
         let parent = match parent {
@@ -115,9 +140,9 @@ impl TypeMap {
         );
         self.messages.push(MessageDescriptorInner {
             file,
+            proto_index,
             fields,
             field_names,
-            field_json_names,
             oneof_decls,
             full_name,
             parent,
@@ -131,6 +156,7 @@ impl TypeMap {
     fn build_message_field(
         &mut self,
         message_name: &str,
+        proto_index: usize,
         field_proto: &FieldDescriptorProto,
         syntax: Syntax,
         oneof_decls: &mut [OneofDescriptorInner],
@@ -229,6 +255,7 @@ impl TypeMap {
             default_value,
             oneof_index,
             ty,
+            proto_index,
         };
         Ok((number, field))
     }
@@ -256,7 +283,9 @@ impl TypeMap {
         &mut self,
         EnumProto {
             file,
+            file_name,
             full_name,
+            proto_index,
             enum_proto,
             parent,
             syntax,
@@ -266,10 +295,12 @@ impl TypeMap {
         let mut values: Vec<_> = enum_proto
             .value
             .iter()
-            .map(|value_proto| EnumValueDescriptorInner {
+            .enumerate()
+            .map(|(proto_index, value_proto)| EnumValueDescriptorInner {
                 name: value_proto.name().into(),
                 number: value_proto.number(),
                 full_name: make_full_name(package_name, value_proto.name()),
+                proto_index,
             })
             .collect();
         values.sort_by_key(|v| v.number);
@@ -291,7 +322,10 @@ impl TypeMap {
             let name = enum_proto
                 .value
                 .get(0)
-                .ok_or_else(DescriptorError::empty_enum)?
+                .ok_or_else(|| {
+                    DescriptorError::empty_enum()
+                        .with_context(file_name, format!("enum {}", full_name))
+                })?
                 .name();
             value_names[name]
         } else {
@@ -299,15 +333,22 @@ impl TypeMap {
                 .iter()
                 .position(|v| v.number == 0)
                 .map(to_index)
-                .ok_or_else(DescriptorError::empty_enum)?
+                .ok_or_else(|| {
+                    DescriptorError::empty_enum()
+                        .with_context(file_name, format!("enum {}", full_name))
+                })?
         };
 
-        debug_assert_eq!(
-            self.get_by_name(&full_name),
-            Some(TypeId::new_enum(to_index(self.enums.len())))
-        );
+        let index = to_index(self.enums.len());
+        debug_assert_eq!(self.get_by_name(&full_name), Some(TypeId::new_enum(index)));
+        for (value_index, value) in values.iter().enumerate() {
+            self.enum_value_names
+                .entry(value.full_name.clone())
+                .or_insert((index, to_index(value_index)));
+        }
         self.enums.push(EnumDescriptorInner {
             file,
+            proto_index,
             full_name,
             parent,
             value_names,
@@ -321,20 +362,36 @@ impl TypeMap {
         &mut self,
         ExtensionProto {
             file,
+            file_name,
             namespace,
+            proto_index,
             field_proto,
             parent,
             syntax,
         }: ExtensionProto,
     ) -> Result<(), DescriptorError> {
-        let (number, field) = self.build_message_field(&namespace, field_proto, syntax, &mut [])?;
+        let with_context = |err: DescriptorError| {
+            err.with_context(
+                file_name,
+                format!(
+                    "extension {}",
+                    make_full_name(&namespace, field_proto.name())
+                ),
+            )
+        };
+
+        let (number, field) = self
+            .build_message_field(&namespace, proto_index, field_proto, syntax, &mut [])
+            .map_err(with_context)?;
 
-        let extendee = self.resolve_type_name(&namespace, field_proto.extendee())?;
+        let extendee = self
+            .resolve_type_name(&namespace, field_proto.extendee())
+            .map_err(with_context)?;
         if !extendee.is_message() {
-            return Err(DescriptorError::invalid_extendee_type(
+            return Err(with_context(DescriptorError::invalid_extendee_type(
                 field.full_name,
                 field_proto.extendee(),
-            ));
+            )));
         }
 
         let mut json_name = String::with_capacity(2 + field.full_name.len());
@@ -352,6 +409,12 @@ impl TypeMap {
 
         let index = to_index(self.extensions.len());
         self.get_message_mut(extendee).extensions.push(index);
+        self.extension_names
+            .entry(field.full_name.clone())
+            .or_insert(index);
+        self.extension_numbers
+            .entry((extendee.1, number))
+            .or_insert(index);
         self.extensions.push(ExtensionDescriptorInner {
             file,
             field,
@@ -374,10 +437,11 @@ impl TypeMap {
         for (file_index, file) in raw {
             let namespace = file.raw.package();
 
-            for message_proto in &file.raw.message_type {
+            for (proto_index, message_proto) in file.raw.message_type.iter().enumerate() {
                 let full_name = make_full_name(namespace, message_proto.name());
                 self.iter_message(
                     file_index,
+                    file.raw.name(),
                     &full_name,
                     messages,
                     enums,
@@ -392,14 +456,16 @@ impl TypeMap {
                 )?;
                 messages.push(MessageProto {
                     file: file_index,
+                    file_name: file.raw.name(),
                     full_name,
+                    proto_index,
                     message_proto,
                     parent: None,
                     syntax: file.syntax,
                 });
             }
 
-            for enum_proto in &file.raw.enum_type {
+            for (proto_index, enum_proto) in file.raw.enum_type.iter().enumerate() {
                 let full_name = make_full_name(namespace, enum_proto.name());
 
                 self.add_named_type(
@@ -408,17 +474,21 @@ impl TypeMap {
                 )?;
                 enums.push(EnumProto {
                     file: file_index,
+                    file_name: file.raw.name(),
                     full_name,
+                    proto_index,
                     enum_proto,
                     parent: None,
                     syntax: file.syntax,
                 });
             }
 
-            for field_proto in &file.raw.extension {
+            for (proto_index, field_proto) in file.raw.extension.iter().enumerate() {
                 extensions.push(ExtensionProto {
                     file: file_index,
+                    file_name: file.raw.name(),
                     namespace: namespace.into(),
+                    proto_index,
                     field_proto,
                     parent: None,
                     syntax: file.syntax,
@@ -433,6 +503,7 @@ impl TypeMap {
     fn iter_message<'a>(
         &mut self,
         file_index: FileIndex,
+        file_name: &'a str,
         namespace: &str,
         messages: &mut Vec<MessageProto<'a>>,
         enums: &mut Vec<EnumProto<'a>>,
@@ -440,10 +511,11 @@ impl TypeMap {
         raw: &'a DescriptorProto,
         syntax: Syntax,
     ) -> Result<(), DescriptorError> {
-        for message_proto in &raw.nested_type {
+        for (proto_index, message_proto) in raw.nested_type.iter().enumerate() {
             let full_name = make_full_name(namespace, message_proto.name());
             self.iter_message(
                 file_index,
+                file_name,
                 &full_name,
                 messages,
                 enums,
@@ -458,14 +530,16 @@ impl TypeMap {
             )?;
             messages.push(MessageProto {
                 file: file_index,
+                file_name,
                 full_name,
+                proto_index,
                 message_proto,
                 parent: Some(namespace.into()),
                 syntax,
             });
         }
 
-        for enum_proto in &raw.enum_type {
+        for (proto_index, enum_proto) in raw.enum_type.iter().enumerate() {
             let full_name = make_full_name(namespace, enum_proto.name());
 
             self.add_named_type(
@@ -474,17 +548,21 @@ impl TypeMap {
             )?;
             enums.push(EnumProto {
                 file: file_index,
+                file_name,
                 full_name,
+                proto_index,
                 enum_proto,
                 parent: Some(namespace.into()),
                 syntax,
             });
         }
 
-        for field_proto in &raw.extension {
+        for (proto_index, field_proto) in raw.extension.iter().enumerate() {
             extensions.push(ExtensionProto {
                 file: file_index,
+                file_name,
                 namespace: namespace.into(),
+                proto_index,
                 field_proto,
                 parent: Some(namespace.into()),
                 syntax,
@@ -498,7 +576,9 @@ impl TypeMap {
 #[derive(Clone)]
 struct MessageProto<'a> {
     file: FileIndex,
+    file_name: &'a str,
     full_name: Box<str>,
+    proto_index: usize,
     message_proto: &'a DescriptorProto,
     parent: Option<Box<str>>,
     syntax: Syntax,
@@ -507,7 +587,9 @@ struct MessageProto<'a> {
 #[derive(Clone)]
 struct EnumProto<'a> {
     file: FileIndex,
+    file_name: &'a str,
     full_name: Box<str>,
+    proto_index: usize,
     enum_proto: &'a EnumDescriptorProto,
     parent: Option<Box<str>>,
     syntax: Syntax,
@@ -516,12 +598,91 @@ struct EnumProto<'a> {
 #[derive(Clone)]
 struct ExtensionProto<'a> {
     file: FileIndex,
+    file_name: &'a str,
     namespace: Box<str>,
+    proto_index: usize,
     field_proto: &'a FieldDescriptorProto,
     parent: Option<Rc<str>>,
     syntax: Syntax,
 }
 
+fn check_reserved(
+    message_name: &str,
+    message_proto: &DescriptorProto,
+    field_proto: &FieldDescriptorProto,
+) -> Result<(), DescriptorError> {
+    let number = field_proto.number();
+    if message_proto
+        .reserved_range
+        .iter()
+        .any(|range| range.start() <= number && number < range.end())
+    {
+        return Err(DescriptorError::reserved_field_number(
+            message_name,
+            field_proto.name(),
+            number as u32,
+        ));
+    }
+
+    if message_proto
+        .reserved_name
+        .iter()
+        .any(|name| name == field_proto.name())
+    {
+        return Err(DescriptorError::reserved_field_name(
+            message_name,
+            field_proto.name(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn check_extension_ranges(
+    message_name: &str,
+    message_proto: &DescriptorProto,
+    field_proto: &FieldDescriptorProto,
+) -> Result<(), DescriptorError> {
+    let number = field_proto.number();
+    if message_proto
+        .extension_range
+        .iter()
+        .any(|range| range.start() <= number && number < range.end())
+    {
+        return Err(DescriptorError::field_number_in_extension_range(
+            message_name,
+            field_proto.name(),
+            number as u32,
+        ));
+    }
+
+    Ok(())
+}
+
+fn check_extension_range_overlap(
+    message_name: &str,
+    message_proto: &DescriptorProto,
+) -> Result<(), DescriptorError> {
+    let mut ranges: Vec<_> = message_proto
+        .extension_range
+        .iter()
+        .map(|range| range.start()..range.end())
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+
+    for pair in ranges.windows(2) {
+        if pair[1].start < pair[0].end {
+            return Err(DescriptorError::overlapping_extension_ranges(
+                message_name,
+                pair[0].clone(),
+                pair[1].clone(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 /// From https://github.com/tokio-rs/prost/blob/c3b7037a7f2c56cef327b41ca32a8c4e9ce5a41c/prost-build/src/code_generator.rs#L887
 /// Based on [`google::protobuf::UnescapeCEscapeString`][1]
 /// [1]: https://github.com/google/protobuf/blob/3.3.x/src/google/protobuf/stubs/strutil.cc#L312-L322
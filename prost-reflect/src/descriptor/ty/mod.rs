@@ -7,19 +7,24 @@ use std::{
         hash_map::{self, HashMap},
         BTreeMap,
     },
-    fmt,
+    fmt, iter,
     ops::{Range, RangeInclusive},
+    ptr,
+    str::FromStr,
 };
 
 use prost::encoding::WireType;
 use prost_types::{
     field_descriptor_proto, DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto,
-    FieldDescriptorProto, FileDescriptorProto, OneofDescriptorProto,
+    FieldDescriptorProto, FieldMask, FileDescriptorProto, OneofDescriptorProto,
 };
 
-use crate::descriptor::{
-    debug_fmt_iter, make_full_name, parse_name, parse_namespace, to_index, DescriptorError,
-    DescriptorPool, FileDescriptor, MAP_ENTRY_KEY_NUMBER, MAP_ENTRY_VALUE_NUMBER,
+use crate::{
+    descriptor::{
+        debug_fmt_iter, make_full_name, parse_name, parse_namespace, to_index, DescriptorError,
+        DescriptorPool, FileDescriptor, MAP_ENTRY_KEY_NUMBER, MAP_ENTRY_VALUE_NUMBER,
+    },
+    DynamicMessage, Value,
 };
 
 use super::{EnumIndex, EnumValueIndex, ExtensionIndex, FileIndex, MessageIndex, OneofIndex};
@@ -33,9 +38,19 @@ pub(super) struct TypeMap {
     messages: Vec<MessageDescriptorInner>,
     enums: Vec<EnumDescriptorInner>,
     extensions: Vec<ExtensionDescriptorInner>,
+    extension_names: HashMap<Box<str>, ExtensionIndex>,
+    extension_numbers: HashMap<(MessageIndex, u32), ExtensionIndex>,
+    enum_value_names: HashMap<Box<str>, (EnumIndex, EnumValueIndex)>,
+    lazy: bool,
 }
 
+const MAX_FIELD_NUMBER: u32 = 536_870_911;
+const IMPLEMENTATION_RESERVED_NUMBERS: Range<u32> = 19_000..20_000;
+
 /// A protobuf message definition.
+///
+/// Two message descriptors compare equal if they describe the same message in the same
+/// [`DescriptorPool`]. This is cheap to check, since the pools are compared by pointer.
 #[derive(Clone, PartialEq, Eq)]
 pub struct MessageDescriptor {
     pool: DescriptorPool,
@@ -46,15 +61,22 @@ pub struct MessageDescriptor {
 struct MessageDescriptorInner {
     full_name: Box<str>,
     file: FileIndex,
+    /// The index of this message's proto within its parent's `message_type` or `nested_type` list.
+    proto_index: usize,
     parent: ParentKind,
     is_map_entry: bool,
     fields: BTreeMap<u32, FieldDescriptorInner>,
-    field_names: HashMap<Box<str>, u32>,
-    field_json_names: HashMap<Box<str>, u32>,
+    field_names: Option<FieldNames>,
     oneof_decls: Box<[OneofDescriptorInner]>,
     extensions: Vec<ExtensionIndex>,
 }
 
+#[derive(Clone)]
+struct FieldNames {
+    names: HashMap<Box<str>, u32>,
+    json_names: HashMap<Box<str>, u32>,
+}
+
 /// A oneof field in a protobuf message.
 #[derive(Clone, PartialEq, Eq)]
 pub struct OneofDescriptor {
@@ -67,6 +89,7 @@ struct OneofDescriptorInner {
     name: Box<str>,
     full_name: Box<str>,
     fields: Vec<u32>,
+    is_synthetic: bool,
 }
 
 /// A protobuf message definition.
@@ -88,6 +111,8 @@ struct FieldDescriptorInner {
     default_value: Option<crate::Value>,
     oneof_index: Option<OneofIndex>,
     ty: TypeId,
+    /// The index of this field's proto within its parent's `field` or `extension` list.
+    proto_index: usize,
 }
 
 /// A protobuf extension field definition.
@@ -118,6 +143,8 @@ pub struct EnumDescriptor {
 struct EnumDescriptorInner {
     full_name: Box<str>,
     file: FileIndex,
+    /// The index of this enum's proto within its parent's `enum_type` list.
+    proto_index: usize,
     parent: ParentKind,
     value_names: HashMap<Box<str>, EnumValueIndex>,
     values: Vec<EnumValueDescriptorInner>,
@@ -136,6 +163,9 @@ struct EnumValueDescriptorInner {
     name: Box<str>,
     number: i32,
     full_name: Box<str>,
+    /// The index of this value's proto within its enum's `value` list. Values are stored in order
+    /// of their numbers, so this may differ from the value's index.
+    proto_index: usize,
 }
 
 /// The type of a protobuf message field.
@@ -188,6 +218,53 @@ pub enum Cardinality {
     Repeated,
 }
 
+/// Error type returned when parsing a [`Cardinality`] from a string fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCardinalityError {
+    value: String,
+}
+
+/// A well-known message type defined by the protobuf library in the `google.protobuf` package.
+///
+/// Some of these types have special representations in the JSON and text formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WellKnownType {
+    /// The `google.protobuf.Any` message type.
+    Any,
+    /// The `google.protobuf.Timestamp` message type.
+    Timestamp,
+    /// The `google.protobuf.Duration` message type.
+    Duration,
+    /// The `google.protobuf.Struct` message type.
+    Struct,
+    /// The `google.protobuf.Value` message type.
+    Value,
+    /// The `google.protobuf.ListValue` message type.
+    ListValue,
+    /// The `google.protobuf.FieldMask` message type.
+    FieldMask,
+    /// The `google.protobuf.Empty` message type.
+    Empty,
+    /// The `google.protobuf.DoubleValue` message type.
+    DoubleValue,
+    /// The `google.protobuf.FloatValue` message type.
+    FloatValue,
+    /// The `google.protobuf.Int64Value` message type.
+    Int64Value,
+    /// The `google.protobuf.UInt64Value` message type.
+    UInt64Value,
+    /// The `google.protobuf.Int32Value` message type.
+    Int32Value,
+    /// The `google.protobuf.UInt32Value` message type.
+    UInt32Value,
+    /// The `google.protobuf.BoolValue` message type.
+    BoolValue,
+    /// The `google.protobuf.StringValue` message type.
+    StringValue,
+    /// The `google.protobuf.BytesValue` message type.
+    BytesValue,
+}
+
 #[derive(Copy, Clone, Debug)]
 enum ParentKind {
     File,
@@ -265,7 +342,15 @@ impl MessageDescriptor {
         find_message_descriptor_proto(self.parent_pool(), self.inner().file, self.index)
     }
 
+    /// Gets the index of the message's proto within its parent's list of definitions.
+    pub(in crate::descriptor) fn proto_index(&self) -> usize {
+        self.inner().proto_index
+    }
+
     /// Gets an iterator yielding a [`FieldDescriptor`] for each field defined in this message.
+    ///
+    /// The fields are yielded in ascending order of their numbers, which is the order they are
+    /// encoded in, rather than the order they are declared.
     pub fn fields(&self) -> impl ExactSizeIterator<Item = FieldDescriptor> + '_ {
         self.inner()
             .fields
@@ -276,12 +361,22 @@ impl MessageDescriptor {
             })
     }
 
+    /// Gets the number of fields defined in this message.
+    pub fn field_count(&self) -> usize {
+        self.inner().fields.len()
+    }
+
     /// Gets an iterator yielding a [`OneofDescriptor`] for each oneof field defined in this message.
     pub fn oneofs(&self) -> impl ExactSizeIterator<Item = OneofDescriptor> + '_ {
         (0..self.inner().oneof_decls.len())
             .map(move |index| OneofDescriptor::new(self.clone(), to_index(index)))
     }
 
+    /// Gets a [`OneofDescriptor`] with the given name, or `None` if no such oneof exists.
+    pub fn get_oneof_by_name(&self, name: &str) -> Option<OneofDescriptor> {
+        self.oneofs().find(|oneof| oneof.name() == name)
+    }
+
     /// Gets the nested message types defined within this message.
     pub fn child_messages(&self) -> impl ExactSizeIterator<Item = MessageDescriptor> + '_ {
         let pool = self.parent_pool();
@@ -353,25 +448,72 @@ impl MessageDescriptor {
     /// Gets a [`FieldDescriptor`] with the given name, or `None` if no such field exists.
     pub fn get_field_by_name(&self, name: &str) -> Option<FieldDescriptor> {
         self.inner()
-            .field_names
-            .get(name)
-            .map(|&number| FieldDescriptor {
+            .get_field_number_by_name(name)
+            .map(|number| FieldDescriptor {
                 message: self.clone(),
                 field: number,
             })
     }
 
+    /// Returns `true` if this message defines a field with the given number.
+    pub fn has_field(&self, number: u32) -> bool {
+        self.inner().fields.contains_key(&number)
+    }
+
+    /// Returns `true` if this message defines a field with the given name.
+    pub fn has_field_by_name(&self, name: &str) -> bool {
+        self.inner().get_field_number_by_name(name).is_some()
+    }
+
     /// Gets a [`FieldDescriptor`] with the given JSON name, or `None` if no such field exists.
     pub fn get_field_by_json_name(&self, json_name: &str) -> Option<FieldDescriptor> {
         self.inner()
-            .field_json_names
-            .get(json_name)
-            .map(|&number| FieldDescriptor {
+            .get_field_number_by_json_name(json_name)
+            .map(|number| FieldDescriptor {
                 message: self.clone(),
                 field: number,
             })
     }
 
+    /// Checks that every path in `mask` refers to a field of this message.
+    ///
+    /// Each path is a sequence of field names separated by `.`. Every segment except the last must
+    /// name a singular message field, which the next segment is resolved against. If any paths are
+    /// invalid, they are returned in the order they appear in the mask.
+    pub fn validate_field_mask(&self, mask: &FieldMask) -> Result<(), Vec<String>> {
+        let invalid_paths: Vec<String> = mask
+            .paths
+            .iter()
+            .filter(|path| !self.is_valid_field_mask_path(path))
+            .cloned()
+            .collect();
+        if invalid_paths.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid_paths)
+        }
+    }
+
+    fn is_valid_field_mask_path(&self, path: &str) -> bool {
+        let mut message = self.clone();
+        let mut segments = path.split('.').peekable();
+        while let Some(segment) = segments.next() {
+            let field = match message.get_field_by_name(segment) {
+                Some(field) => field,
+                None => return false,
+            };
+            if segments.peek().is_some() {
+                match field.kind() {
+                    Kind::Message(field_message) if !field.is_list() && !field.is_map() => {
+                        message = field_message
+                    }
+                    _ => return false,
+                }
+            }
+        }
+        true
+    }
+
     /// Returns `true` if this is an auto-generated message type to
     /// represent the entry type for a map field.
     //
@@ -426,6 +568,51 @@ impl MessageDescriptor {
             .map(|n| n.as_ref())
     }
 
+    /// Returns `true` if the field number `number` is reserved in this message.
+    pub fn is_reserved_number(&self, number: u32) -> bool {
+        self.reserved_ranges().any(|range| range.contains(&number))
+    }
+
+    /// Returns `true` if the field name `name` is reserved in this message.
+    pub fn is_reserved_name(&self, name: &str) -> bool {
+        self.reserved_names().any(|reserved| reserved == name)
+    }
+
+    /// Gets an iterator over the field numbers which are used in this message, in ascending order.
+    ///
+    /// This includes the numbers of the fields defined in this message, as well as every number in
+    /// its extension ranges and reserved ranges. Note that extension ranges are often very large.
+    pub fn used_field_numbers(&self) -> impl Iterator<Item = u32> {
+        coalesce_ranges(self.used_field_ranges())
+            .into_iter()
+            .flatten()
+    }
+
+    /// Gets the smallest field number which is not used in this message, or `None` if every valid
+    /// field number is used.
+    ///
+    /// See [`used_field_numbers`][Self::used_field_numbers] for the numbers which are considered used.
+    /// The numbers `19000` to `19999`, which are reserved for the protobuf implementation, are also
+    /// skipped.
+    pub fn next_available_field_number(&self) -> Option<u32> {
+        let mut ranges = self.used_field_ranges();
+        ranges.push(IMPLEMENTATION_RESERVED_NUMBERS);
+
+        let mut number = 1;
+        for range in coalesce_ranges(ranges) {
+            if range.start > number {
+                break;
+            }
+            number = number.max(range.end);
+        }
+
+        if number <= MAX_FIELD_NUMBER {
+            Some(number)
+        } else {
+            None
+        }
+    }
+
     /// Gets an iterator over extension field number ranges in this message.
     pub fn extension_ranges(&self) -> impl ExactSizeIterator<Item = Range<u32>> + '_ {
         self.descriptor_proto()
@@ -436,7 +623,7 @@ impl MessageDescriptor {
 
     /// Gets an extension to this message by its number, or `None` if no such extension exists.
     pub fn get_extension(&self, number: u32) -> Option<ExtensionDescriptor> {
-        self.extensions().find(|ext| ext.number() == number)
+        ExtensionDescriptor::try_get_by_number(&self.pool, self, number)
     }
 
     /// Gets an extension to this message by its full name (e.g. `my.package.my_extension`), or `None` if no such extension exists.
@@ -449,6 +636,40 @@ impl MessageDescriptor {
         self.extensions().find(|ext| ext.json_name() == name)
     }
 
+    /// Returns `true` if this is one of the well-known types defined in the `google/protobuf/*.proto` files.
+    ///
+    /// See [`well_known_type`][MessageDescriptor::well_known_type] for more details.
+    pub fn is_well_known_type(&self) -> bool {
+        self.well_known_type().is_some()
+    }
+
+    /// Gets the [`WellKnownType`] this message corresponds to, or `None` if it is not a well-known type.
+    ///
+    /// A message is considered well-known if it has the full name of one of the types listed in
+    /// [`WellKnownType`], and it is defined in a file within the `google/protobuf/` directory.
+    pub fn well_known_type(&self) -> Option<WellKnownType> {
+        if !self
+            .parent_file_descriptor_proto()
+            .name()
+            .starts_with("google/protobuf/")
+        {
+            return None;
+        }
+
+        WellKnownType::from_full_name(self.full_name())
+    }
+
+    fn used_field_ranges(&self) -> Vec<Range<u32>> {
+        self.inner()
+            .fields
+            .keys()
+            .map(|&number| number..(number + 1))
+            .chain(self.extension_ranges())
+            .chain(self.reserved_ranges())
+            .map(|range| range.start..range.end.min(MAX_FIELD_NUMBER + 1))
+            .collect()
+    }
+
     fn inner(&self) -> &MessageDescriptorInner {
         self.pool.inner.type_map.get_message(self.index)
     }
@@ -494,12 +715,12 @@ impl FieldDescriptor {
 
     /// Gets a reference to the raw [`FieldDescriptorProto`] wrapped by this [`FieldDescriptor`].
     pub fn field_descriptor_proto(&self) -> &FieldDescriptorProto {
-        self.parent_message()
-            .descriptor_proto()
-            .field
-            .iter()
-            .find(|field| field.number() as u32 == self.field)
-            .expect("field not found")
+        &self.parent_message().descriptor_proto().field[self.inner().proto_index]
+    }
+
+    /// Gets the index of the field's proto within its parent's list of definitions.
+    pub(in crate::descriptor) fn proto_index(&self) -> usize {
+        self.inner().proto_index
     }
 
     /// Gets the unique number for this message field.
@@ -541,6 +762,36 @@ impl FieldDescriptor {
             }
     }
 
+    /// Gets the [`Kind`] of the keys of this map field, or `None` if this field is not a map.
+    pub fn map_key_kind(&self) -> Option<Kind> {
+        self.map_entry_descriptor()
+            .map(|entry| entry.map_entry_key_field().kind())
+    }
+
+    /// Gets the [`Kind`] of the values of this map field, or `None` if this field is not a map.
+    pub fn map_value_kind(&self) -> Option<Kind> {
+        self.map_entry_descriptor()
+            .map(|entry| entry.map_entry_value_field().kind())
+    }
+
+    /// Returns `true` if this field is a map whose values are messages.
+    pub fn is_map_value_message(&self) -> bool {
+        matches!(self.map_value_kind(), Some(Kind::Message(_)))
+    }
+
+    /// Gets the synthetic map entry message type of this map field, or `None` if this field is
+    /// not a map.
+    ///
+    /// The entry message has a field for the key and a field for the value, which can be
+    /// found with [`MessageDescriptor::map_entry_key_field`] and
+    /// [`MessageDescriptor::map_entry_value_field`].
+    pub fn map_entry_descriptor(&self) -> Option<MessageDescriptor> {
+        match self.kind() {
+            Kind::Message(message) if self.is_map() => Some(message),
+            _ => None,
+        }
+    }
+
     /// Whether this field is a list encoded using [packed encoding](https://developers.google.com/protocol-buffers/docs/encoding#packed).
     pub fn is_packed(&self) -> bool {
         self.inner().is_packed
@@ -574,7 +825,20 @@ impl FieldDescriptor {
             .map(|index| OneofDescriptor::new(self.message.clone(), index))
     }
 
-    pub(crate) fn default_value(&self) -> Option<&crate::Value> {
+    /// Gets a [`OneofDescriptor`] representing the oneof containing this field,
+    /// or `None` if this field is not contained in a oneof, or is contained in a
+    /// [synthetic oneof](OneofDescriptor::is_synthetic).
+    pub fn real_containing_oneof(&self) -> Option<OneofDescriptor> {
+        self.containing_oneof()
+            .filter(|oneof| !oneof.is_synthetic())
+    }
+
+    /// Gets the explicit default value of this field, declared with the `default` option in a
+    /// `proto2` file, or `None` if no default value is declared.
+    ///
+    /// Enum default values are resolved to their numbers. To get the value used for an unset field,
+    /// including implicit default values, see [`Value::default_value_for_field`].
+    pub fn default_value(&self) -> Option<&Value> {
         self.inner().default_value.as_ref()
     }
 
@@ -623,6 +887,49 @@ impl ExtensionDescriptor {
             })
     }
 
+    pub(in crate::descriptor) fn iter_for_extendee<'a>(
+        pool: &'a DescriptorPool,
+        extendee: &MessageDescriptor,
+    ) -> impl ExactSizeIterator<Item = Self> + 'a {
+        let extensions = match pool.inner.type_map.resolve_message(extendee) {
+            Some(index) => &pool.inner.type_map.get_message(index).extensions[..],
+            None => &[],
+        };
+        extensions.iter().map(move |&index| ExtensionDescriptor {
+            pool: pool.clone(),
+            index,
+        })
+    }
+
+    pub(in crate::descriptor) fn try_get_by_name(
+        pool: &DescriptorPool,
+        name: &str,
+    ) -> Option<Self> {
+        let name = name.strip_prefix('.').unwrap_or(name);
+        let &index = pool.inner.type_map.extension_names.get(name)?;
+        Some(ExtensionDescriptor {
+            pool: pool.clone(),
+            index,
+        })
+    }
+
+    pub(in crate::descriptor) fn try_get_by_number(
+        pool: &DescriptorPool,
+        extendee: &MessageDescriptor,
+        number: u32,
+    ) -> Option<Self> {
+        let extendee = pool.inner.type_map.resolve_message(extendee)?;
+        let &index = pool
+            .inner
+            .type_map
+            .extension_numbers
+            .get(&(extendee, number))?;
+        Some(ExtensionDescriptor {
+            pool: pool.clone(),
+            index,
+        })
+    }
+
     /// Gets a reference to the [`DescriptorPool`] this extension field is defined in.
     pub fn parent_pool(&self) -> &DescriptorPool {
         &self.pool
@@ -670,22 +977,19 @@ impl ExtensionDescriptor {
 
     /// Gets a reference to the raw [`FieldDescriptorProto`] wrapped by this [`ExtensionDescriptor`].
     pub fn field_descriptor_proto(&self) -> &FieldDescriptorProto {
-        let name = self.name();
         let inner = self.inner();
-        match inner.parent {
-            ParentKind::File => get_file_descriptor_proto(&self.pool, inner.file)
-                .extension
-                .iter()
-                .find(|extension| extension.name() == name)
-                .expect("extension not found"),
+        let extensions = match inner.parent {
+            ParentKind::File => &get_file_descriptor_proto(&self.pool, inner.file).extension,
             ParentKind::Message {
                 index: message_index,
-            } => find_message_descriptor_proto(&self.pool, inner.file, message_index)
-                .extension
-                .iter()
-                .find(|extension| extension.name() == name)
-                .expect("extension not found"),
-        }
+            } => &find_message_descriptor_proto(&self.pool, inner.file, message_index).extension,
+        };
+        &extensions[inner.field.proto_index]
+    }
+
+    /// Gets the index of the extension's proto within its parent's list of definitions.
+    pub(in crate::descriptor) fn proto_index(&self) -> usize {
+        self.inner().field.proto_index
     }
 
     /// Gets the number for this extension field.
@@ -754,7 +1058,11 @@ impl ExtensionDescriptor {
         MessageDescriptor::new(self.pool.clone(), self.inner().extendee)
     }
 
-    pub(crate) fn default_value(&self) -> Option<&crate::Value> {
+    /// Gets the explicit default value of this extension field, declared with the `default`
+    /// option in a `proto2` file, or `None` if no default value is declared.
+    ///
+    /// See [`FieldDescriptor::default_value`] for more details.
+    pub fn default_value(&self) -> Option<&Value> {
         self.field_inner().default_value.as_ref()
     }
 
@@ -813,6 +1121,80 @@ impl Kind {
         }
     }
 
+    /// Returns the default value for this type, for example `0` for integer types, the first
+    /// value for enum types or an empty message for message types.
+    ///
+    /// This does not take into account the cardinality or explicit default value of a field. See
+    /// [`Value::default_value_for_field`] for that.
+    pub fn default_value(&self) -> Value {
+        Value::default_value(self)
+    }
+
+    /// Returns `true` if this is one of the integer or floating point scalar types.
+    ///
+    /// Note that `bool` and enum types are not considered numeric.
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+
+    /// Returns `true` if this is one of the integer scalar types, for example `int32` or `fixed64`.
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            Kind::Int32
+                | Kind::Int64
+                | Kind::Uint32
+                | Kind::Uint64
+                | Kind::Sint32
+                | Kind::Sint64
+                | Kind::Fixed32
+                | Kind::Fixed64
+                | Kind::Sfixed32
+                | Kind::Sfixed64
+        )
+    }
+
+    /// Returns `true` if this is a numeric type which can represent negative values.
+    ///
+    /// This includes the `double` and `float` types.
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            Kind::Double
+                | Kind::Float
+                | Kind::Int32
+                | Kind::Int64
+                | Kind::Sint32
+                | Kind::Sint64
+                | Kind::Sfixed32
+                | Kind::Sfixed64
+        )
+    }
+
+    /// Returns `true` if this is the `double` or `float` type.
+    pub fn is_float(&self) -> bool {
+        matches!(self, Kind::Double | Kind::Float)
+    }
+
+    /// Gets the width in bits of the values of this type, if it is a numeric type, or `None` otherwise.
+    pub fn bit_width(&self) -> Option<u32> {
+        match self {
+            Kind::Float
+            | Kind::Int32
+            | Kind::Uint32
+            | Kind::Sint32
+            | Kind::Fixed32
+            | Kind::Sfixed32 => Some(32),
+            Kind::Double
+            | Kind::Int64
+            | Kind::Uint64
+            | Kind::Sint64
+            | Kind::Fixed64
+            | Kind::Sfixed64 => Some(64),
+            Kind::Bool | Kind::String | Kind::Bytes | Kind::Message(_) | Kind::Enum(_) => None,
+        }
+    }
+
     pub(crate) fn wire_type(&self) -> WireType {
         match self {
             Kind::Double | Kind::Fixed64 | Kind::Sfixed64 => WireType::SixtyFourBit,
@@ -854,6 +1236,93 @@ impl fmt::Debug for Kind {
     }
 }
 
+impl fmt::Display for Kind {
+    /// Formats this kind as it would be written in a `.proto` file, for example `int32` or a fully
+    /// qualified type name such as `my.package.MyMessage`.
+    ///
+    /// See [`DescriptorPool::get_kind_by_name`] for the reverse operation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl Kind {
+    pub(in crate::descriptor) fn try_get_by_name(
+        pool: &DescriptorPool,
+        name: &str,
+    ) -> Option<Kind> {
+        let kind = match name {
+            "double" => Some(Kind::Double),
+            "float" => Some(Kind::Float),
+            "int32" => Some(Kind::Int32),
+            "int64" => Some(Kind::Int64),
+            "uint32" => Some(Kind::Uint32),
+            "uint64" => Some(Kind::Uint64),
+            "sint32" => Some(Kind::Sint32),
+            "sint64" => Some(Kind::Sint64),
+            "fixed32" => Some(Kind::Fixed32),
+            "fixed64" => Some(Kind::Fixed64),
+            "sfixed32" => Some(Kind::Sfixed32),
+            "sfixed64" => Some(Kind::Sfixed64),
+            "bool" => Some(Kind::Bool),
+            "string" => Some(Kind::String),
+            "bytes" => Some(Kind::Bytes),
+            _ => None,
+        };
+        if kind.is_some() {
+            return kind;
+        }
+
+        let ty = pool.inner.type_map.get_by_name(name)?;
+        if ty.is_message() {
+            Some(Kind::Message(MessageDescriptor::new(pool.clone(), ty)))
+        } else {
+            Some(Kind::Enum(EnumDescriptor::new(pool.clone(), ty)))
+        }
+    }
+}
+
+impl fmt::Display for Cardinality {
+    /// Formats this cardinality as the corresponding label in a `.proto` file, for example
+    /// `optional`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cardinality::Optional => write!(f, "optional"),
+            Cardinality::Required => write!(f, "required"),
+            Cardinality::Repeated => write!(f, "repeated"),
+        }
+    }
+}
+
+impl FromStr for Cardinality {
+    type Err = ParseCardinalityError;
+
+    /// Parses a cardinality from the corresponding label in a `.proto` file, for example
+    /// `optional`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "optional" => Ok(Cardinality::Optional),
+            "required" => Ok(Cardinality::Required),
+            "repeated" => Ok(Cardinality::Repeated),
+            _ => Err(ParseCardinalityError {
+                value: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for ParseCardinalityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}' is not a valid cardinality, expected 'optional', 'required' or 'repeated'",
+            self.value
+        )
+    }
+}
+
+impl std::error::Error for ParseCardinalityError {}
+
 impl EnumDescriptor {
     pub(in crate::descriptor) fn new(pool: DescriptorPool, ty: TypeId) -> Self {
         debug_assert_eq!(ty.0, field_descriptor_proto::Type::Enum);
@@ -922,22 +1391,19 @@ impl EnumDescriptor {
 
     /// Gets a reference to the raw [`EnumDescriptorProto`] wrapped by this [`EnumDescriptor`].
     pub fn enum_descriptor_proto(&self) -> &EnumDescriptorProto {
-        let name = self.name();
         let inner = self.inner();
-        match inner.parent {
-            ParentKind::File => get_file_descriptor_proto(&self.pool, inner.file)
-                .enum_type
-                .iter()
-                .find(|extension| extension.name() == name)
-                .expect("extension not found"),
+        let enums = match inner.parent {
+            ParentKind::File => &get_file_descriptor_proto(&self.pool, inner.file).enum_type,
             ParentKind::Message {
                 index: message_index,
-            } => find_message_descriptor_proto(&self.pool, inner.file, message_index)
-                .enum_type
-                .iter()
-                .find(|extension| extension.name() == name)
-                .expect("extension not found"),
-        }
+            } => &find_message_descriptor_proto(&self.pool, inner.file, message_index).enum_type,
+        };
+        &enums[inner.proto_index]
+    }
+
+    /// Gets the index of the enum's proto within its parent's list of definitions.
+    pub(in crate::descriptor) fn proto_index(&self) -> usize {
+        self.inner().proto_index
     }
 
     /// Gets the default value for the enum type.
@@ -974,7 +1440,21 @@ impl EnumDescriptor {
         }
     }
 
+    /// Returns `true` if this enum has a value with the given number.
+    ///
+    /// This is equivalent to `self.get_value(number).is_some()`, but avoids cloning the descriptor.
+    pub fn contains_number(&self, number: i32) -> bool {
+        self.inner()
+            .values
+            .binary_search_by_key(&number, |v| v.number)
+            .is_ok()
+    }
+
     /// Gets an iterator yielding a [`EnumValueDescriptor`] for each value in this enum.
+    ///
+    /// The values are yielded in ascending order of their numbers, rather than in the order they are
+    /// declared. If the enum has multiple values with the same number (using the `allow_alias` option),
+    /// they are yielded in declaration order.
     pub fn values(&self) -> impl ExactSizeIterator<Item = EnumValueDescriptor> + '_ {
         (0..self.inner().values.len())
             .map(move |index| EnumValueDescriptor::new(self.clone(), to_index(index)))
@@ -1017,6 +1497,21 @@ impl EnumValueDescriptor {
         EnumValueDescriptor { parent, index }
     }
 
+    pub(in crate::descriptor) fn try_get_by_name(
+        pool: &DescriptorPool,
+        name: &str,
+    ) -> Option<Self> {
+        let name = name.strip_prefix('.').unwrap_or(name);
+        let &(enum_index, index) = pool.inner.type_map.enum_value_names.get(name)?;
+        Some(EnumValueDescriptor::new(
+            EnumDescriptor {
+                pool: pool.clone(),
+                index: enum_index,
+            },
+            index,
+        ))
+    }
+
     /// Gets a reference to the [`DescriptorPool`] this enum value is defined in.
     pub fn parent_pool(&self) -> &DescriptorPool {
         self.parent.parent_pool()
@@ -1044,12 +1539,12 @@ impl EnumValueDescriptor {
 
     /// Gets a reference to the raw [`EnumValueDescriptorProto`] wrapped by this [`EnumValueDescriptor`].
     pub fn enum_value_descriptor_proto(&self) -> &EnumValueDescriptorProto {
-        self.parent_enum()
-            .enum_descriptor_proto()
-            .value
-            .iter()
-            .find(|value| value.name() == self.name())
-            .expect("enum value not found")
+        &self.parent_enum().enum_descriptor_proto().value[self.enum_value_ty().proto_index]
+    }
+
+    /// Gets the index of the enum value's proto within its parent's list of definitions.
+    pub(in crate::descriptor) fn proto_index(&self) -> usize {
+        self.enum_value_ty().proto_index
     }
 
     /// Gets the number representing this enum value.
@@ -1057,6 +1552,29 @@ impl EnumValueDescriptor {
         self.enum_value_ty().number
     }
 
+    /// Returns `true` if this enum value is marked as deprecated.
+    pub fn is_deprecated(&self) -> bool {
+        self.enum_value_descriptor_proto()
+            .options
+            .as_ref()
+            .map_or(false, |options| options.deprecated())
+    }
+
+    /// Gets the options set on this enum value as a [`DynamicMessage`].
+    ///
+    /// Returns `None` if no options are set, or if the `google.protobuf.EnumValueOptions` message
+    /// type is not defined in the parent pool. Note that extension fields of the options message
+    /// are discarded when the descriptor is decoded, so only the built-in options are populated.
+    pub fn options(&self) -> Option<DynamicMessage> {
+        let options = self.enum_value_descriptor_proto().options.as_ref()?;
+        let desc = self
+            .parent_pool()
+            .get_message_by_name("google.protobuf.EnumValueOptions")?;
+        let mut message = DynamicMessage::new(desc);
+        message.transcode_from(options).ok()?;
+        Some(message)
+    }
+
     fn enum_value_ty(&self) -> &EnumValueDescriptorInner {
         &self.parent.inner().values[self.index as usize]
     }
@@ -1065,13 +1583,20 @@ impl EnumValueDescriptor {
 impl fmt::Debug for EnumValueDescriptor {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("EnumValueDescriptor")
-            .field("name", &self.number())
+            .field("name", &self.name())
             .field("full_name", &self.full_name())
             .field("number", &self.number())
             .finish()
     }
 }
 
+impl fmt::Display for EnumValueDescriptor {
+    /// Formats this value as its short name, for example `MY_VALUE`, as used in the text and JSON formats.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 impl OneofDescriptor {
     fn new(message: MessageDescriptor, index: OneofIndex) -> Self {
         OneofDescriptor { message, index }
@@ -1107,6 +1632,11 @@ impl OneofDescriptor {
         &self.parent_message().descriptor_proto().oneof_decl[self.index as usize]
     }
 
+    /// Gets the index of the oneof's proto within its parent's list of definitions.
+    pub(in crate::descriptor) fn proto_index(&self) -> usize {
+        self.index as usize
+    }
+
     /// Gets an iterator yielding a [`FieldDescriptor`] for each field of the parent message this oneof contains.
     pub fn fields(&self) -> impl ExactSizeIterator<Item = FieldDescriptor> + '_ {
         self.oneof_ty()
@@ -1118,6 +1648,19 @@ impl OneofDescriptor {
             })
     }
 
+    /// Gets the number of fields of the parent message this oneof contains.
+    pub fn field_count(&self) -> usize {
+        self.oneof_ty().fields.len()
+    }
+
+    /// Returns `true` if this is a synthetic oneof generated by the compiler to represent a
+    /// proto3 `optional` field.
+    ///
+    /// Synthetic oneofs always contain exactly one field.
+    pub fn is_synthetic(&self) -> bool {
+        self.oneof_ty().is_synthetic
+    }
+
     fn oneof_ty(&self) -> &OneofDescriptorInner {
         &self.message.inner().oneof_decls[self.index as usize]
     }
@@ -1129,16 +1672,114 @@ impl fmt::Debug for OneofDescriptor {
             .field("name", &self.name())
             .field("full_name", &self.full_name())
             .field("fields", &debug_fmt_iter(self.fields()))
+            .field("is_synthetic", &self.is_synthetic())
             .finish()
     }
 }
 
+impl WellKnownType {
+    /// Gets the fully qualified name of this type, for example `google.protobuf.Timestamp`.
+    pub fn full_name(&self) -> &'static str {
+        match self {
+            WellKnownType::Any => "google.protobuf.Any",
+            WellKnownType::Timestamp => "google.protobuf.Timestamp",
+            WellKnownType::Duration => "google.protobuf.Duration",
+            WellKnownType::Struct => "google.protobuf.Struct",
+            WellKnownType::Value => "google.protobuf.Value",
+            WellKnownType::ListValue => "google.protobuf.ListValue",
+            WellKnownType::FieldMask => "google.protobuf.FieldMask",
+            WellKnownType::Empty => "google.protobuf.Empty",
+            WellKnownType::DoubleValue => "google.protobuf.DoubleValue",
+            WellKnownType::FloatValue => "google.protobuf.FloatValue",
+            WellKnownType::Int64Value => "google.protobuf.Int64Value",
+            WellKnownType::UInt64Value => "google.protobuf.UInt64Value",
+            WellKnownType::Int32Value => "google.protobuf.Int32Value",
+            WellKnownType::UInt32Value => "google.protobuf.UInt32Value",
+            WellKnownType::BoolValue => "google.protobuf.BoolValue",
+            WellKnownType::StringValue => "google.protobuf.StringValue",
+            WellKnownType::BytesValue => "google.protobuf.BytesValue",
+        }
+    }
+
+    pub(crate) fn from_full_name(full_name: &str) -> Option<Self> {
+        match full_name {
+            "google.protobuf.Any" => Some(WellKnownType::Any),
+            "google.protobuf.Timestamp" => Some(WellKnownType::Timestamp),
+            "google.protobuf.Duration" => Some(WellKnownType::Duration),
+            "google.protobuf.Struct" => Some(WellKnownType::Struct),
+            "google.protobuf.Value" => Some(WellKnownType::Value),
+            "google.protobuf.ListValue" => Some(WellKnownType::ListValue),
+            "google.protobuf.FieldMask" => Some(WellKnownType::FieldMask),
+            "google.protobuf.Empty" => Some(WellKnownType::Empty),
+            "google.protobuf.DoubleValue" => Some(WellKnownType::DoubleValue),
+            "google.protobuf.FloatValue" => Some(WellKnownType::FloatValue),
+            "google.protobuf.Int64Value" => Some(WellKnownType::Int64Value),
+            "google.protobuf.UInt64Value" => Some(WellKnownType::UInt64Value),
+            "google.protobuf.Int32Value" => Some(WellKnownType::Int32Value),
+            "google.protobuf.UInt32Value" => Some(WellKnownType::UInt32Value),
+            "google.protobuf.BoolValue" => Some(WellKnownType::BoolValue),
+            "google.protobuf.StringValue" => Some(WellKnownType::StringValue),
+            "google.protobuf.BytesValue" => Some(WellKnownType::BytesValue),
+            _ => None,
+        }
+    }
+}
+
 impl TypeMap {
+    pub fn new_lazy() -> Self {
+        TypeMap {
+            lazy: true,
+            ..Default::default()
+        }
+    }
+
+    /// Creates an empty type map which indexes files in the same way as this one.
+    pub fn new_empty(&self) -> Self {
+        TypeMap {
+            lazy: self.lazy,
+            ..Default::default()
+        }
+    }
+
     pub fn shrink_to_fit(&mut self) {
         self.named_types.shrink_to_fit();
         self.messages.shrink_to_fit();
         self.enums.shrink_to_fit();
         self.extensions.shrink_to_fit();
+        self.extension_names.shrink_to_fit();
+        self.extension_numbers.shrink_to_fit();
+        self.enum_value_names.shrink_to_fit();
+    }
+
+    /// Gets the index of `message` in this type map, looking it up by name if it belongs to a
+    /// different pool.
+    fn resolve_message(&self, message: &MessageDescriptor) -> Option<MessageIndex> {
+        if ptr::eq(self, &message.pool.inner.type_map) {
+            Some(message.index)
+        } else {
+            match self.get_by_name(message.full_name()) {
+                Some(ty) if ty.is_message() => Some(ty.1),
+                _ => None,
+            }
+        }
+    }
+
+    /// Gets the full names of all messages, fields, oneofs, enums, enum values and extensions.
+    pub fn symbol_names(&self) -> impl Iterator<Item = &str> {
+        let messages = self.messages.iter().flat_map(|message| {
+            iter::once(&*message.full_name)
+                .chain(message.fields.values().map(|field| &*field.full_name))
+                .chain(message.oneof_decls.iter().map(|oneof| &*oneof.full_name))
+        });
+        let enums = self.enums.iter().flat_map(|enum_ty| {
+            iter::once(&*enum_ty.full_name)
+                .chain(enum_ty.values.iter().map(|value| &*value.full_name))
+        });
+        let extensions = self
+            .extensions
+            .iter()
+            .map(|extension| &*extension.field.full_name);
+        messages.chain(enums).chain(extensions)
     }
 
     pub fn try_get_by_name(&self, full_name: &str) -> Result<TypeId, DescriptorError> {
@@ -1217,6 +1858,45 @@ impl TypeMap {
     }
 }
 
+impl MessageDescriptorInner {
+    fn get_field_number_by_name(&self, name: &str) -> Option<u32> {
+        match &self.field_names {
+            Some(field_names) => field_names.names.get(name).copied(),
+            None => self
+                .fields
+                .iter()
+                .find(|(_, field)| &*field.name == name)
+                .map(|(&number, _)| number),
+        }
+    }
+
+    fn get_field_number_by_json_name(&self, json_name: &str) -> Option<u32> {
+        match &self.field_names {
+            Some(field_names) => field_names.json_names.get(json_name).copied(),
+            None => self
+                .fields
+                .iter()
+                .find(|(_, field)| &*field.json_name == json_name)
+                .map(|(&number, _)| number),
+        }
+    }
+}
+
+impl FieldNames {
+    fn new(fields: &BTreeMap<u32, FieldDescriptorInner>) -> Self {
+        FieldNames {
+            names: fields
+                .iter()
+                .map(|(&number, field)| (field.name.clone(), number))
+                .collect(),
+            json_names: fields
+                .iter()
+                .map(|(&number, field)| (field.json_name.clone(), number))
+                .collect(),
+        }
+    }
+}
+
 impl TypeId {
     pub fn new_message(index: MessageIndex) -> Self {
         TypeId(field_descriptor_proto::Type::Message, index)
@@ -1304,6 +1984,20 @@ impl ParentKind {
     }
 }
 
+/// Sorts `ranges` and merges any which overlap or are adjacent.
+fn coalesce_ranges(mut ranges: Vec<Range<u32>>) -> Vec<Range<u32>> {
+    ranges.sort_by_key(|range| range.start);
+
+    let mut result: Vec<Range<u32>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match result.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => result.push(range),
+        }
+    }
+    result
+}
+
 fn get_file_descriptor_proto(pool: &DescriptorPool, index: FileIndex) -> &FileDescriptorProto {
     &pool.inner.files[index as usize].raw
 }
@@ -1315,17 +2009,14 @@ fn find_message_descriptor_proto(
 ) -> &DescriptorProto {
     let message = pool.inner.type_map.get_message(index);
     match message.parent {
-        ParentKind::File => get_file_descriptor_proto(pool, file_index)
-            .message_type
-            .iter()
-            .find(|ty| ty.name() == parse_name(&message.full_name))
-            .expect("message not found"),
+        ParentKind::File => {
+            &get_file_descriptor_proto(pool, file_index).message_type[message.proto_index]
+        }
         ParentKind::Message {
             index: parent_index,
-        } => find_message_descriptor_proto(pool, file_index, parent_index)
-            .nested_type
-            .iter()
-            .find(|ty| ty.name() == parse_name(&message.full_name))
-            .expect("message not found"),
+        } => {
+            &find_message_descriptor_proto(pool, file_index, parent_index).nested_type
+                [message.proto_index]
+        }
     }
 }
@@ -1,10 +1,12 @@
 use prost_types::{
+    descriptor_proto,
     field_descriptor_proto::{self, Label, Type},
     DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
-    FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto, ServiceDescriptorProto,
+    FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto, OneofDescriptorProto,
+    ServiceDescriptorProto,
 };
 
-use crate::DescriptorPool;
+use crate::{DescriptorError, DescriptorPool, Kind, ReflectMessage, WellKnownType};
 
 #[test]
 fn resolve_service_name() {
@@ -267,6 +269,199 @@ fn reference_type_in_previously_added_file() {
     assert_eq!(field.kind().as_message().unwrap().parent_file(), file1);
 }
 
+#[test]
+fn file_descriptor_set_dependency_order() {
+    let file1 = FileDescriptorProto {
+        name: Some("myfile1.proto".to_owned()),
+        package: Some("my.package1".to_owned()),
+        syntax: Some("proto3".to_owned()),
+        message_type: vec![DescriptorProto {
+            name: Some("MyFieldMessage".to_owned()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let file2 = FileDescriptorProto {
+        name: Some("myfile2.proto".to_owned()),
+        package: Some("my.package2".to_owned()),
+        syntax: Some("proto3".to_owned()),
+        dependency: vec!["myfile1.proto".to_owned()],
+        message_type: vec![DescriptorProto {
+            name: Some("MyMessage".to_owned()),
+            field: vec![FieldDescriptorProto {
+                name: Some("my_field".to_owned()),
+                number: Some(1),
+                label: Some(Label::Optional as i32),
+                r#type: Some(Type::Message as i32),
+                type_name: Some(".my.package1.MyFieldMessage".to_owned()),
+                json_name: Some("myfield".to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_protos(vec![file2.clone(), file1.clone()])
+        .unwrap();
+    assert_eq!(
+        pool.file_descriptor_protos().collect::<Vec<_>>(),
+        vec![&file2, &file1]
+    );
+
+    let file_descriptor_set = pool.file_descriptor_set();
+    assert_eq!(file_descriptor_set.file, vec![file1, file2]);
+
+    let roundtripped = DescriptorPool::decode(pool.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(roundtripped.file_descriptor_set(), file_descriptor_set);
+}
+
+#[test]
+fn server_reflection_queries() {
+    let file1 = FileDescriptorProto {
+        name: Some("myfile1.proto".to_owned()),
+        package: Some("my.package1".to_owned()),
+        syntax: Some("proto2".to_owned()),
+        message_type: vec![DescriptorProto {
+            name: Some("MyFieldMessage".to_owned()),
+            extension_range: vec![descriptor_proto::ExtensionRange {
+                start: Some(100),
+                end: Some(200),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        enum_type: vec![EnumDescriptorProto {
+            name: Some("MyEnum".to_owned()),
+            value: vec![EnumValueDescriptorProto {
+                name: Some("MY_VALUE".to_owned()),
+                number: Some(0),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let file2 = FileDescriptorProto {
+        name: Some("myfile2.proto".to_owned()),
+        package: Some("my.package2".to_owned()),
+        syntax: Some("proto2".to_owned()),
+        dependency: vec!["myfile1.proto".to_owned()],
+        message_type: vec![DescriptorProto {
+            name: Some("MyMessage".to_owned()),
+            field: vec![FieldDescriptorProto {
+                name: Some("my_field".to_owned()),
+                number: Some(1),
+                label: Some(Label::Optional as i32),
+                r#type: Some(Type::Message as i32),
+                type_name: Some(".my.package1.MyFieldMessage".to_owned()),
+                json_name: Some("myField".to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        service: vec![ServiceDescriptorProto {
+            name: Some("MyService".to_owned()),
+            method: vec![MethodDescriptorProto {
+                name: Some("my_method".to_owned()),
+                input_type: Some(".my.package2.MyMessage".to_owned()),
+                output_type: Some(".my.package2.MyMessage".to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        extension: vec![FieldDescriptorProto {
+            name: Some("my_extension".to_owned()),
+            number: Some(100),
+            label: Some(Label::Optional as i32),
+            r#type: Some(Type::Int32 as i32),
+            extendee: Some(".my.package1.MyFieldMessage".to_owned()),
+            json_name: Some("myExtension".to_owned()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let pool = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: vec![file1.clone(), file2.clone()],
+    })
+    .unwrap();
+
+    let file1_only = Some(vec![file1.clone()]);
+    let file2_with_deps = Some(vec![file2, file1]);
+
+    assert_eq!(pool.file_by_filename("myfile1.proto"), file1_only);
+    assert_eq!(pool.file_by_filename("myfile2.proto"), file2_with_deps);
+    assert_eq!(pool.file_by_filename("notfound.proto"), None);
+
+    assert_eq!(
+        pool.file_containing_symbol("my.package1.MyFieldMessage"),
+        file1_only
+    );
+    assert_eq!(
+        pool.file_containing_symbol("my.package1.MyEnum"),
+        file1_only
+    );
+    assert_eq!(
+        pool.file_containing_symbol("my.package1.MY_VALUE"),
+        file1_only
+    );
+    assert_eq!(
+        pool.file_containing_symbol(".my.package2.MyMessage"),
+        file2_with_deps
+    );
+    assert_eq!(
+        pool.file_containing_symbol("my.package2.MyMessage.my_field"),
+        file2_with_deps
+    );
+    assert_eq!(
+        pool.file_containing_symbol("my.package2.MyService"),
+        file2_with_deps
+    );
+    assert_eq!(
+        pool.file_containing_symbol("my.package2.MyService.my_method"),
+        file2_with_deps
+    );
+    assert_eq!(
+        pool.file_containing_symbol("my.package2.my_extension"),
+        file2_with_deps
+    );
+    assert_eq!(pool.file_containing_symbol("my.package2.NotFound"), None);
+    assert_eq!(
+        pool.file_containing_symbol("my.package2.MyMessage.not_found"),
+        None
+    );
+
+    assert_eq!(
+        pool.file_containing_extension("my.package1.MyFieldMessage", 100),
+        file2_with_deps
+    );
+    assert_eq!(
+        pool.file_containing_extension("my.package1.MyFieldMessage", 101),
+        None
+    );
+
+    let mut symbol_names: Vec<&str> = pool.all_symbol_names().collect();
+    symbol_names.sort_unstable();
+    assert_eq!(
+        symbol_names,
+        vec![
+            "my.package1.MY_VALUE",
+            "my.package1.MyEnum",
+            "my.package1.MyFieldMessage",
+            "my.package2.MyMessage",
+            "my.package2.MyMessage.my_field",
+            "my.package2.MyService",
+            "my.package2.MyService.my_method",
+            "my.package2.my_extension",
+        ]
+    );
+    for name in symbol_names {
+        assert!(pool.file_containing_symbol(name).is_some(), "{}", name);
+    }
+}
+
 #[test]
 fn add_duplicate_file() {
     let file_descriptor_set = FileDescriptorSet {
@@ -328,6 +523,71 @@ fn add_conflicting_duplicate_file() {
     );
 }
 
+#[test]
+fn from_file_descriptor_sets() {
+    let dependency = FileDescriptorProto {
+        name: Some("dependency.proto".to_owned()),
+        package: Some("my.package".to_owned()),
+        syntax: Some("proto3".to_owned()),
+        message_type: vec![DescriptorProto {
+            name: Some("MyFieldMessage".to_owned()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let file = FileDescriptorProto {
+        name: Some("myfile.proto".to_owned()),
+        package: Some("my.package".to_owned()),
+        syntax: Some("proto3".to_owned()),
+        dependency: vec!["dependency.proto".to_owned()],
+        message_type: vec![DescriptorProto {
+            name: Some("MyMessage".to_owned()),
+            field: vec![FieldDescriptorProto {
+                name: Some("my_field".to_owned()),
+                number: Some(1),
+                label: Some(Label::Optional as i32),
+                r#type: Some(Type::Message as i32),
+                type_name: Some(".my.package.MyFieldMessage".to_owned()),
+                json_name: Some("myField".to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let pool = DescriptorPool::from_file_descriptor_sets(vec![
+        FileDescriptorSet {
+            file: vec![file.clone()],
+        },
+        FileDescriptorSet {
+            file: vec![dependency.clone()],
+        },
+        FileDescriptorSet {
+            file: vec![dependency.clone()],
+        },
+    ])
+    .unwrap();
+    assert_eq!(pool.file_descriptor_protos().len(), 2);
+    assert!(pool.get_message_by_name("my.package.MyMessage").is_some());
+
+    let mut conflicting = dependency.clone();
+    conflicting.message_type[0].name = Some("MyOtherMessage".to_owned());
+    let err = DescriptorPool::from_file_descriptor_sets(vec![
+        FileDescriptorSet {
+            file: vec![file, dependency],
+        },
+        FileDescriptorSet {
+            file: vec![conflicting],
+        },
+    ])
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "a conflicting file named 'dependency.proto' is already added. Duplicate files must match exactly"
+    );
+}
+
 #[test]
 fn add_file_rollback_on_error() {
     let bad_file_descriptor_set = FileDescriptorSet {
@@ -359,7 +619,12 @@ fn add_file_rollback_on_error() {
         .unwrap_err();
     assert_eq!(
         err.to_string(),
-        "the message or enum type 'my.package.NopeMessage' was not found"
+        "the message or enum type 'my.package.NopeMessage' was not found (in file 'myfile.proto', service my.package.MyService, method my_method)"
+    );
+    assert_eq!(err.file_name(), Some("myfile.proto"));
+    assert_eq!(
+        err.context_path(),
+        Some("service my.package.MyService, method my_method")
     );
     assert_eq!(pool.file_descriptor_protos().count(), 0);
     assert_eq!(pool.get_message_by_name(".my.package.MyMessage"), None);
@@ -420,7 +685,11 @@ fn service_method_type_not_message() {
     let err = DescriptorPool::from_file_descriptor_set(file_descriptor_set).unwrap_err();
     assert_eq!(
         err.to_string(),
-        "invalid type '.my.package.MyMessage' for method 'my.package.MyService.my_method'"
+        "invalid type '.my.package.MyMessage' for method 'my.package.MyService.my_method' (in file 'myfile.proto')"
+    );
+    assert_eq!(
+        err.context_path(),
+        Some("service my.package.MyService, method my_method")
     );
 }
 
@@ -455,6 +724,1165 @@ fn extension_extendee_type_not_message() {
     let err = DescriptorPool::from_file_descriptor_set(file_descriptor_set).unwrap_err();
     assert_eq!(
         err.to_string(),
-        "invalid type 'my.package.MyMessage' for extension 'my.package.my_extension'"
+        "invalid type 'my.package.MyMessage' for extension 'my.package.my_extension' (in file 'myfile.proto')"
+    );
+}
+
+#[test]
+fn message_field_type_not_found() {
+    let file_descriptor_set = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("myfile.proto".to_owned()),
+            package: Some("my.package".to_owned()),
+            syntax: Some("proto3".to_owned()),
+            message_type: vec![DescriptorProto {
+                name: Some("MyMessage".to_owned()),
+                field: vec![FieldDescriptorProto {
+                    name: Some("my_field".to_owned()),
+                    number: Some(1),
+                    label: Some(Label::Optional as i32),
+                    r#type: Some(field_descriptor_proto::Type::Message as i32),
+                    type_name: Some("NopeMessage".to_owned()),
+                    json_name: Some("myField".to_owned()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    };
+
+    let err = DescriptorPool::from_file_descriptor_set(file_descriptor_set).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the message or enum type 'NopeMessage' was not found (in file 'myfile.proto', message my.package.MyMessage, field my_field)"
+    );
+    assert_eq!(err.file_name(), Some("myfile.proto"));
+    assert_eq!(
+        err.context_path(),
+        Some("message my.package.MyMessage, field my_field")
+    );
+}
+
+fn build_message(message: DescriptorProto) -> Result<DescriptorPool, DescriptorError> {
+    DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("myfile.proto".to_owned()),
+            package: Some("my.package".to_owned()),
+            syntax: Some("proto2".to_owned()),
+            message_type: vec![DescriptorProto {
+                name: Some("MyMessage".to_owned()),
+                ..message
+            }],
+            ..Default::default()
+        }],
+    })
+}
+
+fn int32_field(name: &str, number: i32) -> FieldDescriptorProto {
+    FieldDescriptorProto {
+        name: Some(name.to_owned()),
+        number: Some(number),
+        label: Some(Label::Optional as i32),
+        r#type: Some(Type::Int32 as i32),
+        json_name: Some(name.to_owned()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn message_field_reserved() {
+    let reserved_range = |start, end| descriptor_proto::ReservedRange {
+        start: Some(start),
+        end: Some(end),
+    };
+
+    let cases = vec![
+        (
+            DescriptorProto {
+                field: vec![int32_field("my_field", 3)],
+                reserved_range: vec![reserved_range(2, 4)],
+                ..Default::default()
+            },
+            Some("the field 'my_field' of message 'my.package.MyMessage' uses the reserved number 3 (in file 'myfile.proto')"),
+        ),
+        (
+            DescriptorProto {
+                field: vec![int32_field("my_field", 4)],
+                reserved_range: vec![reserved_range(2, 4)],
+                reserved_name: vec!["my_field".to_owned()],
+                ..Default::default()
+            },
+            Some("the field name 'my_field' is reserved in message 'my.package.MyMessage' (in file 'myfile.proto')"),
+        ),
+        (
+            DescriptorProto {
+                field: vec![int32_field("my_field", 4)],
+                reserved_range: vec![reserved_range(2, 4)],
+                reserved_name: vec!["other_field".to_owned()],
+                ..Default::default()
+            },
+            None,
+        ),
+    ];
+
+    for (message, expected) in cases {
+        assert_eq!(
+            build_message(message)
+                .err()
+                .map(|err| err.to_string())
+                .as_deref(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn message_extension_ranges() {
+    let extension_range = |start, end| descriptor_proto::ExtensionRange {
+        start: Some(start),
+        end: Some(end),
+        ..Default::default()
+    };
+
+    let cases = vec![
+        (
+            DescriptorProto {
+                field: vec![int32_field("my_field", 100)],
+                extension_range: vec![extension_range(100, 200)],
+                ..Default::default()
+            },
+            Some("the field 'my_field' of message 'my.package.MyMessage' uses the number 100, which is in an extension range (in file 'myfile.proto')"),
+        ),
+        (
+            DescriptorProto {
+                extension_range: vec![extension_range(150, 300), extension_range(100, 200)],
+                ..Default::default()
+            },
+            Some("the extension ranges 100 to 199 and 150 to 299 of message 'my.package.MyMessage' overlap (in file 'myfile.proto')"),
+        ),
+        (
+            DescriptorProto {
+                field: vec![int32_field("my_field", 99)],
+                extension_range: vec![extension_range(200, 300), extension_range(100, 200)],
+                ..Default::default()
+            },
+            None,
+        ),
+    ];
+
+    for (message, expected) in cases {
+        assert_eq!(
+            build_message(message)
+                .err()
+                .map(|err| err.to_string())
+                .as_deref(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn synthetic_oneof() {
+    let file_descriptor_set = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("myfile.proto".to_owned()),
+            package: Some("my.package".to_owned()),
+            syntax: Some("proto3".to_owned()),
+            message_type: vec![DescriptorProto {
+                name: Some("MyMessage".to_owned()),
+                field: vec![
+                    FieldDescriptorProto {
+                        name: Some("real_field".to_owned()),
+                        number: Some(1),
+                        label: Some(Label::Optional as i32),
+                        r#type: Some(Type::Int32 as i32),
+                        json_name: Some("realField".to_owned()),
+                        oneof_index: Some(0),
+                        ..Default::default()
+                    },
+                    FieldDescriptorProto {
+                        name: Some("optional_field".to_owned()),
+                        number: Some(2),
+                        label: Some(Label::Optional as i32),
+                        r#type: Some(Type::Int32 as i32),
+                        json_name: Some("optionalField".to_owned()),
+                        oneof_index: Some(1),
+                        proto3_optional: Some(true),
+                        ..Default::default()
+                    },
+                ],
+                oneof_decl: vec![
+                    OneofDescriptorProto {
+                        name: Some("real_oneof".to_owned()),
+                        ..Default::default()
+                    },
+                    OneofDescriptorProto {
+                        name: Some("_optional_field".to_owned()),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    };
+
+    let descriptor_pool = DescriptorPool::from_file_descriptor_set(file_descriptor_set).unwrap();
+    let message = descriptor_pool
+        .get_message_by_name("my.package.MyMessage")
+        .unwrap();
+
+    let real_field = message.get_field_by_name("real_field").unwrap();
+    assert!(!real_field.containing_oneof().unwrap().is_synthetic());
+    assert_eq!(
+        real_field.real_containing_oneof().unwrap().name(),
+        "real_oneof"
+    );
+
+    let optional_field = message.get_field_by_name("optional_field").unwrap();
+    assert!(optional_field.supports_presence());
+    assert!(optional_field.containing_oneof().unwrap().is_synthetic());
+    assert_eq!(optional_field.containing_oneof().unwrap().field_count(), 1);
+    assert_eq!(optional_field.real_containing_oneof(), None);
+}
+
+#[test]
+fn well_known_type() {
+    let file_descriptor_set = FileDescriptorSet {
+        file: vec![
+            FileDescriptorProto {
+                name: Some("google/protobuf/timestamp.proto".to_owned()),
+                package: Some("google.protobuf".to_owned()),
+                syntax: Some("proto3".to_owned()),
+                message_type: vec![
+                    DescriptorProto {
+                        name: Some("Timestamp".to_owned()),
+                        ..Default::default()
+                    },
+                    DescriptorProto {
+                        name: Some("NotWellKnown".to_owned()),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+            FileDescriptorProto {
+                name: Some("myfile.proto".to_owned()),
+                package: Some("my.package".to_owned()),
+                syntax: Some("proto3".to_owned()),
+                message_type: vec![DescriptorProto {
+                    name: Some("Timestamp".to_owned()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        ],
+    };
+
+    let pool = DescriptorPool::from_file_descriptor_set(file_descriptor_set).unwrap();
+
+    let timestamp = pool
+        .get_message_by_name("google.protobuf.Timestamp")
+        .unwrap();
+    assert!(timestamp.is_well_known_type());
+    assert_eq!(timestamp.well_known_type(), Some(WellKnownType::Timestamp));
+    assert_eq!(
+        WellKnownType::Timestamp.full_name(),
+        "google.protobuf.Timestamp"
+    );
+
+    let not_well_known = pool
+        .get_message_by_name("google.protobuf.NotWellKnown")
+        .unwrap();
+    assert!(!not_well_known.is_well_known_type());
+    assert_eq!(not_well_known.well_known_type(), None);
+
+    let my_timestamp = pool.get_message_by_name("my.package.Timestamp").unwrap();
+    assert!(!my_timestamp.is_well_known_type());
+    assert_eq!(my_timestamp.well_known_type(), None);
+}
+
+#[test]
+fn enum_values_sorted_by_number() {
+    let file_descriptor_set = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("myfile.proto".to_owned()),
+            package: Some("my.package".to_owned()),
+            syntax: Some("proto3".to_owned()),
+            enum_type: vec![EnumDescriptorProto {
+                name: Some("MyEnum".to_owned()),
+                value: vec![
+                    EnumValueDescriptorProto {
+                        name: Some("ZERO".to_owned()),
+                        number: Some(0),
+                        ..Default::default()
+                    },
+                    EnumValueDescriptorProto {
+                        name: Some("FIVE".to_owned()),
+                        number: Some(5),
+                        ..Default::default()
+                    },
+                    EnumValueDescriptorProto {
+                        name: Some("MINUS_ONE".to_owned()),
+                        number: Some(-1),
+                        ..Default::default()
+                    },
+                    EnumValueDescriptorProto {
+                        name: Some("TWO".to_owned()),
+                        number: Some(2),
+                        ..Default::default()
+                    },
+                    EnumValueDescriptorProto {
+                        name: Some("ALSO_TWO".to_owned()),
+                        number: Some(2),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    };
+
+    let pool = DescriptorPool::from_file_descriptor_set(file_descriptor_set).unwrap();
+    let enum_ty = pool.get_enum_by_name("my.package.MyEnum").unwrap();
+
+    assert_eq!(
+        enum_ty
+            .values()
+            .map(|v| v.name().to_owned())
+            .collect::<Vec<_>>(),
+        vec!["MINUS_ONE", "ZERO", "TWO", "ALSO_TWO", "FIVE"]
     );
+    assert_eq!(enum_ty.default_value().name(), "ZERO");
+}
+
+#[test]
+fn kind_numeric_classifiers() {
+    assert!(Kind::Sint32.is_numeric());
+    assert!(Kind::Sint32.is_integer());
+    assert!(Kind::Sint32.is_signed());
+    assert!(!Kind::Sint32.is_float());
+    assert_eq!(Kind::Sint32.bit_width(), Some(32));
+
+    assert!(Kind::Fixed64.is_integer());
+    assert!(!Kind::Fixed64.is_signed());
+    assert_eq!(Kind::Fixed64.bit_width(), Some(64));
+
+    assert!(Kind::Float.is_numeric());
+    assert!(!Kind::Float.is_integer());
+    assert!(Kind::Float.is_signed());
+    assert!(Kind::Float.is_float());
+    assert_eq!(Kind::Float.bit_width(), Some(32));
+
+    for kind in [Kind::Bool, Kind::String, Kind::Bytes] {
+        assert!(!kind.is_numeric());
+        assert!(!kind.is_integer());
+        assert!(!kind.is_signed());
+        assert!(!kind.is_float());
+        assert_eq!(kind.bit_width(), None);
+    }
+}
+
+#[test]
+fn to_proto_source() {
+    let file_descriptor_set = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("myfile.proto".to_owned()),
+            package: Some("my.package".to_owned()),
+            syntax: Some("proto3".to_owned()),
+            message_type: vec![DescriptorProto {
+                name: Some("MyMessage".to_owned()),
+                field: vec![
+                    FieldDescriptorProto {
+                        name: Some("my_map".to_owned()),
+                        number: Some(1),
+                        label: Some(Label::Repeated as i32),
+                        r#type: Some(Type::Message as i32),
+                        type_name: Some(".my.package.MyMessage.MyMapEntry".to_owned()),
+                        json_name: Some("myMap".to_owned()),
+                        ..Default::default()
+                    },
+                    FieldDescriptorProto {
+                        name: Some("my_enum".to_owned()),
+                        number: Some(2),
+                        label: Some(Label::Repeated as i32),
+                        r#type: Some(Type::Enum as i32),
+                        type_name: Some(".my.package.MyMessage.MyEnum".to_owned()),
+                        json_name: Some("myEnum".to_owned()),
+                        options: Some(prost_types::FieldOptions {
+                            packed: Some(false),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    },
+                    FieldDescriptorProto {
+                        name: Some("my_oneof_field".to_owned()),
+                        number: Some(3),
+                        label: Some(Label::Optional as i32),
+                        r#type: Some(Type::String as i32),
+                        json_name: Some("myOneofField".to_owned()),
+                        oneof_index: Some(0),
+                        ..Default::default()
+                    },
+                    FieldDescriptorProto {
+                        name: Some("my_optional".to_owned()),
+                        number: Some(4),
+                        label: Some(Label::Optional as i32),
+                        r#type: Some(Type::Int32 as i32),
+                        json_name: Some("myOptional".to_owned()),
+                        oneof_index: Some(1),
+                        proto3_optional: Some(true),
+                        ..Default::default()
+                    },
+                ],
+                nested_type: vec![DescriptorProto {
+                    name: Some("MyMapEntry".to_owned()),
+                    field: vec![
+                        FieldDescriptorProto {
+                            name: Some("key".to_owned()),
+                            number: Some(1),
+                            label: Some(Label::Optional as i32),
+                            r#type: Some(Type::String as i32),
+                            json_name: Some("key".to_owned()),
+                            ..Default::default()
+                        },
+                        FieldDescriptorProto {
+                            name: Some("value".to_owned()),
+                            number: Some(2),
+                            label: Some(Label::Optional as i32),
+                            r#type: Some(Type::Message as i32),
+                            type_name: Some(".my.package.MyMessage".to_owned()),
+                            json_name: Some("value".to_owned()),
+                            ..Default::default()
+                        },
+                    ],
+                    options: Some(prost_types::MessageOptions {
+                        map_entry: Some(true),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }],
+                enum_type: vec![EnumDescriptorProto {
+                    name: Some("MyEnum".to_owned()),
+                    value: vec![EnumValueDescriptorProto {
+                        name: Some("MY_VALUE".to_owned()),
+                        number: Some(0),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                oneof_decl: vec![
+                    OneofDescriptorProto {
+                        name: Some("my_oneof".to_owned()),
+                        ..Default::default()
+                    },
+                    OneofDescriptorProto {
+                        name: Some("_my_optional".to_owned()),
+                        ..Default::default()
+                    },
+                ],
+                reserved_range: vec![descriptor_proto::ReservedRange {
+                    start: Some(9),
+                    end: Some(12),
+                }],
+                reserved_name: vec!["foo".to_owned()],
+                ..Default::default()
+            }],
+            service: vec![ServiceDescriptorProto {
+                name: Some("MyService".to_owned()),
+                method: vec![MethodDescriptorProto {
+                    name: Some("MyMethod".to_owned()),
+                    input_type: Some(".my.package.MyMessage".to_owned()),
+                    output_type: Some(".my.package.MyMessage".to_owned()),
+                    server_streaming: Some(true),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    };
+
+    let pool = DescriptorPool::from_file_descriptor_set(file_descriptor_set).unwrap();
+    let file = pool.get_file_by_name("myfile.proto").unwrap();
+
+    assert_eq!(
+        file.to_proto_source(),
+        r#"syntax = "proto3";
+
+package my.package;
+
+message MyMessage {
+  map<string, .my.package.MyMessage> my_map = 1;
+  repeated .my.package.MyMessage.MyEnum my_enum = 2 [packed = false];
+  oneof my_oneof {
+    string my_oneof_field = 3;
+  }
+  optional int32 my_optional = 4;
+  enum MyEnum {
+    MY_VALUE = 0;
+  }
+  reserved 9 to 11;
+  reserved "foo";
+}
+
+service MyService {
+  rpc MyMethod(.my.package.MyMessage) returns (stream .my.package.MyMessage);
+}
+"#
+    );
+}
+
+#[test]
+fn to_proto_source_group_extension() {
+    let group_extension = |name: &str, number: i32| FieldDescriptorProto {
+        name: Some(name.to_lowercase()),
+        number: Some(number),
+        label: Some(Label::Optional as i32),
+        r#type: Some(Type::Group as i32),
+        type_name: Some(format!(".my.package.{}", name)),
+        extendee: Some(".my.package.Extendee".to_owned()),
+        json_name: Some(name.to_lowercase()),
+        ..Default::default()
+    };
+    let group_type = |name: &str, field: &str| DescriptorProto {
+        name: Some(name.to_owned()),
+        field: vec![FieldDescriptorProto {
+            name: Some(field.to_owned()),
+            number: Some(1),
+            label: Some(Label::Optional as i32),
+            r#type: Some(Type::Int32 as i32),
+            json_name: Some(field.to_owned()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let file_descriptor_set = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("myfile.proto".to_owned()),
+            package: Some("my.package".to_owned()),
+            syntax: Some("proto2".to_owned()),
+            message_type: vec![
+                DescriptorProto {
+                    name: Some("Extendee".to_owned()),
+                    extension_range: vec![descriptor_proto::ExtensionRange {
+                        start: Some(100),
+                        end: Some(536_870_912),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+                DescriptorProto {
+                    name: Some("Scope".to_owned()),
+                    nested_type: vec![group_type("NestedGroup", "b")],
+                    extension: vec![FieldDescriptorProto {
+                        type_name: Some(".my.package.Scope.NestedGroup".to_owned()),
+                        ..group_extension("NestedGroup", 101)
+                    }],
+                    ..Default::default()
+                },
+                group_type("MyGroup", "a"),
+            ],
+            extension: vec![group_extension("MyGroup", 100)],
+            ..Default::default()
+        }],
+    };
+
+    let pool = DescriptorPool::from_file_descriptor_set(file_descriptor_set).unwrap();
+    let file = pool.get_file_by_name("myfile.proto").unwrap();
+
+    assert_eq!(
+        file.to_proto_source(),
+        r#"syntax = "proto2";
+
+package my.package;
+
+message Extendee {
+  extensions 100 to max;
+}
+
+message Scope {
+  extend .my.package.Extendee {
+    optional group NestedGroup = 101 {
+      optional int32 b = 1;
+    }
+  }
+}
+
+extend .my.package.Extendee {
+  optional group MyGroup = 100 {
+    optional int32 a = 1;
+  }
+}
+"#
+    );
+}
+
+#[test]
+fn enum_value_options() {
+    let file_descriptor_set = FileDescriptorSet {
+        file: vec![
+            FileDescriptorProto {
+                name: Some("google/protobuf/descriptor.proto".to_owned()),
+                package: Some("google.protobuf".to_owned()),
+                message_type: vec![DescriptorProto {
+                    name: Some("EnumValueOptions".to_owned()),
+                    field: vec![FieldDescriptorProto {
+                        name: Some("deprecated".to_owned()),
+                        number: Some(1),
+                        label: Some(Label::Optional as i32),
+                        r#type: Some(Type::Bool as i32),
+                        json_name: Some("deprecated".to_owned()),
+                        default_value: Some("false".to_owned()),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            FileDescriptorProto {
+                name: Some("myfile.proto".to_owned()),
+                package: Some("my.package".to_owned()),
+                syntax: Some("proto3".to_owned()),
+                enum_type: vec![EnumDescriptorProto {
+                    name: Some("MyEnum".to_owned()),
+                    value: vec![
+                        EnumValueDescriptorProto {
+                            name: Some("DEFAULT".to_owned()),
+                            number: Some(0),
+                            ..Default::default()
+                        },
+                        EnumValueDescriptorProto {
+                            name: Some("OLD".to_owned()),
+                            number: Some(1),
+                            options: Some(prost_types::EnumValueOptions {
+                                deprecated: Some(true),
+                                ..Default::default()
+                            }),
+                        },
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        ],
+    };
+
+    let pool = DescriptorPool::from_file_descriptor_set(file_descriptor_set).unwrap();
+    let enum_desc = pool.get_enum_by_name("my.package.MyEnum").unwrap();
+
+    let default = enum_desc.get_value_by_name("DEFAULT").unwrap();
+    assert!(!default.is_deprecated());
+    assert_eq!(default.options(), None);
+
+    let old = enum_desc.get_value_by_name("OLD").unwrap();
+    assert!(old.is_deprecated());
+    let options = old.options().unwrap();
+    assert_eq!(
+        options.descriptor().full_name(),
+        "google.protobuf.EnumValueOptions"
+    );
+    assert_eq!(
+        options.get_field_by_name("deprecated").unwrap().as_bool(),
+        Some(true)
+    );
+}
+
+#[test]
+fn with_file_descriptor_proto_mut() {
+    let file_descriptor_set = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("myfile.proto".to_owned()),
+            package: Some("my.package".to_owned()),
+            syntax: Some("proto2".to_owned()),
+            message_type: vec![DescriptorProto {
+                name: Some("MyMessage".to_owned()),
+                field: vec![FieldDescriptorProto {
+                    name: Some("my_field".to_owned()),
+                    number: Some(1),
+                    label: Some(Label::Repeated as i32),
+                    r#type: Some(Type::Int32 as i32),
+                    json_name: Some("myField".to_owned()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    };
+
+    let mut pool = DescriptorPool::from_file_descriptor_set(file_descriptor_set).unwrap();
+    let old_field = pool
+        .get_message_by_name("my.package.MyMessage")
+        .unwrap()
+        .get_field_by_name("my_field")
+        .unwrap();
+    assert!(!old_field.is_packed());
+
+    let modified = pool
+        .with_file_descriptor_proto_mut("myfile.proto", |file| {
+            let field = &mut file.message_type[0].field[0];
+            field.json_name = Some("renamedField".to_owned());
+            field.options = Some(prost_types::FieldOptions {
+                packed: Some(true),
+                ..Default::default()
+            });
+        })
+        .unwrap();
+    assert!(modified);
+
+    let message = pool.get_message_by_name("my.package.MyMessage").unwrap();
+    let field = message.get_field_by_json_name("renamedField").unwrap();
+    assert_eq!(field.name(), "my_field");
+    assert!(field.is_packed());
+    assert!(message.get_field_by_json_name("myField").is_none());
+
+    assert_eq!(old_field.json_name(), "myField");
+    assert!(!old_field.is_packed());
+
+    assert!(!pool
+        .with_file_descriptor_proto_mut("otherfile.proto", |_| unreachable!())
+        .unwrap());
+
+    let err = pool
+        .with_file_descriptor_proto_mut("myfile.proto", |file| {
+            file.message_type[0].field[0].r#type = Some(Type::Message as i32);
+        })
+        .unwrap_err();
+    assert!(err.to_string().contains("my_field"), "{}", err);
+    assert!(pool
+        .get_message_by_name("my.package.MyMessage")
+        .unwrap()
+        .get_field_by_json_name("renamedField")
+        .is_some());
+}
+
+#[test]
+fn comments() {
+    use prost_types::{source_code_info::Location, SourceCodeInfo};
+
+    let location = |path: Vec<i32>, leading: &str| Location {
+        path,
+        span: vec![0, 0, 0],
+        leading_comments: Some(leading.to_owned()),
+        trailing_comments: None,
+        leading_detached_comments: vec![],
+    };
+
+    let file_descriptor_set = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("myfile.proto".to_owned()),
+            package: Some("my.package".to_owned()),
+            syntax: Some("proto3".to_owned()),
+            message_type: vec![DescriptorProto {
+                name: Some("MyMessage".to_owned()),
+                field: vec![
+                    FieldDescriptorProto {
+                        name: Some("foo".to_owned()),
+                        number: Some(1),
+                        label: Some(Label::Optional as i32),
+                        r#type: Some(Type::Int32 as i32),
+                        json_name: Some("foo".to_owned()),
+                        ..Default::default()
+                    },
+                    FieldDescriptorProto {
+                        name: Some("bar".to_owned()),
+                        number: Some(2),
+                        label: Some(Label::Optional as i32),
+                        r#type: Some(Type::Int32 as i32),
+                        json_name: Some("bar".to_owned()),
+                        ..Default::default()
+                    },
+                ],
+                enum_type: vec![EnumDescriptorProto {
+                    name: Some("MyEnum".to_owned()),
+                    value: vec![
+                        EnumValueDescriptorProto {
+                            name: Some("OTHER".to_owned()),
+                            number: Some(1),
+                            ..Default::default()
+                        },
+                        EnumValueDescriptorProto {
+                            name: Some("DEFAULT".to_owned()),
+                            number: Some(0),
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            service: vec![ServiceDescriptorProto {
+                name: Some("MyService".to_owned()),
+                method: vec![MethodDescriptorProto {
+                    name: Some("MyMethod".to_owned()),
+                    input_type: Some(".my.package.MyMessage".to_owned()),
+                    output_type: Some(".my.package.MyMessage".to_owned()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            source_code_info: Some(SourceCodeInfo {
+                location: vec![
+                    Location {
+                        path: vec![4, 0],
+                        span: vec![0, 0, 0],
+                        leading_comments: Some(" message\n".to_owned()),
+                        trailing_comments: Some(" trailing\n".to_owned()),
+                        leading_detached_comments: vec![" detached\n".to_owned()],
+                    },
+                    location(vec![4, 0, 2, 1], " bar\n"),
+                    location(vec![4, 0, 4, 0, 2, 1], " value\n"),
+                    location(vec![6, 0, 2, 0], " method\n"),
+                ],
+            }),
+            ..Default::default()
+        }],
+    };
+
+    let pool = DescriptorPool::from_file_descriptor_set(file_descriptor_set).unwrap();
+    let message = pool.get_message_by_name("my.package.MyMessage").unwrap();
+
+    let comments = message.comments().unwrap();
+    assert_eq!(comments.leading(), Some(" message\n"));
+    assert_eq!(comments.trailing(), Some(" trailing\n"));
+    assert_eq!(comments.leading_detached(), &[" detached\n".to_owned()]);
+
+    assert!(message
+        .get_field_by_name("foo")
+        .unwrap()
+        .comments()
+        .is_none());
+    assert_eq!(
+        message
+            .get_field_by_name("bar")
+            .unwrap()
+            .comments()
+            .unwrap()
+            .leading(),
+        Some(" bar\n")
+    );
+
+    let enum_ty = pool
+        .get_enum_by_name("my.package.MyMessage.MyEnum")
+        .unwrap();
+    assert!(enum_ty.comments().is_none());
+    assert_eq!(
+        enum_ty
+            .get_value_by_name("DEFAULT")
+            .unwrap()
+            .comments()
+            .unwrap()
+            .leading(),
+        Some(" value\n")
+    );
+    assert!(enum_ty
+        .get_value_by_name("OTHER")
+        .unwrap()
+        .comments()
+        .is_none());
+
+    let service = pool.services().next().unwrap();
+    assert!(service.comments().is_none());
+    let method = service.methods().next().unwrap();
+    assert_eq!(method.comments().unwrap().leading(), Some(" method\n"));
+
+    let stripped = pool.clone().without_source_code_info();
+    assert!(stripped
+        .file_descriptor_protos()
+        .all(|file| file.source_code_info.is_none()));
+    assert!(stripped
+        .get_message_by_name("my.package.MyMessage")
+        .unwrap()
+        .comments()
+        .is_none());
+    assert!(pool
+        .get_message_by_name("my.package.MyMessage")
+        .unwrap()
+        .comments()
+        .is_some());
+
+    let mut without_source_info = pool.file_descriptor_set();
+    without_source_info.file[0].source_code_info = None;
+    let pool = DescriptorPool::from_file_descriptor_set(without_source_info).unwrap();
+    assert!(pool
+        .get_message_by_name("my.package.MyMessage")
+        .unwrap()
+        .comments()
+        .is_none());
+}
+
+#[test]
+fn decode_lazy() {
+    use prost::Message;
+
+    let file_descriptor_set = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("myfile.proto".to_owned()),
+            package: Some("my.package".to_owned()),
+            syntax: Some("proto3".to_owned()),
+            message_type: vec![DescriptorProto {
+                name: Some("MyMessage".to_owned()),
+                field: vec![FieldDescriptorProto {
+                    name: Some("my_field".to_owned()),
+                    number: Some(1),
+                    label: Some(Label::Optional as i32),
+                    r#type: Some(Type::Int32 as i32),
+                    json_name: Some("myField".to_owned()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    };
+
+    let mut pool =
+        DescriptorPool::decode_lazy(file_descriptor_set.encode_to_vec().as_slice()).unwrap();
+    assert!(pool.inner.type_map.lazy);
+    let message = pool.get_message_by_name("my.package.MyMessage").unwrap();
+    assert!(message.has_field_by_name("my_field"));
+    assert_eq!(message.get_field_by_name("my_field").unwrap().number(), 1);
+    assert_eq!(
+        message.get_field_by_json_name("myField").unwrap().number(),
+        1
+    );
+    assert!(message.get_field_by_name("myField").is_none());
+
+    pool.with_file_descriptor_proto_mut("myfile.proto", |file| {
+        file.message_type[0].field[0].json_name = Some("renamedField".to_owned());
+    })
+    .unwrap();
+    assert!(pool.inner.type_map.lazy);
+    let message = pool.get_message_by_name("my.package.MyMessage").unwrap();
+    assert_eq!(
+        message
+            .get_field_by_json_name("renamedField")
+            .unwrap()
+            .number(),
+        1
+    );
+
+    let err = DescriptorPool::decode_lazy(b"invalid".as_ref()).unwrap_err();
+    assert!(err.to_string().contains("decode"), "{}", err);
+}
+
+#[test]
+fn pool_extension_lookup() {
+    let file1 = FileDescriptorProto {
+        name: Some("myfile1.proto".to_owned()),
+        package: Some("my.package1".to_owned()),
+        syntax: Some("proto2".to_owned()),
+        message_type: vec![DescriptorProto {
+            name: Some("MyMessage".to_owned()),
+            extension_range: vec![descriptor_proto::ExtensionRange {
+                start: Some(100),
+                end: Some(200),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let file2 = FileDescriptorProto {
+        name: Some("myfile2.proto".to_owned()),
+        package: Some("my.package2".to_owned()),
+        syntax: Some("proto2".to_owned()),
+        dependency: vec!["myfile1.proto".to_owned()],
+        message_type: vec![DescriptorProto {
+            name: Some("MyScope".to_owned()),
+            extension: vec![FieldDescriptorProto {
+                name: Some("nested_extension".to_owned()),
+                number: Some(101),
+                label: Some(Label::Optional as i32),
+                r#type: Some(Type::String as i32),
+                extendee: Some(".my.package1.MyMessage".to_owned()),
+                json_name: Some("nestedExtension".to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        extension: vec![FieldDescriptorProto {
+            name: Some("my_extension".to_owned()),
+            number: Some(100),
+            label: Some(Label::Optional as i32),
+            r#type: Some(Type::Int32 as i32),
+            extendee: Some(".my.package1.MyMessage".to_owned()),
+            json_name: Some("myExtension".to_owned()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let pool = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: vec![file1.clone(), file2],
+    })
+    .unwrap();
+    let message = pool.get_message_by_name("my.package1.MyMessage").unwrap();
+
+    let extension = pool.get_extension(&message, 100).unwrap();
+    assert_eq!(extension.full_name(), "my.package2.my_extension");
+    assert_eq!(message.get_extension(100), Some(extension.clone()));
+    assert_eq!(
+        pool.get_extension_by_name("my.package2.my_extension"),
+        Some(extension.clone())
+    );
+    assert_eq!(
+        pool.get_extension_by_name(".my.package2.MyScope.nested_extension")
+            .unwrap()
+            .number(),
+        101
+    );
+    assert_eq!(pool.get_extension(&message, 102), None);
+    assert_eq!(pool.get_extension_by_name("my.package2.MyScope"), None);
+
+    let mut names: Vec<_> = pool
+        .extensions_for(&message)
+        .map(|ext| ext.full_name().to_owned())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "my.package2.MyScope.nested_extension",
+            "my.package2.my_extension"
+        ]
+    );
+
+    let other_pool =
+        DescriptorPool::from_file_descriptor_set(FileDescriptorSet { file: vec![file1] }).unwrap();
+    let other_message = other_pool
+        .get_message_by_name("my.package1.MyMessage")
+        .unwrap();
+    assert_eq!(other_pool.get_extension(&other_message, 100), None);
+    assert_eq!(other_pool.extensions_for(&other_message).len(), 0);
+    assert_eq!(pool.get_extension(&other_message, 100), Some(extension));
+    assert_eq!(pool.extensions_for(&other_message).len(), 2);
+}
+
+#[test]
+fn pool_content_eq() {
+    let file1 = FileDescriptorProto {
+        name: Some("myfile1.proto".to_owned()),
+        package: Some("my.package1".to_owned()),
+        syntax: Some("proto3".to_owned()),
+        message_type: vec![DescriptorProto {
+            name: Some("MyMessage".to_owned()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let file2 = FileDescriptorProto {
+        name: Some("myfile2.proto".to_owned()),
+        package: Some("my.package2".to_owned()),
+        syntax: Some("proto3".to_owned()),
+        ..Default::default()
+    };
+
+    let pool = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: vec![file1.clone(), file2.clone()],
+    })
+    .unwrap();
+    let reordered = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: vec![file2.clone(), file1.clone()],
+    })
+    .unwrap();
+    assert_ne!(pool, reordered);
+    assert!(pool.content_eq(&reordered));
+    assert!(pool.content_eq(&pool.clone()));
+
+    let subset = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: vec![file1.clone()],
+    })
+    .unwrap();
+    assert!(!pool.content_eq(&subset));
+    assert!(!subset.content_eq(&pool));
+
+    let mut modified_file1 = file1;
+    modified_file1.message_type[0].name = Some("OtherMessage".to_owned());
+    let modified = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: vec![modified_file1, file2],
+    })
+    .unwrap();
+    assert!(!pool.content_eq(&modified));
+}
+
+#[test]
+fn used_field_numbers() {
+    let field = |name: &str, number: i32| FieldDescriptorProto {
+        name: Some(name.to_owned()),
+        number: Some(number),
+        label: Some(Label::Optional as i32),
+        r#type: Some(Type::Int32 as i32),
+        json_name: Some(name.to_owned()),
+        ..Default::default()
+    };
+
+    let file_descriptor_set = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("myfile.proto".to_owned()),
+            package: Some("my.package".to_owned()),
+            syntax: Some("proto2".to_owned()),
+            message_type: vec![
+                DescriptorProto {
+                    name: Some("MyMessage".to_owned()),
+                    field: vec![field("a", 1), field("b", 2), field("c", 7)],
+                    extension_range: vec![descriptor_proto::ExtensionRange {
+                        start: Some(10),
+                        end: Some(12),
+                        ..Default::default()
+                    }],
+                    reserved_range: vec![
+                        descriptor_proto::ReservedRange {
+                            start: Some(3),
+                            end: Some(5),
+                        },
+                        descriptor_proto::ReservedRange {
+                            start: Some(11),
+                            end: Some(13),
+                        },
+                    ],
+                    ..Default::default()
+                },
+                DescriptorProto {
+                    name: Some("Crowded".to_owned()),
+                    field: vec![field("a", 1)],
+                    reserved_range: vec![descriptor_proto::ReservedRange {
+                        start: Some(2),
+                        end: Some(19000),
+                    }],
+                    ..Default::default()
+                },
+                DescriptorProto {
+                    name: Some("Full".to_owned()),
+                    extension_range: vec![descriptor_proto::ExtensionRange {
+                        start: Some(1),
+                        end: Some(536_870_912),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }],
+    };
+
+    let pool = DescriptorPool::from_file_descriptor_set(file_descriptor_set).unwrap();
+
+    let message = pool.get_message_by_name("my.package.MyMessage").unwrap();
+    assert_eq!(
+        message.used_field_numbers().collect::<Vec<_>>(),
+        [1, 2, 3, 4, 7, 10, 11, 12]
+    );
+    assert_eq!(message.next_available_field_number(), Some(5));
+
+    let crowded = pool.get_message_by_name("my.package.Crowded").unwrap();
+    assert_eq!(crowded.next_available_field_number(), Some(20000));
+
+    let full = pool.get_message_by_name("my.package.Full").unwrap();
+    assert_eq!(full.used_field_numbers().last(), Some(536_870_911));
+    assert_eq!(full.next_available_field_number(), None);
 }
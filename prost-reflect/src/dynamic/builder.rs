@@ -0,0 +1,185 @@
+use std::fmt;
+
+use crate::{
+    Cardinality, DynamicMessage, FieldDescriptor, MessageDescriptor, OneofDescriptor,
+    SetFieldError, Value,
+};
+
+/// A builder for constructing a [`DynamicMessage`] from a list of field assignments.
+///
+/// See [`MessageDescriptor::build`].
+///
+/// # Examples
+///
+/// ```
+/// # use prost::Message;
+/// # use prost_types::FileDescriptorSet;
+/// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// let message = message_descriptor
+///     .build()
+///     .field("foo", Value::I32(150))
+///     .build()
+///     .unwrap();
+/// assert_eq!(message.encode_to_vec(), b"\x08\x96\x01");
+///
+/// let result = message_descriptor.build().field("foo", Value::String("150".to_owned())).build();
+/// assert!(result.is_err());
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct DynamicMessageBuilder {
+    desc: MessageDescriptor,
+    fields: Vec<(FieldKey, Value)>,
+}
+
+#[derive(Debug, Clone)]
+enum FieldKey {
+    Name(String),
+    Number(u32),
+}
+
+/// Error type returned by [`DynamicMessageBuilder::build`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildMessageError {
+    /// A field could not be set, because it does not exist or the value has an incompatible type.
+    SetField(SetFieldError),
+    /// The message has no field with the given number.
+    NumberNotFound {
+        /// The field number.
+        number: u32,
+    },
+    /// Two different fields of the same oneof were assigned.
+    OneofConflict {
+        /// The oneof descriptor.
+        oneof: OneofDescriptor,
+        /// The first field assigned.
+        first: FieldDescriptor,
+        /// The second field assigned.
+        second: FieldDescriptor,
+    },
+    /// A required field was not assigned.
+    MissingRequired {
+        /// The field descriptor.
+        field: FieldDescriptor,
+    },
+}
+
+impl MessageDescriptor {
+    /// Creates a [`DynamicMessageBuilder`] for constructing a message of this type.
+    pub fn build(&self) -> DynamicMessageBuilder {
+        DynamicMessageBuilder {
+            desc: self.clone(),
+            fields: Vec::new(),
+        }
+    }
+}
+
+impl DynamicMessageBuilder {
+    /// Assigns `value` to the field with the given name.
+    ///
+    /// The assignment is validated when [`build`][Self::build] is called.
+    pub fn field(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.fields.push((FieldKey::Name(name.into()), value));
+        self
+    }
+
+    /// Assigns `value` to the field with the given number.
+    ///
+    /// The assignment is validated when [`build`][Self::build] is called.
+    pub fn field_by_number(mut self, number: u32, value: Value) -> Self {
+        self.fields.push((FieldKey::Number(number), value));
+        self
+    }
+
+    /// Validates the assigned fields, and returns the constructed message.
+    ///
+    /// Fields are set in the order they were assigned, so if a field is assigned more than once
+    /// the last value is kept. An error is returned if any field does not exist or has an
+    /// incompatible value, if two different fields of the same oneof are assigned, or if a
+    /// required field is not set.
+    pub fn build(self) -> Result<DynamicMessage, BuildMessageError> {
+        let mut message = DynamicMessage::new(self.desc);
+        let mut oneofs: Vec<(OneofDescriptor, FieldDescriptor)> = Vec::new();
+
+        for (key, value) in self.fields {
+            let field_desc = match key {
+                FieldKey::Name(name) => {
+                    message
+                        .desc
+                        .get_field_by_name(&name)
+                        .ok_or(BuildMessageError::SetField(SetFieldError::NotFound {
+                            name,
+                        }))?
+                }
+                FieldKey::Number(number) => message
+                    .desc
+                    .get_field(number)
+                    .ok_or(BuildMessageError::NumberNotFound { number })?,
+            };
+
+            if let Some(oneof) = field_desc.containing_oneof() {
+                match oneofs.iter().find(|(o, _)| *o == oneof) {
+                    Some((_, first)) if *first != field_desc => {
+                        return Err(BuildMessageError::OneofConflict {
+                            oneof,
+                            first: first.clone(),
+                            second: field_desc,
+                        })
+                    }
+                    Some(_) => (),
+                    None => oneofs.push((oneof, field_desc.clone())),
+                }
+            }
+
+            message
+                .try_set_field(&field_desc, value)
+                .map_err(BuildMessageError::SetField)?;
+        }
+
+        if let Some(field) = message
+            .desc
+            .fields()
+            .find(|field| field.cardinality() == Cardinality::Required && !message.has_field(field))
+        {
+            return Err(BuildMessageError::MissingRequired { field });
+        }
+
+        Ok(message)
+    }
+}
+
+impl fmt::Display for BuildMessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildMessageError::SetField(err) => err.fmt(f),
+            BuildMessageError::NumberNotFound { number } => {
+                write!(f, "field number {} not found", number)
+            }
+            BuildMessageError::OneofConflict {
+                oneof,
+                first,
+                second,
+            } => write!(
+                f,
+                "fields '{}' and '{}' are both in oneof '{}'",
+                first.name(),
+                second.name(),
+                oneof.full_name()
+            ),
+            BuildMessageError::MissingRequired { field } => {
+                write!(f, "required field '{}' is not set", field.full_name())
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildMessageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BuildMessageError::SetField(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+
+use prost::bytes::Bytes;
+
+use crate::{DynamicMessage, Value};
+
+/// A type which can be converted from a [`Value`] of the corresponding variant.
+///
+/// See [`DynamicMessage::get_field_by_name_as`].
+pub trait TryFromValue: Sized {
+    /// Converts `value` to this type, returning an error if it is a different variant.
+    fn try_from_value(value: Value) -> Result<Self, ConversionError>;
+}
+
+/// Error type returned by [`TryFromValue::try_from_value`] if a value has an unexpected type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionError {
+    expected: &'static str,
+    value: Value,
+}
+
+impl DynamicMessage {
+    /// Gets the value of the field with the given name, converted to the type `T`.
+    ///
+    /// If the message has no field with the given name, `None` is returned. If the field value
+    /// cannot be converted to `T`, a [`ConversionError`] is returned.
+    ///
+    /// See [`get_field_by_name`][Self::get_field_by_name] for more details.
+    pub fn get_field_by_name_as<T>(&self, name: &str) -> Option<Result<T, ConversionError>>
+    where
+        T: TryFromValue,
+    {
+        self.get_field_by_name(name)
+            .map(|value| T::try_from_value(value.into_owned()))
+    }
+}
+
+impl ConversionError {
+    /// Gets the value which could not be converted.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Converts this error into the value which could not be converted.
+    pub fn into_value(self) -> Value {
+        self.value
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected a value of type '{}', but found a value of type '{}'",
+            self.expected,
+            self.value.type_name()
+        )
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+macro_rules! impl_try_from_value {
+    ($($ty:ty => $variant:ident($name:literal);)*) => {
+        $(
+            impl TryFromValue for $ty {
+                fn try_from_value(value: Value) -> Result<Self, ConversionError> {
+                    match value {
+                        Value::$variant(value) => Ok(value),
+                        value => Err(ConversionError {
+                            expected: $name,
+                            value,
+                        }),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_value! {
+    bool => Bool("bool");
+    i32 => I32("i32");
+    i64 => I64("i64");
+    u32 => U32("u32");
+    u64 => U64("u64");
+    f32 => F32("f32");
+    f64 => F64("f64");
+    String => String("string");
+    Bytes => Bytes("bytes");
+    DynamicMessage => Message("message");
+}
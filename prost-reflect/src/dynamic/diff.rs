@@ -0,0 +1,342 @@
+use std::fmt::Write;
+
+use crate::{DynamicMessage, MapKey, Value};
+
+impl DynamicMessage {
+    /// Compares this message with `other`, returning a human-readable description of the first
+    /// difference found, or `None` if the messages are equal.
+    ///
+    /// Fields are compared in the order of their field numbers, recursing into nested messages, lists
+    /// and maps. The description includes the path of the differing field, for example
+    /// `field "nested.int32": 3 != 4`. This is intended to make test failures easier to diagnose than
+    /// comparing messages with [`PartialEq`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_types::FileDescriptorSet;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let a = DynamicMessage::decode(message_descriptor.clone(), b"\x08\x03".as_ref()).unwrap();
+    /// let b = DynamicMessage::decode(message_descriptor, b"\x08\x04".as_ref()).unwrap();
+    /// assert_eq!(a.diff_first(&a), None);
+    /// assert_eq!(a.diff_first(&b).as_deref(), Some(r#"field "foo": 3 != 4"#));
+    /// ```
+    pub fn diff_first(&self, other: &DynamicMessage) -> Option<String> {
+        let mut path = String::new();
+        diff_message(self, other, &[], &mut path)
+    }
+
+    /// Compares this message with `other` like [`diff_first`][Self::diff_first], but skipping the
+    /// fields with the given paths.
+    ///
+    /// Each path is a list of field names separated by `.`, for example `nested.created_at`, and
+    /// extensions are named by their full name in brackets, for example `[my.package.my_ext]`. A
+    /// path through a repeated or map field applies to every message it contains. The segment `*`
+    /// matches any field, so `*.updated_at` skips the `updated_at` field of every direct child
+    /// message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_types::FileDescriptorSet;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let a = DynamicMessage::decode(message_descriptor.clone(), b"\x08\x03".as_ref()).unwrap();
+    /// let b = DynamicMessage::decode(message_descriptor, b"\x08\x04".as_ref()).unwrap();
+    /// assert_eq!(a.diff_first_ignoring(&b, &["foo"]), None);
+    /// assert!(a.diff_first_ignoring(&b, &["bar"]).is_some());
+    /// ```
+    pub fn diff_first_ignoring(
+        &self,
+        other: &DynamicMessage,
+        ignore_paths: &[&str],
+    ) -> Option<String> {
+        let patterns: Vec<Vec<&str>> = ignore_paths.iter().map(|path| split_path(path)).collect();
+        let patterns: Vec<&[&str]> = patterns.iter().map(Vec::as_slice).collect();
+        let mut path = String::new();
+        diff_message(self, other, &patterns, &mut path)
+    }
+
+    /// Returns `true` if this message is equal to `other`, ignoring the fields with the given paths.
+    ///
+    /// See [`diff_first_ignoring`][Self::diff_first_ignoring] for the syntax of paths.
+    pub fn eq_ignoring(&self, other: &DynamicMessage, ignore_paths: &[&str]) -> bool {
+        self.diff_first_ignoring(other, ignore_paths).is_none()
+    }
+
+    /// Computes how similar this message is to `other`, as a score between `0.0` and `1.0`.
+    ///
+    /// This is a softer comparison than [`PartialEq`], and is intended for ranking candidate matches
+    /// rather than for checking exact equality. The score is computed as follows:
+    ///
+    /// * messages of different types score `0.0`.
+    /// * otherwise, the score is the mean of the scores of each field and extension which is set in
+    ///   either message, or `1.0` if no fields are set in either. Unknown fields are ignored.
+    /// * a nested message field scores `0.5 + 0.5 * s`, where `s` is the similarity of the nested
+    ///   messages, so differences count for half as much at each level of nesting.
+    /// * a list field scores the sum of the scores of corresponding elements, divided by the length
+    ///   of the longer list, or `1.0` if both are empty.
+    /// * a map field scores the sum of the scores of values whose key is present in both maps,
+    ///   divided by the number of distinct keys, or `1.0` if both are empty.
+    /// * any other value scores `1.0` if equal and `0.0` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_types::FileDescriptorSet;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let a = DynamicMessage::decode(message_descriptor.clone(), b"\x08\x03".as_ref()).unwrap();
+    /// let b = DynamicMessage::decode(message_descriptor, b"\x08\x04".as_ref()).unwrap();
+    /// assert_eq!(a.similarity(&a), 1.0);
+    /// assert_eq!(a.similarity(&b), 0.0);
+    /// ```
+    pub fn similarity(&self, other: &DynamicMessage) -> f64 {
+        similarity_message(self, other)
+    }
+}
+
+fn diff_message(
+    a: &DynamicMessage,
+    b: &DynamicMessage,
+    ignore: &[&[&str]],
+    path: &mut String,
+) -> Option<String> {
+    if a.desc != b.desc {
+        return Some(format!(
+            "{}message type {} != {}",
+            describe_path(path),
+            a.desc.full_name(),
+            b.desc.full_name()
+        ));
+    }
+
+    for field in a.desc.fields() {
+        if !a.has_field(&field) && !b.has_field(&field) {
+            continue;
+        }
+
+        let ignore = match match_ignore_patterns(ignore, field.name()) {
+            Some(ignore) => ignore,
+            None => continue,
+        };
+
+        let len = path.len();
+        push_path_segment(path, field.name());
+        let diff = diff_value(&a.get_field(&field), &b.get_field(&field), &ignore, path);
+        path.truncate(len);
+        if diff.is_some() {
+            return diff;
+        }
+    }
+
+    for extension in a.desc.extensions() {
+        if !a.has_extension(&extension) && !b.has_extension(&extension) {
+            continue;
+        }
+
+        let name = format!("[{}]", extension.full_name());
+        let ignore = match match_ignore_patterns(ignore, &name) {
+            Some(ignore) => ignore,
+            None => continue,
+        };
+
+        let len = path.len();
+        push_path_segment(path, &name);
+        let diff = diff_value(
+            &a.get_extension(&extension),
+            &b.get_extension(&extension),
+            &ignore,
+            path,
+        );
+        path.truncate(len);
+        if diff.is_some() {
+            return diff;
+        }
+    }
+
+    if !a.fields.unknown_eq(&b.fields) {
+        return Some(format!("{}unknown fields differ", describe_path(path)));
+    }
+
+    None
+}
+
+fn diff_value(a: &Value, b: &Value, ignore: &[&[&str]], path: &mut String) -> Option<String> {
+    match (a, b) {
+        (Value::Message(a), Value::Message(b)) => diff_message(a, b, ignore, path),
+        (Value::List(a), Value::List(b)) => {
+            for (index, (a, b)) in a.iter().zip(b).enumerate() {
+                let len = path.len();
+                write!(path, "[{}]", index).unwrap();
+                let diff = diff_value(a, b, ignore, path);
+                path.truncate(len);
+                if diff.is_some() {
+                    return diff;
+                }
+            }
+
+            if a.len() != b.len() {
+                return Some(format!(
+                    "{}list length {} != {}",
+                    describe_path(path),
+                    a.len(),
+                    b.len()
+                ));
+            }
+
+            None
+        }
+        (Value::Map(a), Value::Map(b)) => {
+            let mut keys: Vec<&MapKey> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                let len = path.len();
+                write!(path, "[{}]", Value::from(key.clone())).unwrap();
+                let diff = match (a.get(key), b.get(key)) {
+                    (Some(a), Some(b)) => diff_value(a, b, ignore, path),
+                    (Some(_), None) => Some(format!("{}missing on right", describe_path(path))),
+                    (None, Some(_)) => Some(format!("{}missing on left", describe_path(path))),
+                    (None, None) => unreachable!(),
+                };
+                path.truncate(len);
+                if diff.is_some() {
+                    return diff;
+                }
+            }
+
+            None
+        }
+        (a, b) if a == b => None,
+        (a, b) => Some(format!("{}{} != {}", describe_path(path), a, b)),
+    }
+}
+
+/// The weight given to differences in a nested message, relative to its parent.
+const NESTED_MESSAGE_DECAY: f64 = 0.5;
+
+fn similarity_message(a: &DynamicMessage, b: &DynamicMessage) -> f64 {
+    if a.desc != b.desc {
+        return 0.0;
+    }
+
+    let mut total = 0.0;
+    let mut count = 0;
+
+    for field in a.desc.fields() {
+        if !a.has_field(&field) && !b.has_field(&field) {
+            continue;
+        }
+
+        total += similarity_value(&a.get_field(&field), &b.get_field(&field));
+        count += 1;
+    }
+
+    for extension in a.desc.extensions() {
+        if !a.has_extension(&extension) && !b.has_extension(&extension) {
+            continue;
+        }
+
+        total += similarity_value(&a.get_extension(&extension), &b.get_extension(&extension));
+        count += 1;
+    }
+
+    if count == 0 {
+        1.0
+    } else {
+        total / count as f64
+    }
+}
+
+fn similarity_value(a: &Value, b: &Value) -> f64 {
+    match (a, b) {
+        (Value::Message(a), Value::Message(b)) => {
+            1.0 - NESTED_MESSAGE_DECAY * (1.0 - similarity_message(a, b))
+        }
+        (Value::List(a), Value::List(b)) => {
+            let len = a.len().max(b.len());
+            if len == 0 {
+                return 1.0;
+            }
+
+            let total: f64 = a.iter().zip(b).map(|(a, b)| similarity_value(a, b)).sum();
+            total / len as f64
+        }
+        (Value::Map(a), Value::Map(b)) => {
+            let len = a.len() + b.keys().filter(|key| !a.contains_key(key)).count();
+            if len == 0 {
+                return 1.0;
+            }
+
+            let total: f64 = a
+                .iter()
+                .filter_map(|(key, a)| b.get(key).map(|b| similarity_value(a, b)))
+                .sum();
+            total / len as f64
+        }
+        (a, b) if a == b => 1.0,
+        _ => 0.0,
+    }
+}
+
+/// Finds the patterns which apply to the children of the field `name`, or returns `None` if the field
+/// itself is ignored.
+fn match_ignore_patterns<'a>(ignore: &[&'a [&'a str]], name: &str) -> Option<Vec<&'a [&'a str]>> {
+    let mut children = Vec::new();
+    for pattern in ignore {
+        match pattern.split_first() {
+            Some((&segment, rest)) if segment == "*" || segment == name => {
+                if rest.is_empty() {
+                    return None;
+                }
+                children.push(rest);
+            }
+            _ => (),
+        }
+    }
+    Some(children)
+}
+
+/// Splits a path into its segments, ignoring separators within bracketed extension names.
+pub(super) fn split_path(path: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, ch) in path.char_indices() {
+        match ch {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            '.' if depth == 0 => {
+                segments.push(&path[start..index]);
+                start = index + 1;
+            }
+            _ => (),
+        }
+    }
+    segments.push(&path[start..]);
+    segments
+}
+
+pub(super) fn push_path_segment(path: &mut String, name: &str) {
+    if !path.is_empty() {
+        path.push('.');
+    }
+    path.push_str(name);
+}
+
+fn describe_path(path: &str) -> String {
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!("field \"{}\": ", path)
+    }
+}
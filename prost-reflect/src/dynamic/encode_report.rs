@@ -0,0 +1,135 @@
+use std::fmt::Write;
+
+use prost::Message;
+
+use crate::{DynamicMessage, FieldDescriptor, Value};
+
+use super::{
+    diff::push_path_segment,
+    fields::{FieldDescriptorLike, ValueAndDescriptor},
+};
+
+/// A report of fields which were set, but not included in the encoded form of a
+/// [`DynamicMessage`].
+///
+/// See [`DynamicMessage::encode_to_vec_verbose`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EncodeReport {
+    skipped_default_fields: Vec<String>,
+}
+
+impl DynamicMessage {
+    /// Encodes this message into a newly allocated buffer, and reports which fields were skipped.
+    ///
+    /// Fields which do not track presence, such as proto3 fields not marked `optional`, are not
+    /// encoded when set to their default value. The returned [`EncodeReport`] lists such fields
+    /// in this message and any nested messages, which can explain why a value which was set does
+    /// not appear in the output.
+    pub fn encode_to_vec_verbose(&self) -> (Vec<u8>, EncodeReport) {
+        let mut report = EncodeReport::default();
+        report.check_message(self, &mut String::new());
+        (self.encode_to_vec(), report)
+    }
+
+    /// Encodes this message into a newly allocated buffer, including the fields with the given
+    /// numbers even if they are set to their default value.
+    ///
+    /// Singular fields which do not track presence, such as proto3 fields not marked `optional`,
+    /// are normally not encoded when set to their default value. Any such field of this message
+    /// whose number is in `fields` is instead always encoded, using its default value if it is
+    /// not set. This is useful for producing valid input which other decoders must accept. Other
+    /// numbers in `fields` are ignored, and nested messages are encoded as usual.
+    pub fn encode_forcing_presence(&self, fields: &[u32]) -> Vec<u8> {
+        let mut forced: Vec<FieldDescriptor> = fields
+            .iter()
+            .filter_map(|&number| self.desc.get_field(number))
+            .filter(|field| !field.supports_presence() && !field.is_list() && !field.is_map())
+            .collect();
+        forced.sort_by_key(FieldDescriptor::number);
+        forced.dedup();
+        let mut forced = forced.into_iter().peekable();
+
+        let mut buf = Vec::with_capacity(self.encoded_len());
+        for field in self.fields.iter(&self.desc) {
+            let number = match &field {
+                ValueAndDescriptor::Field(_, field_desc) => field_desc.number(),
+                ValueAndDescriptor::Extension(_, extension_desc) => extension_desc.number(),
+                ValueAndDescriptor::Unknown(number, _) => *number,
+            };
+            while let Some(field_desc) = forced.next_if(|field_desc| field_desc.number() <= number)
+            {
+                // Fields which are already encoded with a non-default value are not repeated.
+                if field_desc.number() < number {
+                    self.fields
+                        .get(&field_desc)
+                        .encode_field_unconditionally(&field_desc, &mut buf);
+                }
+            }
+            self.encode_field(field, &mut buf);
+        }
+        for field_desc in forced {
+            self.fields
+                .get(&field_desc)
+                .encode_field_unconditionally(&field_desc, &mut buf);
+        }
+        buf
+    }
+}
+
+impl EncodeReport {
+    /// Gets the paths of singular fields which were set to their default value, and so were not
+    /// encoded, for example `nested.int32`. Repeated and map fields which are empty are not
+    /// included.
+    pub fn skipped_default_fields(&self) -> &[String] {
+        &self.skipped_default_fields
+    }
+
+    /// Returns `true` if every field which was set was encoded.
+    pub fn is_empty(&self) -> bool {
+        self.skipped_default_fields.is_empty()
+    }
+
+    fn check_message(&mut self, message: &DynamicMessage, path: &mut String) {
+        for field in message.desc.fields() {
+            let value = match message.fields.get_value(field.number()) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let len = path.len();
+            push_path_segment(path, field.name());
+            if field.has(value) {
+                self.check_value(value, path);
+            } else if !field.is_list() && !field.is_map() {
+                // Empty lists and maps have no elements to encode, so are not reported.
+                self.skipped_default_fields.push(path.clone());
+            }
+            path.truncate(len);
+        }
+    }
+
+    fn check_value(&mut self, value: &Value, path: &mut String) {
+        match value {
+            Value::Message(message) => self.check_message(message, path),
+            Value::List(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    let len = path.len();
+                    write!(path, "[{}]", index).unwrap();
+                    self.check_value(value, path);
+                    path.truncate(len);
+                }
+            }
+            Value::Map(values) => {
+                let mut entries: Vec<_> = values.iter().collect();
+                entries.sort_by_key(|(key, _)| *key);
+                for (key, value) in entries {
+                    let len = path.len();
+                    write!(path, "[{}]", Value::from(key.clone())).unwrap();
+                    self.check_value(value, path);
+                    path.truncate(len);
+                }
+            }
+            _ => (),
+        }
+    }
+}
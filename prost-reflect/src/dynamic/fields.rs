@@ -2,6 +2,8 @@ use std::{
     borrow::Cow,
     collections::btree_map::{self, BTreeMap},
     fmt,
+    hash::{Hash, Hasher},
+    mem,
 };
 
 use crate::{
@@ -29,15 +31,33 @@ pub(super) trait FieldDescriptorLike: fmt::Debug {
 }
 
 /// A set of extension fields in a protobuf message.
-#[derive(Default, Debug, Clone, PartialEq)]
+#[derive(Default, Debug, Clone)]
 pub(super) struct DynamicMessageFieldSet {
     fields: BTreeMap<u32, ValueOrUnknown>,
+    /// Fields whose packed encoding has been overridden with
+    /// [`DynamicMessage::set_field_packed`](crate::DynamicMessage::set_field_packed). Only
+    /// overrides which differ from the field's default encoding are stored. The map is boxed to keep
+    /// the size of messages which have no overrides small.
+    #[allow(clippy::box_collection)]
+    packed: Option<Box<BTreeMap<u32, bool>>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub(super) enum ValueOrUnknown {
     Value(Value),
     Unknown(Vec<UnknownField>),
+    /// A field which has been cleared by
+    /// [`DynamicMessage::clear_and_merge`](crate::DynamicMessage::clear_and_merge). The emptied
+    /// value is kept so its allocations can be reused if the field is set again.
+    Cleared(Value),
+}
+
+/// A field descriptor whose packed encoding has been overridden with
+/// [`DynamicMessage::set_field_packed`](crate::DynamicMessage::set_field_packed).
+#[derive(Debug)]
+pub(super) struct PackedOverride<'a> {
+    desc: &'a FieldDescriptor,
+    packed: bool,
 }
 
 pub(super) enum ValueAndDescriptor<'a> {
@@ -47,17 +67,17 @@ pub(super) enum ValueAndDescriptor<'a> {
 }
 
 impl DynamicMessageFieldSet {
-    fn get_value(&self, number: u32) -> Option<&Value> {
-        match self.fields.get(&number) {
-            Some(ValueOrUnknown::Value(value)) => Some(value),
-            Some(ValueOrUnknown::Unknown(_)) | None => None,
-        }
+    pub(super) fn get_value(&self, number: u32) -> Option<&Value> {
+        self.fields.get(&number).and_then(ValueOrUnknown::as_value)
     }
 
     pub(super) fn has(&self, desc: &impl FieldDescriptorLike) -> bool {
+        self.get_ref(desc).is_some()
+    }
+
+    pub(super) fn get_ref(&self, desc: &impl FieldDescriptorLike) -> Option<&Value> {
         self.get_value(desc.number())
-            .map(|value| desc.has(value))
-            .unwrap_or(false)
+            .filter(|value| desc.has(value))
     }
 
     pub(super) fn get(&self, desc: &impl FieldDescriptorLike) -> Cow<'_, Value> {
@@ -76,6 +96,16 @@ impl DynamicMessageFieldSet {
                     *value = ValueOrUnknown::Value(desc.default_value());
                     value.unwrap_value_mut()
                 }
+                value @ ValueOrUnknown::Cleared(_) => {
+                    let mut reused = desc.default_value();
+                    if let ValueOrUnknown::Cleared(spare) =
+                        mem::replace(value, ValueOrUnknown::Unknown(Vec::new()))
+                    {
+                        reuse_allocation(&mut reused, spare);
+                    }
+                    *value = ValueOrUnknown::Value(reused);
+                    value.unwrap_value_mut()
+                }
             },
             btree_map::Entry::Vacant(entry) => entry
                 .insert(ValueOrUnknown::Value(desc.default_value()))
@@ -113,6 +143,41 @@ impl DynamicMessageFieldSet {
                     panic!("expected no field to be found with number {}", number)
                 }
                 ValueOrUnknown::Unknown(unknowns) => unknowns.push(unknown),
+                value @ ValueOrUnknown::Cleared(_) => {
+                    *value = ValueOrUnknown::Unknown(vec![unknown])
+                }
+            },
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(ValueOrUnknown::Unknown(vec![unknown]));
+            }
+        }
+    }
+
+    /// Adds an occurrence of a known field which was encoded with an unexpected wire type. If the
+    /// field already has a valid value, the occurrence is discarded.
+    pub(super) fn add_mismatched(&mut self, number: u32, unknown: UnknownField) {
+        match self.fields.entry(number) {
+            btree_map::Entry::Occupied(mut entry) => match entry.get_mut() {
+                ValueOrUnknown::Value(_) => (),
+                ValueOrUnknown::Unknown(unknowns) => unknowns.push(unknown),
+                value @ ValueOrUnknown::Cleared(_) => {
+                    *value = ValueOrUnknown::Unknown(vec![unknown])
+                }
+            },
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(ValueOrUnknown::Unknown(vec![unknown]));
+            }
+        }
+    }
+
+    /// Adds an occurrence of a field which is unknown in another message being merged into this
+    /// one. If the field already has a valid value, it is replaced, in the same way as a singular
+    /// field which is set in the other message.
+    pub(super) fn merge_unknown(&mut self, number: u32, unknown: UnknownField) {
+        match self.fields.entry(number) {
+            btree_map::Entry::Occupied(mut entry) => match entry.get_mut() {
+                ValueOrUnknown::Unknown(unknowns) => unknowns.push(unknown),
+                value => *value = ValueOrUnknown::Unknown(vec![unknown]),
             },
             btree_map::Entry::Vacant(entry) => {
                 entry.insert(ValueOrUnknown::Unknown(vec![unknown]));
@@ -122,39 +187,56 @@ impl DynamicMessageFieldSet {
 
     pub(super) fn clear(&mut self, desc: &impl FieldDescriptorLike) {
         self.fields.remove(&desc.number());
+        self.remove_packed(desc.number());
     }
 
     pub(crate) fn iter<'a>(
         &'a self,
         message: &'a MessageDescriptor,
     ) -> impl Iterator<Item = ValueAndDescriptor> + 'a {
-        self.fields
-            .iter()
-            .filter_map(move |(&number, value)| match value {
-                ValueOrUnknown::Value(value) => {
-                    if let Some(field) = message.get_field(number) {
-                        if field.has(value) {
-                            Some(ValueAndDescriptor::Field(Cow::Borrowed(value), field))
-                        } else {
-                            None
-                        }
-                    } else if let Some(extension) = message.get_extension(number) {
-                        if extension.has(value) {
-                            Some(ValueAndDescriptor::Extension(
-                                Cow::Borrowed(value),
-                                extension,
-                            ))
-                        } else {
-                            None
-                        }
-                    } else {
-                        panic!("no field found with number {}", number)
-                    }
-                }
+        self.fields.iter().filter_map(move |(&number, value)| {
+            let value = match value {
+                ValueOrUnknown::Value(value) => value,
                 ValueOrUnknown::Unknown(unknown) => {
-                    Some(ValueAndDescriptor::Unknown(number, unknown.as_slice()))
+                    return Some(ValueAndDescriptor::Unknown(number, unknown.as_slice()))
                 }
-            })
+                ValueOrUnknown::Cleared(_) => return None,
+            };
+
+            if let Some(field) = message.get_field(number) {
+                if field.has(value) {
+                    Some(ValueAndDescriptor::Field(Cow::Borrowed(value), field))
+                } else {
+                    None
+                }
+            } else if let Some(extension) = message.get_extension(number) {
+                if extension.has(value) {
+                    Some(ValueAndDescriptor::Extension(
+                        Cow::Borrowed(value),
+                        extension,
+                    ))
+                } else {
+                    None
+                }
+            } else {
+                panic!("no field found with number {}", number)
+            }
+        })
+    }
+
+    pub(super) fn iter_fields<'a>(
+        &'a self,
+        message: &'a MessageDescriptor,
+    ) -> impl Iterator<Item = (FieldDescriptor, &'a Value)> + 'a {
+        self.fields.iter().filter_map(move |(&number, value)| {
+            let value = value.as_value()?;
+            let field = message.get_field(number)?;
+            if field.has(value) {
+                Some((field, value))
+            } else {
+                None
+            }
+        })
     }
 
     #[cfg(feature = "serde")]
@@ -166,43 +248,231 @@ impl DynamicMessageFieldSet {
             .fields()
             .filter(move |f| !f.supports_presence() || self.has(f))
             .map(move |f| ValueAndDescriptor::Field(self.get(&f), f));
-        let others = self
+        let others = self.fields.iter().filter_map(move |(&number, value)| {
+            let value = match value {
+                ValueOrUnknown::Value(value) => value,
+                ValueOrUnknown::Unknown(unknown) => {
+                    return Some(ValueAndDescriptor::Unknown(number, unknown.as_slice()))
+                }
+                ValueOrUnknown::Cleared(_) => return None,
+            };
+
+            if let Some(extension) = message.get_extension(number) {
+                if extension.has(value) {
+                    Some(ValueAndDescriptor::Extension(
+                        Cow::Borrowed(value),
+                        extension,
+                    ))
+                } else {
+                    None
+                }
+            } else {
+                None
+            }
+        });
+        fields.chain(others)
+    }
+
+    pub(super) fn retain<F>(&mut self, message: &MessageDescriptor, mut f: F)
+    where
+        F: FnMut(&FieldDescriptor, &Value) -> bool,
+    {
+        self.fields.retain(
+            |&number, value| match (value.as_value(), message.get_field(number)) {
+                (Some(value), Some(field)) if field.has(value) => f(&field, value),
+                _ => true,
+            },
+        )
+    }
+
+    pub(super) fn values_mut(&mut self) -> impl Iterator<Item = &mut Value> {
+        self.fields
+            .values_mut()
+            .filter_map(ValueOrUnknown::as_value_mut)
+    }
+
+    /// Removes the unknown fields whose number matches `f`, returning them in order of field number.
+    pub(super) fn take_unknown<F>(&mut self, mut f: F) -> Vec<(u32, Vec<UnknownField>)>
+    where
+        F: FnMut(u32) -> bool,
+    {
+        let numbers: Vec<u32> = self
             .fields
             .iter()
-            .filter_map(move |(&number, value)| match value {
-                ValueOrUnknown::Value(value) => {
-                    if let Some(extension) = message.get_extension(number) {
-                        if extension.has(value) {
-                            Some(ValueAndDescriptor::Extension(
-                                Cow::Borrowed(value),
-                                extension,
-                            ))
-                        } else {
-                            None
+            .filter(|&(&number, value)| matches!(value, ValueOrUnknown::Unknown(_)) && f(number))
+            .map(|(&number, _)| number)
+            .collect();
+
+        numbers
+            .into_iter()
+            .filter_map(|number| match self.fields.remove(&number) {
+                Some(ValueOrUnknown::Unknown(unknown)) => Some((number, unknown)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Compares the fields which are set in `self` and `other`, ignoring fields which are stored with
+    /// a default value but do not support presence. Packed encoding overrides are also compared.
+    pub(super) fn eq(&self, other: &Self, message: &MessageDescriptor) -> bool {
+        if self.packed != other.packed {
+            return false;
+        }
+
+        let mut lhs = self.iter(message);
+        let mut rhs = other.iter(message);
+        loop {
+            match (lhs.next(), rhs.next()) {
+                (None, None) => return true,
+                (Some(l), Some(r)) => {
+                    let eq = match (l, r) {
+                        (ValueAndDescriptor::Field(l, ld), ValueAndDescriptor::Field(r, rd)) => {
+                            ld == rd && l == r
                         }
-                    } else {
-                        None
+                        (
+                            ValueAndDescriptor::Extension(l, ld),
+                            ValueAndDescriptor::Extension(r, rd),
+                        ) => ld == rd && l == r,
+                        (
+                            ValueAndDescriptor::Unknown(ln, l),
+                            ValueAndDescriptor::Unknown(rn, r),
+                        ) => ln == rn && l == r,
+                        _ => false,
+                    };
+                    if !eq {
+                        return false;
                     }
                 }
-                ValueOrUnknown::Unknown(unknown) => {
-                    Some(ValueAndDescriptor::Unknown(number, unknown.as_slice()))
+                _ => return false,
+            }
+        }
+    }
+
+    /// Compares only the unknown fields in `self` and `other`.
+    pub(super) fn unknown_eq(&self, other: &Self) -> bool {
+        self.unknown_fields().eq(other.unknown_fields())
+    }
+
+    fn unknown_fields(&self) -> impl Iterator<Item = (u32, &'_ [UnknownField])> {
+        self.fields
+            .iter()
+            .filter_map(|(&number, value)| match value {
+                ValueOrUnknown::Unknown(unknown) => Some((number, unknown.as_slice())),
+                _ => None,
+            })
+    }
+
+    pub(super) fn set_packed(&mut self, desc: &FieldDescriptor, packed: bool) {
+        if packed == desc.is_packed() {
+            self.remove_packed(desc.number());
+        } else {
+            self.packed
+                .get_or_insert_with(Default::default)
+                .insert(desc.number(), packed);
+        }
+    }
+
+    fn remove_packed(&mut self, number: u32) {
+        if let Some(packed) = &mut self.packed {
+            packed.remove(&number);
+            if packed.is_empty() {
+                self.packed = None;
+            }
+        }
+    }
+
+    pub(super) fn packed_override<'a>(
+        &self,
+        desc: &'a FieldDescriptor,
+    ) -> Option<PackedOverride<'a>> {
+        self.packed
+            .as_ref()?
+            .get(&desc.number())
+            .map(|&packed| PackedOverride { desc, packed })
+    }
+
+    /// Hashes the fields which are set, consistently with [`eq`](Self::eq).
+    pub(super) fn hash<H: Hasher>(&self, message: &MessageDescriptor, state: &mut H) {
+        for field in self.iter(message) {
+            match field {
+                ValueAndDescriptor::Field(value, field_desc) => {
+                    field_desc.number().hash(state);
+                    value.hash(state);
                 }
-            });
-        fields.chain(others)
+                ValueAndDescriptor::Extension(value, extension_desc) => {
+                    extension_desc.number().hash(state);
+                    value.hash(state);
+                }
+                ValueAndDescriptor::Unknown(number, unknowns) => {
+                    number.hash(state);
+                    unknowns.hash(state);
+                }
+            }
+        }
+        self.packed.hash(state);
     }
 
     pub(super) fn clear_all(&mut self) {
         self.fields.clear();
+        self.packed = None;
+    }
+
+    /// Clears all fields, but keeps the emptied strings, lists, maps and nested messages so their
+    /// allocations can be reused by [`get_mut`](Self::get_mut). Packed encoding overrides are kept.
+    pub(super) fn clear_retaining(&mut self) {
+        self.fields.retain(|_, value| {
+            let mut spare = match mem::replace(value, ValueOrUnknown::Unknown(Vec::new())) {
+                ValueOrUnknown::Value(value) | ValueOrUnknown::Cleared(value) => value,
+                ValueOrUnknown::Unknown(_) => return false,
+            };
+            match &mut spare {
+                Value::String(value) => value.clear(),
+                Value::List(values) => values.clear(),
+                Value::Map(values) => values.clear(),
+                Value::Message(message) => message.fields.clear_retaining(),
+                _ => (),
+            }
+            *value = ValueOrUnknown::Cleared(spare);
+            true
+        })
+    }
+}
+
+/// Moves the storage of `spare`, which has been emptied by
+/// [`clear_retaining`](DynamicMessageFieldSet::clear_retaining), into the default value `value`.
+fn reuse_allocation(value: &mut Value, spare: Value) {
+    match (value, spare) {
+        (Value::String(value), Value::String(mut spare)) => {
+            spare.push_str(value);
+            *value = spare;
+        }
+        (Value::List(values), Value::List(spare)) if values.is_empty() => *values = spare,
+        (Value::Map(values), Value::Map(spare)) if values.is_empty() => *values = spare,
+        (Value::Message(message), Value::Message(spare)) if message.desc == spare.desc => {
+            *message = spare
+        }
+        _ => (),
     }
 }
 
 impl ValueOrUnknown {
-    fn unwrap_value_mut(&mut self) -> &mut Value {
+    fn as_value(&self) -> Option<&Value> {
         match self {
-            ValueOrUnknown::Value(value) => value,
-            ValueOrUnknown::Unknown(_) => unreachable!(),
+            ValueOrUnknown::Value(value) => Some(value),
+            ValueOrUnknown::Unknown(_) | ValueOrUnknown::Cleared(_) => None,
         }
     }
+
+    fn as_value_mut(&mut self) -> Option<&mut Value> {
+        match self {
+            ValueOrUnknown::Value(value) => Some(value),
+            ValueOrUnknown::Unknown(_) | ValueOrUnknown::Cleared(_) => None,
+        }
+    }
+
+    fn unwrap_value_mut(&mut self) -> &mut Value {
+        self.as_value_mut().unwrap()
+    }
 }
 
 impl FieldDescriptorLike for FieldDescriptor {
@@ -304,3 +574,53 @@ impl FieldDescriptorLike for ExtensionDescriptor {
         self.is_packable()
     }
 }
+
+impl<'a> FieldDescriptorLike for PackedOverride<'a> {
+    fn number(&self) -> u32 {
+        self.desc.number()
+    }
+
+    fn default_value(&self) -> Value {
+        Value::default_value_for_field(self.desc)
+    }
+
+    fn is_default_value(&self, value: &Value) -> bool {
+        value.is_default_for_field(self.desc)
+    }
+
+    fn is_valid(&self, value: &Value) -> bool {
+        value.is_valid_for_field(self.desc)
+    }
+
+    fn containing_oneof(&self) -> Option<OneofDescriptor> {
+        self.desc.containing_oneof()
+    }
+
+    fn supports_presence(&self) -> bool {
+        self.desc.supports_presence()
+    }
+
+    fn kind(&self) -> Kind {
+        self.desc.kind()
+    }
+
+    fn is_group(&self) -> bool {
+        self.desc.is_group()
+    }
+
+    fn is_list(&self) -> bool {
+        self.desc.is_list()
+    }
+
+    fn is_map(&self) -> bool {
+        self.desc.is_map()
+    }
+
+    fn is_packed(&self) -> bool {
+        self.packed && self.desc.is_packable()
+    }
+
+    fn is_packable(&self) -> bool {
+        self.desc.is_packable()
+    }
+}
@@ -0,0 +1,339 @@
+use std::{cmp::Ordering, collections::BTreeMap, fmt};
+
+use crate::{DynamicMessage, Kind, MapKey, MessageDescriptor, Value};
+
+use super::{
+    diff::{push_path_segment, split_path},
+    fields::{DynamicMessageFieldSet, FieldDescriptorLike, ValueAndDescriptor},
+};
+
+/// Error type returned by [`DynamicMessage::from_flat_map`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FromFlatMapError {
+    key: String,
+    kind: FromFlatMapErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FromFlatMapErrorKind {
+    FieldNotFound { name: String, message: String },
+    InvalidValue { type_name: &'static str },
+    InvalidIndex { index: String },
+    IndexOutOfOrder { index: usize, expected: usize },
+    InvalidMapKey { key: String },
+    NotAMessage { name: String },
+}
+
+impl DynamicMessage {
+    /// Flattens this message into a map from dotted field paths to values.
+    ///
+    /// Fields of nested messages are included with paths such as `nested.int32`, elements of
+    /// repeated fields with their index, for example `items.0.name`, and values of map fields
+    /// with their key, for example `map.key`. Extension fields use their full name in brackets,
+    /// as in the text format. Only scalar and enum values appear in the output, so empty
+    /// messages, lists and maps are omitted. Unknown fields are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+    /// let flat_map = dynamic_message.to_flat_map();
+    /// assert_eq!(flat_map.len(), 1);
+    /// assert_eq!(flat_map["foo"], Value::I32(150));
+    /// ```
+    pub fn to_flat_map(&self) -> BTreeMap<String, Value> {
+        let mut map = BTreeMap::new();
+        flatten_message(self, &mut String::new(), &mut map);
+        map
+    }
+
+    /// Builds a message of the given type from a map of dotted field paths to values.
+    ///
+    /// This is the inverse of [`to_flat_map`][Self::to_flat_map]. Intermediate messages are created
+    /// as needed, list indices must be contiguous starting from zero, and map keys are parsed
+    /// according to the key type of the map field. Note that map keys containing a `.` cannot be
+    /// represented, and a list containing an empty message cannot be rebuilt, because
+    /// `to_flat_map` omits the empty element and so leaves a gap in the indices.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, which includes the offending key, if a path refers to a field which does
+    /// not exist, a list index is missing or invalid, a map key cannot be parsed, or a value is not
+    /// valid for its field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::BTreeMap;
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut flat_map = BTreeMap::new();
+    /// flat_map.insert("foo".to_owned(), Value::I32(150));
+    /// let dynamic_message = DynamicMessage::from_flat_map(message_descriptor.clone(), &flat_map).unwrap();
+    /// assert_eq!(dynamic_message.encode_to_vec(), b"\x08\x96\x01");
+    ///
+    /// flat_map.insert("foo".to_owned(), Value::Bool(true));
+    /// let err = DynamicMessage::from_flat_map(message_descriptor, &flat_map).unwrap_err();
+    /// assert_eq!(err.to_string(), "invalid key 'foo': a value of type 'bool' is not valid for this field");
+    /// ```
+    pub fn from_flat_map(
+        desc: MessageDescriptor,
+        map: &BTreeMap<String, Value>,
+    ) -> Result<Self, FromFlatMapError> {
+        // Sort list indices numerically, so that the elements of each list are visited in order.
+        let mut entries: Vec<_> = map
+            .iter()
+            .map(|(key, value)| (split_path(key), key, value))
+            .collect();
+        entries.sort_by(|(l, _, _), (r, _, _)| cmp_path(l, r));
+
+        let mut message = DynamicMessage::new(desc);
+        for (path, key, value) in entries {
+            set_path(&mut message, &path, value).map_err(|kind| FromFlatMapError {
+                key: key.clone(),
+                kind,
+            })?;
+        }
+        Ok(message)
+    }
+}
+
+fn flatten_message(message: &DynamicMessage, path: &mut String, map: &mut BTreeMap<String, Value>) {
+    for field in message.fields.iter(&message.desc) {
+        let len = path.len();
+        match field {
+            ValueAndDescriptor::Field(value, field_desc) => {
+                push_path_segment(path, field_desc.name());
+                flatten_value(&value, path, map);
+            }
+            ValueAndDescriptor::Extension(value, extension_desc) => {
+                push_path_segment(path, &format!("[{}]", extension_desc.full_name()));
+                flatten_value(&value, path, map);
+            }
+            ValueAndDescriptor::Unknown(..) => (),
+        }
+        path.truncate(len);
+    }
+}
+
+fn flatten_value(value: &Value, path: &mut String, map: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Message(message) => flatten_message(message, path, map),
+        Value::List(values) => {
+            for (index, value) in values.iter().enumerate() {
+                let len = path.len();
+                push_path_segment(path, &index.to_string());
+                flatten_value(value, path, map);
+                path.truncate(len);
+            }
+        }
+        Value::Map(values) => {
+            for (key, value) in values {
+                let len = path.len();
+                match key {
+                    MapKey::Bool(key) => push_path_segment(path, &key.to_string()),
+                    MapKey::I32(key) => push_path_segment(path, &key.to_string()),
+                    MapKey::I64(key) => push_path_segment(path, &key.to_string()),
+                    MapKey::U32(key) => push_path_segment(path, &key.to_string()),
+                    MapKey::U64(key) => push_path_segment(path, &key.to_string()),
+                    MapKey::String(key) => push_path_segment(path, key),
+                }
+                flatten_value(value, path, map);
+                path.truncate(len);
+            }
+        }
+        value => {
+            map.insert(path.clone(), value.clone());
+        }
+    }
+}
+
+fn cmp_path(l: &[&str], r: &[&str]) -> Ordering {
+    for (l, r) in l.iter().zip(r) {
+        let ordering = match (l.parse::<usize>(), r.parse::<usize>()) {
+            (Ok(l), Ok(r)) => l.cmp(&r),
+            _ => l.cmp(r),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    l.len().cmp(&r.len())
+}
+
+fn set_path(
+    message: &mut DynamicMessage,
+    path: &[&str],
+    value: &Value,
+) -> Result<(), FromFlatMapErrorKind> {
+    let (name, rest) = path
+        .split_first()
+        .expect("split_path returns at least one segment");
+    let field_not_found = || FromFlatMapErrorKind::FieldNotFound {
+        name: name.to_string(),
+        message: message.desc.full_name().to_owned(),
+    };
+
+    if let Some(extension_name) = name.strip_prefix('[').and_then(|n| n.strip_suffix(']')) {
+        let extension_desc = message
+            .desc
+            .get_extension_by_full_name(extension_name)
+            .ok_or_else(field_not_found)?;
+        set_field_path(&mut message.fields, &extension_desc, name, rest, value)
+    } else {
+        let field_desc = message
+            .desc
+            .get_field_by_name(name)
+            .ok_or_else(field_not_found)?;
+        set_field_path(&mut message.fields, &field_desc, name, rest, value)
+    }
+}
+
+fn set_field_path(
+    fields: &mut DynamicMessageFieldSet,
+    desc: &impl FieldDescriptorLike,
+    name: &str,
+    path: &[&str],
+    value: &Value,
+) -> Result<(), FromFlatMapErrorKind> {
+    let (segment, rest) = match path.split_first() {
+        Some(split) => split,
+        None if desc.is_valid(value) => {
+            fields.set(desc, value.clone());
+            return Ok(());
+        }
+        None => {
+            return Err(FromFlatMapErrorKind::InvalidValue {
+                type_name: value.type_name(),
+            })
+        }
+    };
+
+    let kind = desc.kind();
+    match fields.get_mut(desc) {
+        Value::Message(message) => set_path(message, path, value),
+        Value::List(values) => {
+            let index =
+                segment
+                    .parse::<usize>()
+                    .map_err(|_| FromFlatMapErrorKind::InvalidIndex {
+                        index: segment.to_string(),
+                    })?;
+            if index == values.len() {
+                values.push(Value::default_value(&kind));
+            } else if index + 1 != values.len() {
+                return Err(FromFlatMapErrorKind::IndexOutOfOrder {
+                    index,
+                    expected: values.len(),
+                });
+            }
+            let element = values.last_mut().expect("list is not empty");
+            set_element_path(element, &kind, name, rest, value)
+        }
+        Value::Map(values) => {
+            let map_entry = match kind {
+                Kind::Message(map_entry) => map_entry,
+                _ => unreachable!("map field must have a message type"),
+            };
+            let key = parse_map_key(segment, &map_entry.map_entry_key_field().kind()).ok_or_else(
+                || FromFlatMapErrorKind::InvalidMapKey {
+                    key: segment.to_string(),
+                },
+            )?;
+            let value_kind = map_entry.map_entry_value_field().kind();
+            let element = values
+                .entry(key)
+                .or_insert_with(|| Value::default_value(&value_kind));
+            set_element_path(element, &value_kind, name, rest, value)
+        }
+        _ => Err(FromFlatMapErrorKind::NotAMessage {
+            name: name.to_owned(),
+        }),
+    }
+}
+
+/// Sets the value at `path` within an element of a list or map.
+fn set_element_path(
+    element: &mut Value,
+    kind: &Kind,
+    name: &str,
+    path: &[&str],
+    value: &Value,
+) -> Result<(), FromFlatMapErrorKind> {
+    if path.is_empty() {
+        if !value.is_valid(kind) {
+            return Err(FromFlatMapErrorKind::InvalidValue {
+                type_name: value.type_name(),
+            });
+        }
+        *element = value.clone();
+        Ok(())
+    } else if let Value::Message(message) = element {
+        set_path(message, path, value)
+    } else {
+        Err(FromFlatMapErrorKind::NotAMessage {
+            name: name.to_owned(),
+        })
+    }
+}
+
+fn parse_map_key(key: &str, kind: &Kind) -> Option<MapKey> {
+    match kind {
+        Kind::Bool => key.parse().ok().map(MapKey::Bool),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => key.parse().ok().map(MapKey::I32),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => key.parse().ok().map(MapKey::I64),
+        Kind::Uint32 | Kind::Fixed32 => key.parse().ok().map(MapKey::U32),
+        Kind::Uint64 | Kind::Fixed64 => key.parse().ok().map(MapKey::U64),
+        Kind::String => Some(MapKey::String(key.to_owned())),
+        _ => None,
+    }
+}
+
+impl FromFlatMapError {
+    /// Gets the key of the flat map which could not be set.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl fmt::Display for FromFlatMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid key '{}': ", self.key)?;
+        match &self.kind {
+            FromFlatMapErrorKind::FieldNotFound { name, message } => {
+                write!(f, "field '{}' not found in message '{}'", name, message)
+            }
+            FromFlatMapErrorKind::InvalidValue { type_name } => {
+                write!(
+                    f,
+                    "a value of type '{}' is not valid for this field",
+                    type_name
+                )
+            }
+            FromFlatMapErrorKind::InvalidIndex { index } => {
+                write!(f, "'{}' is not a valid list index", index)
+            }
+            FromFlatMapErrorKind::IndexOutOfOrder { index, expected } => {
+                write!(
+                    f,
+                    "list index {} is out of order, expected {}",
+                    index, expected
+                )
+            }
+            FromFlatMapErrorKind::InvalidMapKey { key } => {
+                write!(f, "'{}' is not a valid map key", key)
+            }
+            FromFlatMapErrorKind::NotAMessage { name } => {
+                write!(f, "field '{}' does not contain a message", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FromFlatMapError {}
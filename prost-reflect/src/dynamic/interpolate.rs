@@ -0,0 +1,130 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{DynamicMessage, Value};
+
+/// Error type returned by [`DynamicMessage::try_interpolate`] if a string refers to a variable
+/// which is not defined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterpolateError {
+    variable: String,
+}
+
+impl DynamicMessage {
+    /// Replaces each `${name}` token in the string fields of this message with the value of the
+    /// variable `name` in `vars`.
+    ///
+    /// This applies to singular and repeated string fields and string map values, in this message
+    /// and in all nested messages, including those contained in lists and maps, and those set in
+    /// extension fields. Map keys and fields of other types are not changed. Tokens which refer to
+    /// a variable not in `vars` are left unchanged, and replaced values are not searched for
+    /// further tokens.
+    pub fn interpolate(&mut self, vars: &HashMap<String, String>) {
+        let result = self.interpolate_inner(vars, false);
+        debug_assert!(result.is_ok());
+    }
+
+    /// Replaces each `${name}` token in the string fields of this message with the value of the
+    /// variable `name` in `vars`, returning an error if a variable is not defined.
+    ///
+    /// See [`interpolate`][Self::interpolate] for which fields are affected. If an error is
+    /// returned, some tokens may already have been replaced.
+    pub fn try_interpolate(
+        &mut self,
+        vars: &HashMap<String, String>,
+    ) -> Result<(), InterpolateError> {
+        self.interpolate_inner(vars, true)
+    }
+
+    fn interpolate_inner(
+        &mut self,
+        vars: &HashMap<String, String>,
+        strict: bool,
+    ) -> Result<(), InterpolateError> {
+        for value in self.fields.values_mut() {
+            interpolate_value(value, vars, strict)?;
+        }
+        Ok(())
+    }
+}
+
+fn interpolate_value(
+    value: &mut Value,
+    vars: &HashMap<String, String>,
+    strict: bool,
+) -> Result<(), InterpolateError> {
+    match value {
+        Value::String(string) => {
+            if let Some(replaced) = interpolate_str(string, vars, strict)? {
+                *string = replaced;
+            }
+        }
+        Value::Message(message) => message.interpolate_inner(vars, strict)?,
+        Value::List(list) => {
+            for value in list {
+                interpolate_value(value, vars, strict)?;
+            }
+        }
+        Value::Map(map) => {
+            for value in map.values_mut() {
+                interpolate_value(value, vars, strict)?;
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Replaces the tokens in `string`, or returns `None` if it contains no tokens.
+fn interpolate_str(
+    string: &str,
+    vars: &HashMap<String, String>,
+    strict: bool,
+) -> Result<Option<String>, InterpolateError> {
+    if !string.contains("${") {
+        return Ok(None);
+    }
+
+    let mut result = String::with_capacity(string.len());
+    let mut rest = string;
+    while let Some(start) = rest.find("${") {
+        let (before, token) = rest.split_at(start);
+        result.push_str(before);
+
+        let end = match token.find('}') {
+            Some(end) => end,
+            None => {
+                rest = token;
+                break;
+            }
+        };
+        let name = &token[2..end];
+        match vars.get(name) {
+            Some(value) => result.push_str(value),
+            None if strict => {
+                return Err(InterpolateError {
+                    variable: name.to_owned(),
+                })
+            }
+            None => result.push_str(&token[..=end]),
+        }
+        rest = &token[end + 1..];
+    }
+    result.push_str(rest);
+
+    Ok(Some(result))
+}
+
+impl InterpolateError {
+    /// Gets the name of the variable which was not defined.
+    pub fn variable(&self) -> &str {
+        &self.variable
+    }
+}
+
+impl fmt::Display for InterpolateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "variable '{}' is not defined", self.variable)
+    }
+}
+
+impl std::error::Error for InterpolateError {}
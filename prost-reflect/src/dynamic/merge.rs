@@ -0,0 +1,118 @@
+use std::fmt;
+
+use crate::{DynamicMessage, MessageDescriptor, Value};
+
+use super::fields::{DynamicMessageFieldSet, FieldDescriptorLike, ValueAndDescriptor};
+
+/// Error type returned by [`DynamicMessage::merge_from`] if the messages have different types.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeError {
+    expected: MessageDescriptor,
+    actual: MessageDescriptor,
+}
+
+impl DynamicMessage {
+    /// Merges the fields of `other` into this message.
+    ///
+    /// This has the same result as encoding `other` and merging the bytes into this message with
+    /// [`merge`][prost::Message::merge], but without the intermediate buffer. Singular fields set in
+    /// `other` overwrite the value in this message, except for message fields, which are merged
+    /// recursively. Repeated fields are appended, and map entries replace any existing entry with
+    /// the same key. Extension fields follow the same rules, and unknown fields are appended. An
+    /// unknown field in `other` with the same number as a field set in this message, such as a field
+    /// decoded with [`allow_wire_type_mismatch`][crate::DecodeOptions::allow_wire_type_mismatch],
+    /// replaces its value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `other` does not have the same message type as this message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_types::FileDescriptorSet;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut message = DynamicMessage::new(message_descriptor.clone());
+    /// message.set_field_by_name("foo", Value::I32(1));
+    ///
+    /// let mut other = DynamicMessage::new(message_descriptor);
+    /// other.set_field_by_name("foo", Value::I32(2));
+    ///
+    /// message.merge_from(&other).unwrap();
+    /// assert_eq!(message.get_field_by_name("foo").unwrap().as_ref(), &Value::I32(2));
+    /// ```
+    pub fn merge_from(&mut self, other: &DynamicMessage) -> Result<(), MergeError> {
+        if self.desc != other.desc {
+            return Err(MergeError {
+                expected: self.desc.clone(),
+                actual: other.desc.clone(),
+            });
+        }
+
+        self.merge_fields(other);
+        Ok(())
+    }
+
+    fn merge_fields(&mut self, other: &DynamicMessage) {
+        for field in other.fields.iter(&other.desc) {
+            match field {
+                ValueAndDescriptor::Field(value, field_desc) => {
+                    merge_value(&mut self.fields, &field_desc, &value)
+                }
+                ValueAndDescriptor::Extension(value, extension_desc) => {
+                    merge_value(&mut self.fields, &extension_desc, &value)
+                }
+                ValueAndDescriptor::Unknown(number, unknowns) => {
+                    for unknown in unknowns {
+                        self.fields.merge_unknown(number, unknown.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn merge_value(
+    fields: &mut DynamicMessageFieldSet,
+    desc: &impl FieldDescriptorLike,
+    value: &Value,
+) {
+    match (fields.get_mut(desc), value) {
+        (Value::Message(message), Value::Message(other)) => message.merge_fields(other),
+        (Value::List(list), Value::List(other)) => list.extend(other.iter().cloned()),
+        (Value::Map(map), Value::Map(other)) => map.extend(
+            other
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        ),
+        (field_value, value) => *field_value = value.clone(),
+    }
+}
+
+impl MergeError {
+    /// Gets the type of the message which was merged into.
+    pub fn expected(&self) -> &MessageDescriptor {
+        &self.expected
+    }
+
+    /// Gets the type of the message which could not be merged.
+    pub fn actual(&self) -> &MessageDescriptor {
+        &self.actual
+    }
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot merge a message of type '{}' into a message of type '{}'",
+            self.actual.full_name(),
+            self.expected.full_name()
+        )
+    }
+}
+
+impl std::error::Error for MergeError {}
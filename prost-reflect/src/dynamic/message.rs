@@ -1,3 +1,8 @@
+use std::{
+    io::{self, Write},
+    mem,
+};
+
 use prost::{
     bytes::{Buf, BufMut},
     encoding::{DecodeContext, WireType},
@@ -6,7 +11,7 @@ use prost::{
 
 use crate::{
     descriptor::{FieldDescriptor, Kind, MAP_ENTRY_KEY_NUMBER, MAP_ENTRY_VALUE_NUMBER},
-    DynamicMessage, MapKey, Value,
+    DecodeOptions, DynamicMessage, MapKey, Value,
 };
 
 use super::{
@@ -21,19 +26,7 @@ impl Message for DynamicMessage {
         Self: Sized,
     {
         for field in self.fields.iter(&self.desc) {
-            match field {
-                ValueAndDescriptor::Field(value, field_desc) => {
-                    value.encode_field(&field_desc, buf)
-                }
-                ValueAndDescriptor::Extension(value, extension_desc) => {
-                    value.encode_field(&extension_desc, buf)
-                }
-                ValueAndDescriptor::Unknown(number, unknowns) => {
-                    for unknown in unknowns {
-                        unknown.encode_field(number, buf);
-                    }
-                }
-            }
+            self.encode_field(field, buf);
         }
     }
 
@@ -48,21 +41,7 @@ impl Message for DynamicMessage {
         B: Buf,
         Self: Sized,
     {
-        if let Some(field_desc) = self.desc.get_field(number) {
-            self.get_field_mut(&field_desc)
-                .merge_field(&field_desc, wire_type, buf, ctx)
-        } else if let Some(extension_desc) = self.desc.get_extension(number) {
-            self.get_extension_mut(&extension_desc).merge_field(
-                &extension_desc,
-                wire_type,
-                buf,
-                ctx,
-            )
-        } else {
-            let field = UnknownField::decode(number, wire_type, buf, ctx)?;
-            self.fields.add_unknown(number, field);
-            Ok(())
-        }
+        self.merge_field_with_options(number, wire_type, buf, ctx, &DecodeOptions::new())
     }
 
     fn encoded_len(&self) -> usize {
@@ -70,7 +49,10 @@ impl Message for DynamicMessage {
         for field in self.fields.iter(&self.desc) {
             match field {
                 ValueAndDescriptor::Field(value, field_desc) => {
-                    len += value.encoded_len(&field_desc);
+                    len += match self.fields.packed_override(&field_desc) {
+                        Some(field_desc) => value.encoded_len(&field_desc),
+                        None => value.encoded_len(&field_desc),
+                    };
                 }
                 ValueAndDescriptor::Extension(value, extension_desc) => {
                     len += value.encoded_len(&extension_desc);
@@ -90,6 +72,237 @@ impl Message for DynamicMessage {
     }
 }
 
+impl DynamicMessage {
+    pub(super) fn encode_to_writer_impl<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut buf = Vec::new();
+        for field in self.fields.iter(&self.desc) {
+            buf.clear();
+            self.encode_field(field, &mut buf);
+            writer.write_all(&buf)?;
+        }
+        Ok(())
+    }
+
+    pub(super) fn encode_field<B>(&self, field: ValueAndDescriptor, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        match field {
+            ValueAndDescriptor::Field(value, field_desc) => {
+                match self.fields.packed_override(&field_desc) {
+                    Some(field_desc) => value.encode_field(&field_desc, buf),
+                    None => value.encode_field(&field_desc, buf),
+                }
+            }
+            ValueAndDescriptor::Extension(value, extension_desc) => {
+                value.encode_field(&extension_desc, buf)
+            }
+            ValueAndDescriptor::Unknown(number, unknowns) => {
+                for unknown in unknowns {
+                    unknown.encode_field(number, buf);
+                }
+            }
+        }
+    }
+}
+
+pub(super) fn decode_field<B>(
+    field_desc: &impl FieldDescriptorLike,
+    mut buf: B,
+) -> Result<Option<Value>, DecodeError>
+where
+    B: Buf,
+{
+    let ctx = DecodeContext::default();
+    let oneof = field_desc.containing_oneof();
+
+    let mut value = None;
+    while buf.has_remaining() {
+        let (number, wire_type) = prost::encoding::decode_key(&mut buf)?;
+        if number == field_desc.number() {
+            value
+                .get_or_insert_with(|| field_desc.default_value())
+                .merge_field(
+                    field_desc,
+                    wire_type,
+                    &mut buf,
+                    ctx.clone(),
+                    &DecodeOptions::new(),
+                )?;
+        } else {
+            // Decoding a later field in the same oneof would clear this field.
+            if let Some(oneof) = &oneof {
+                if oneof.fields().any(|f| f.number() == number) {
+                    value = None;
+                }
+            }
+            prost::encoding::skip_field(wire_type, number, &mut buf, ctx.clone())?;
+        }
+    }
+    Ok(value)
+}
+
+impl DynamicMessage {
+    pub(super) fn merge_field_with_options<B>(
+        &mut self,
+        number: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+        options: &DecodeOptions,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        if let Some(field_desc) = self.desc.get_field(number) {
+            if options.allow_wire_type_mismatch && !accepts_wire_type(&field_desc, wire_type) {
+                let field = UnknownField::decode(number, wire_type, buf, ctx)?;
+                self.fields.add_mismatched(number, field);
+                Ok(())
+            } else {
+                self.get_field_mut(&field_desc).merge_field(
+                    &field_desc,
+                    wire_type,
+                    buf,
+                    ctx,
+                    options,
+                )
+            }
+        } else if let Some(extension_desc) = self.desc.get_extension(number) {
+            if options.allow_wire_type_mismatch && !accepts_wire_type(&extension_desc, wire_type) {
+                let field = UnknownField::decode(number, wire_type, buf, ctx)?;
+                self.fields.add_mismatched(number, field);
+                Ok(())
+            } else {
+                self.get_extension_mut(&extension_desc).merge_field(
+                    &extension_desc,
+                    wire_type,
+                    buf,
+                    ctx,
+                    options,
+                )
+            }
+        } else {
+            let field = UnknownField::decode(number, wire_type, buf, ctx)?;
+            self.fields.add_unknown(number, field);
+            Ok(())
+        }
+    }
+}
+
+/// Decodes a string value, reporting the position of the first invalid byte if the value is not
+/// valid UTF-8, or replacing invalid sequences if [`DecodeOptions::validate_utf8`] is disabled.
+fn merge_string<B>(
+    wire_type: WireType,
+    value: &mut String,
+    buf: &mut B,
+    ctx: DecodeContext,
+    options: &DecodeOptions,
+) -> Result<(), DecodeError>
+where
+    B: Buf,
+{
+    let mut bytes = mem::take(value).into_bytes();
+    prost::encoding::bytes::merge(wire_type, &mut bytes, buf, ctx)?;
+    match String::from_utf8(bytes) {
+        Ok(string) => {
+            *value = string;
+            Ok(())
+        }
+        Err(err) if options.validate_utf8 => Err(DecodeError::new(format!(
+            "invalid string value: data is not UTF-8 encoded (invalid byte at offset {})",
+            err.utf8_error().valid_up_to()
+        ))),
+        Err(err) => {
+            *value = String::from_utf8_lossy(err.as_bytes()).into_owned();
+            Ok(())
+        }
+    }
+}
+
+impl DecodeOptions {
+    /// Gets the options to use for decoding a nested message, or returns an error if the
+    /// recursion limit has been reached.
+    fn enter_message(&self) -> Result<DecodeOptions, DecodeError> {
+        match self.recursion_limit.checked_sub(1) {
+            Some(recursion_limit) => Ok(DecodeOptions {
+                recursion_limit,
+                ..self.clone()
+            }),
+            None => Err(DecodeError::new("recursion limit reached")),
+        }
+    }
+}
+
+/// Gets the length of the longest prefix of `buf` which consists of complete, well-formed fields.
+pub(super) fn well_formed_prefix_len(buf: &[u8]) -> usize {
+    let mut rest = buf;
+    while !rest.is_empty() {
+        let mut field = rest;
+        let result = prost::encoding::decode_key(&mut field).and_then(|(number, wire_type)| {
+            prost::encoding::skip_field(wire_type, number, &mut field, DecodeContext::default())
+        });
+        if result.is_err() {
+            break;
+        }
+        rest = field;
+    }
+    buf.len() - rest.len()
+}
+
+/// Wraps a nested message being decoded, so that the decode options are used for its fields too.
+#[derive(Debug)]
+struct MergeWithOptions<'a> {
+    message: &'a mut DynamicMessage,
+    options: &'a DecodeOptions,
+}
+
+impl<'a> Message for MergeWithOptions<'a> {
+    fn encode_raw<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+        Self: Sized,
+    {
+        self.message.encode_raw(buf)
+    }
+
+    fn merge_field<B>(
+        &mut self,
+        number: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+        Self: Sized,
+    {
+        self.message
+            .merge_field_with_options(number, wire_type, buf, ctx, self.options)
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.message.encoded_len()
+    }
+
+    fn clear(&mut self) {
+        self.message.clear()
+    }
+}
+
+fn accepts_wire_type(field_desc: &impl FieldDescriptorLike, wire_type: WireType) -> bool {
+    if field_desc.is_list() && field_desc.is_packable() && wire_type == WireType::LengthDelimited {
+        true
+    } else if field_desc.is_group() {
+        wire_type == WireType::StartGroup
+    } else {
+        wire_type == field_desc.kind().wire_type()
+    }
+}
+
 impl Value {
     pub(super) fn encode_field<B>(&self, field_desc: &impl FieldDescriptorLike, buf: &mut B)
     where
@@ -99,6 +312,17 @@ impl Value {
             return;
         }
 
+        self.encode_field_unconditionally(field_desc, buf)
+    }
+
+    /// Encodes this value, even if it is the default value of a field without presence.
+    pub(super) fn encode_field_unconditionally<B>(
+        &self,
+        field_desc: &impl FieldDescriptorLike,
+        buf: &mut B,
+    ) where
+        B: BufMut,
+    {
         let number = field_desc.number();
         match (self, field_desc.kind()) {
             (Value::Bool(value), Kind::Bool) => prost::encoding::bool::encode(number, value, buf),
@@ -286,6 +510,7 @@ impl Value {
         wire_type: WireType,
         buf: &mut B,
         ctx: DecodeContext,
+        options: &DecodeOptions,
     ) -> Result<(), DecodeError>
     where
         B: Buf,
@@ -331,32 +556,56 @@ impl Value {
                 prost::encoding::double::merge(wire_type, value, buf, ctx)
             }
             (Value::String(value), Kind::String) => {
-                prost::encoding::string::merge(wire_type, value, buf, ctx)
+                merge_string(wire_type, value, buf, ctx, options)
             }
             (Value::Bytes(value), Kind::Bytes) => {
                 prost::encoding::bytes::merge(wire_type, value, buf, ctx)
             }
-            (Value::EnumNumber(value), Kind::Enum(_)) => {
-                prost::encoding::int32::merge(wire_type, value, buf, ctx)
+            (Value::EnumNumber(value), Kind::Enum(enum_desc)) => {
+                prost::encoding::int32::merge(wire_type, value, buf, ctx)?;
+                if options.strict_enums && !enum_desc.contains_number(*value) {
+                    return Err(DecodeError::new(format!(
+                        "invalid value {} for enum {}",
+                        value,
+                        enum_desc.full_name()
+                    )));
+                }
+                Ok(())
             }
             (Value::Message(message), Kind::Message(_)) => {
+                let options = options.enter_message()?;
+                let mut message = MergeWithOptions {
+                    message,
+                    options: &options,
+                };
                 if field_desc.is_group() {
-                    prost::encoding::group::merge(field_desc.number(), wire_type, message, buf, ctx)
+                    prost::encoding::group::merge(
+                        field_desc.number(),
+                        wire_type,
+                        &mut message,
+                        buf,
+                        ctx,
+                    )
                 } else {
-                    prost::encoding::message::merge(wire_type, message, buf, ctx)
+                    prost::encoding::message::merge(wire_type, &mut message, buf, ctx)
                 }
             }
             (Value::List(values), field_kind) if field_desc.is_list() => {
                 if wire_type == WireType::LengthDelimited && field_desc.is_packable() {
                     prost::encoding::merge_loop(values, buf, ctx, |values, buf, ctx| {
                         let mut value = Value::default_value(&field_kind);
-                        value.merge_field(field_desc, field_kind.wire_type(), buf, ctx)?;
+                        value.merge_field(field_desc, field_kind.wire_type(), buf, ctx, options)?;
                         values.push(value);
                         Ok(())
                     })
                 } else {
-                    let mut value = Value::default_value(&field_kind);
-                    value.merge_field(field_desc, wire_type, buf, ctx)?;
+                    // Reuse the descriptor in `field_kind` for the new element, rather than
+                    // cloning it again, since this is hit for every element of a repeated message field.
+                    let mut value = match field_kind {
+                        Kind::Message(desc) => Value::Message(DynamicMessage::new(desc)),
+                        field_kind => Value::default_value(&field_kind),
+                    };
+                    value.merge_field(field_desc, wire_type, buf, ctx, options)?;
                     values.push(value);
                     Ok(())
                 }
@@ -374,9 +623,17 @@ impl Value {
                     |(key, value), buf, ctx| {
                         let (number, wire_type) = prost::encoding::decode_key(buf)?;
                         match number {
-                            MAP_ENTRY_KEY_NUMBER => key.merge_field(&key_desc, wire_type, buf, ctx),
-                            MAP_ENTRY_VALUE_NUMBER => {
-                                value.merge_field(&value_desc, wire_type, buf, ctx)
+                            MAP_ENTRY_KEY_NUMBER
+                                if !options.allow_wire_type_mismatch
+                                    || accepts_wire_type(&key_desc, wire_type) =>
+                            {
+                                key.merge_field(&key_desc, wire_type, buf, ctx, options)
+                            }
+                            MAP_ENTRY_VALUE_NUMBER
+                                if !options.allow_wire_type_mismatch
+                                    || accepts_wire_type(&value_desc, wire_type) =>
+                            {
+                                value.merge_field(&value_desc, wire_type, buf, ctx, options)
                             }
                             _ => prost::encoding::skip_field(wire_type, number, buf, ctx),
                         }
@@ -607,6 +864,7 @@ impl MapKey {
         wire_type: WireType,
         buf: &mut B,
         ctx: DecodeContext,
+        options: &DecodeOptions,
     ) -> Result<(), DecodeError>
     where
         B: Buf,
@@ -646,7 +904,7 @@ impl MapKey {
                 prost::encoding::fixed64::merge(wire_type, value, buf, ctx)
             }
             (MapKey::String(value), Kind::String) => {
-                prost::encoding::string::merge(wire_type, value, buf, ctx)
+                merge_string(wire_type, value, buf, ctx, options)
             }
             (value, ty) => panic!(
                 "mismatch between DynamicMessage value {:?} and type {:?}",
@@ -1,30 +1,71 @@
+mod builder;
+mod convert;
+mod diff;
+mod encode_report;
 mod fields;
+mod flatten;
 mod fmt;
+mod interpolate;
+mod merge;
 mod message;
 #[cfg(feature = "serde")]
 mod serde;
+mod transcode;
 mod unknown;
 
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    io::{self, Write},
+    mem,
+};
+
+pub use self::{
+    builder::{BuildMessageError, DynamicMessageBuilder},
+    convert::{ConversionError, TryFromValue},
+    encode_report::EncodeReport,
+    flatten::FromFlatMapError,
+    interpolate::InterpolateError,
+    merge::MergeError,
+    transcode::{TranscodeIssue, TranscodeReport},
+};
 
 #[cfg(feature = "serde")]
 pub use self::serde::{DeserializeOptions, SerializeOptions};
 
 use prost::{
     bytes::{Buf, Bytes},
+    encoding::DecodeContext,
     DecodeError, Message,
 };
 
 use self::fields::DynamicMessageFieldSet;
 use crate::{
-    descriptor::Kind, ExtensionDescriptor, FieldDescriptor, MessageDescriptor, ReflectMessage,
+    descriptor::Kind, DescriptorPool, EnumDescriptor, EnumValueDescriptor, ExtensionDescriptor,
+    FieldDescriptor, MessageDescriptor, OneofDescriptor, ReflectMessage, WellKnownType,
 };
 
 /// [`DynamicMessage`] provides encoding, decoding and reflection of a protobuf message.
 ///
 /// It wraps a [`MessageDescriptor`] and the [`Value`] for each field of the message, and implements
 /// [`Message`][`prost::Message`].
-#[derive(Debug, Clone, PartialEq)]
+///
+/// The [`Display`][std::fmt::Display] implementation formats the message using the protobuf text format, on a single
+/// line unless the alternate format specifier is used. This is suitable for logging. The [`Debug`] implementation
+/// instead shows the internal representation, including the full message descriptor.
+///
+/// Two messages compare equal with [`PartialEq`] if they have the same descriptor and the same set of
+/// fields as reported by [`has_field`][DynamicMessage::has_field], with equal values. In particular, a
+/// field without presence tracking (such as a proto3 scalar field) which was explicitly set to its default
+/// value compares equal to the same field being unset, since neither is included in the encoded message.
+/// Unknown fields are also compared.
+///
+/// Floating point field values are compared as described for [`Value`], so a message containing NaN compares
+/// equal to itself. The [`Hash`] implementation is consistent with [`PartialEq`], and [`Eq`] is implemented so
+/// that messages can be used as keys in a [`HashMap`].
+#[derive(Debug, Clone)]
 pub struct DynamicMessage {
     desc: MessageDescriptor,
     fields: DynamicMessageFieldSet,
@@ -34,7 +75,19 @@ pub struct DynamicMessage {
 ///
 /// Note this type may map to multiple possible protobuf wire formats, so it must be
 /// serialized as part of a DynamicMessage.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Floating point values compare equal with [`PartialEq`] if they have the same bit representation,
+/// except that positive and negative zero are equal. In particular, a NaN value compares equal to
+/// itself. The [`Hash`] implementation is consistent with [`PartialEq`]. Map values are hashed in key
+/// order.
+///
+/// Scalar values of the same variant may be compared with [`PartialOrd`]. Numbers and enum values
+/// are ordered by value, strings and bytes lexicographically, and `false` before `true`. Floating
+/// point values are ordered as by [`f64::total_cmp`], except that positive and negative zero compare
+/// equal, so the ordering is consistent with [`PartialEq`]. See also [`Value::total_cmp`].
+/// Comparing values of different variants, or [`Message`][Value::Message], [`List`][Value::List] or
+/// [`Map`][Value::Map] values which are not equal, also returns `None`.
+#[derive(Debug, Clone)]
 pub enum Value {
     /// A boolean value, encoded as the `bool` protobuf type.
     Bool(bool),
@@ -65,6 +118,10 @@ pub enum Value {
 }
 
 /// A dynamically-typed key for a protobuf map.
+///
+/// Keys of the same type are ordered by value, with `false` before `true` and strings compared
+/// lexicographically by their bytes. Keys of different types are ordered by their variant, in
+/// the order they are declared here.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum MapKey {
     /// A boolean value, encoded as the `bool` protobuf type.
@@ -81,6 +138,46 @@ pub enum MapKey {
     String(String),
 }
 
+/// Error type returned by [`DynamicMessage::try_set_field`] and related methods.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetFieldError {
+    /// The message has no field with the given name.
+    NotFound {
+        /// The name of the field.
+        name: String,
+    },
+    /// The value type is not compatible with the field type, as defined by
+    /// [`Value::is_valid_for_field`].
+    InvalidType {
+        /// The field descriptor.
+        field: FieldDescriptor,
+        /// The invalid value.
+        value: Value,
+    },
+}
+
+/// Error type returned by [`Value::enum_value`] if a number is not defined by a closed enum.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumValueError {
+    enum_desc: EnumDescriptor,
+    number: i32,
+}
+
+/// The recursion limit enforced by `prost` when decoding nested messages.
+const MAX_RECURSION_LIMIT: u32 = 100;
+
+/// Options to control decoding of messages from the protobuf binary format.
+///
+/// See [`DynamicMessage::decode_with_options`] and [`DynamicMessage::merge_with_options`].
+#[derive(Debug, Clone)]
+pub struct DecodeOptions {
+    allow_wire_type_mismatch: bool,
+    strict_enums: bool,
+    recursion_limit: u32,
+    allow_trailing_bytes: bool,
+    validate_utf8: bool,
+}
+
 impl DynamicMessage {
     /// Creates a new, empty instance of [`DynamicMessage`] for the message type specified by the [`MessageDescriptor`].
     pub fn new(desc: MessageDescriptor) -> Self {
@@ -113,6 +210,239 @@ impl DynamicMessage {
         Ok(message)
     }
 
+    /// Decodes an instance of the message type specified by the [`MessageDescriptor`] from the buffer, using
+    /// the decoding behaviour specified by `options`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_types::FileDescriptorSet;
+    /// # use prost_reflect::{DecodeOptions, DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// // The field `foo` has type `int32`, but is encoded here with the 32-bit wire type.
+    /// let bytes = b"\x0d\x96\x00\x00\x00".as_ref();
+    /// assert!(DynamicMessage::decode(message_descriptor.clone(), bytes).is_err());
+    ///
+    /// let options = DecodeOptions::new().allow_wire_type_mismatch(true);
+    /// let dynamic_message = DynamicMessage::decode_with_options(message_descriptor, bytes, &options).unwrap();
+    /// assert!(!dynamic_message.has_field_by_name("foo"));
+    /// ```
+    pub fn decode_with_options<B>(
+        desc: MessageDescriptor,
+        buf: B,
+        options: &DecodeOptions,
+    ) -> Result<Self, DecodeError>
+    where
+        B: Buf,
+    {
+        let mut message = DynamicMessage::new(desc);
+        message.merge_with_options(buf, options)?;
+        Ok(message)
+    }
+
+    /// Decodes fields from the buffer and merges them into this message, using the decoding behaviour
+    /// specified by `options`.
+    ///
+    /// This behaves like [`Message::merge`], which uses the default options.
+    pub fn merge_with_options<B>(
+        &mut self,
+        mut buf: B,
+        options: &DecodeOptions,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        if options.allow_trailing_bytes {
+            let bytes = buf.copy_to_bytes(buf.remaining());
+            let len = message::well_formed_prefix_len(&bytes);
+            return self
+                .merge_with_options(&bytes[..len], &options.clone().allow_trailing_bytes(false));
+        }
+
+        let ctx = DecodeContext::default();
+        while buf.has_remaining() {
+            let (number, wire_type) = prost::encoding::decode_key(&mut buf)?;
+            self.merge_field_with_options(number, wire_type, &mut buf, ctx.clone(), options)?;
+        }
+        Ok(())
+    }
+
+    /// Clears this message, then decodes fields from the buffer and merges them into it.
+    ///
+    /// This is equivalent to calling [`Message::clear`] followed by [`Message::merge`], except
+    /// that the strings, lists, maps and nested messages allocated for the previous contents are
+    /// reused when the same fields are decoded again. This avoids allocating when a single
+    /// message is used to decode many records of the same type. Packed encoding overrides set with
+    /// [`set_field_packed`][Self::set_field_packed] are also kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// for record in [b"\x08\x96\x01".as_ref(), b"\x08\x01".as_ref(), b"".as_ref()] {
+    ///     dynamic_message.clear_and_merge(record).unwrap();
+    ///     assert_eq!(dynamic_message.encode_to_vec(), record);
+    /// }
+    /// ```
+    pub fn clear_and_merge<B>(&mut self, buf: B) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        self.fields.clear_retaining();
+        self.merge(buf)
+    }
+
+    /// Decodes an instance of the message type specified by the [`MessageDescriptor`] from the start
+    /// of the buffer, ignoring any trailing bytes which do not form a complete field.
+    ///
+    /// Returns the message and the number of bytes of `buf` which were decoded. See
+    /// [`DecodeOptions::allow_trailing_bytes`] for how the end of the message is found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_types::FileDescriptorSet;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let bytes = b"\x08\x96\x01\xff\xff";
+    /// assert!(DynamicMessage::decode(message_descriptor.clone(), bytes.as_ref()).is_err());
+    ///
+    /// let (dynamic_message, len) = DynamicMessage::decode_prefix(message_descriptor, bytes).unwrap();
+    /// assert_eq!(dynamic_message.get_field_by_name("foo").unwrap().as_ref(), &Value::I32(150));
+    /// assert_eq!(len, 3);
+    /// ```
+    pub fn decode_prefix(
+        desc: MessageDescriptor,
+        buf: &[u8],
+    ) -> Result<(Self, usize), DecodeError> {
+        let len = message::well_formed_prefix_len(buf);
+        let message = DynamicMessage::decode(desc, &buf[..len])?;
+        Ok((message, len))
+    }
+
+    /// Decodes any unknown fields of this message which match an extension registered in `pool`, and
+    /// sets them as extension values.
+    ///
+    /// This is useful when a message was decoded before the files defining some of its extensions
+    /// were available. The message and any nested messages, including those contained in lists, maps
+    /// and extension fields, are updated to use the descriptors from `pool`, so it must contain
+    /// every message type and extension currently used by this message.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message type used by this message is not found in `pool`, or an unknown
+    /// field fails to decode as its extension type. In that case the message may have been partially
+    /// updated.
+    pub fn reparse_extensions(&mut self, pool: &DescriptorPool) -> Result<(), DecodeError> {
+        self.desc = match pool.get_message_by_name(self.desc.full_name()) {
+            Some(desc) => desc,
+            None => {
+                return Err(DecodeError::new(format!(
+                    "message '{}' not found in pool",
+                    self.desc.full_name()
+                )))
+            }
+        };
+
+        for value in self.fields.values_mut() {
+            match value {
+                Value::Message(message) => message.reparse_extensions(pool)?,
+                Value::List(list) => {
+                    for value in list {
+                        if let Value::Message(message) = value {
+                            message.reparse_extensions(pool)?;
+                        }
+                    }
+                }
+                Value::Map(map) => {
+                    for value in map.values_mut() {
+                        if let Value::Message(message) = value {
+                            message.reparse_extensions(pool)?;
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let desc = &self.desc;
+        let unknown = self
+            .fields
+            .take_unknown(|number| desc.get_extension(number).is_some());
+        let mut buf = Vec::new();
+        for (number, fields) in unknown {
+            for field in fields {
+                field.encode_field(number, &mut buf);
+            }
+        }
+        self.merge(buf.as_slice())
+    }
+
+    /// Decodes a single field of the message type specified by the [`MessageDescriptor`] from the buffer,
+    /// without decoding the rest of the message.
+    ///
+    /// All other fields are skipped over without being parsed. The returned value is the same as would
+    /// be returned by [`get_field`][DynamicMessage::get_field] after decoding the whole message, except
+    /// that `None` is returned if the field is not present in the buffer. For singular fields the last
+    /// occurrence is used (or all occurrences are merged, for message fields), while all occurrences of
+    /// repeated fields are collected.
+    ///
+    /// `field_number` may refer to a field or an extension of the message. If it refers to neither,
+    /// `None` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_types::FileDescriptorSet;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let value = DynamicMessage::decode_field(&message_descriptor, 1, b"\x08\x96\x01".as_ref()).unwrap();
+    /// assert_eq!(value, Some(Value::I32(150)));
+    /// ```
+    pub fn decode_field<B>(
+        desc: &MessageDescriptor,
+        field_number: u32,
+        buf: B,
+    ) -> Result<Option<Value>, DecodeError>
+    where
+        B: Buf,
+    {
+        if let Some(field_desc) = desc.get_field(field_number) {
+            message::decode_field(&field_desc, buf)
+        } else if let Some(extension_desc) = desc.get_extension(field_number) {
+            message::decode_field(&extension_desc, buf)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns `true` if this message has no fields, extensions or unknown fields set.
+    ///
+    /// This is equivalent to checking whether [`encode_to_vec`][Message::encode_to_vec] returns an
+    /// empty buffer, but does not require encoding the message.
+    pub fn is_empty(&self) -> bool {
+        self.fields.iter(&self.desc).next().is_none()
+    }
+
+    /// Gets an iterator over the fields of this message which are set, and their values.
+    ///
+    /// A field is included if [`has_field`][Self::has_field] returns `true` for it. Fields are
+    /// yielded in ascending order of their numbers, which is the order they are encoded in.
+    /// Extension fields and unknown fields are not included.
+    pub fn iter_fields_in_order(&self) -> impl Iterator<Item = (FieldDescriptor, &'_ Value)> + '_ {
+        self.fields.iter_fields(&self.desc)
+    }
+
     /// Returns `true` if this message has the given field set.
     ///
     /// If the field type supports distinguishing whether a value has been set (see [`supports_presence`][FieldDescriptor::supports_presence]),
@@ -167,7 +497,38 @@ impl DynamicMessage {
     }
 
     /// Gets the value of the given field, or the default value if it is unset.
+    ///
+    /// This is equivalent to [`get_field_or_default`][Self::get_field_or_default]. If the field
+    /// is unset, its default value is constructed on each call, which can be avoided by using
+    /// [`get_field_ref`][Self::get_field_ref] instead.
     pub fn get_field(&self, field_desc: &FieldDescriptor) -> Cow<'_, Value> {
+        self.get_field_or_default(field_desc)
+    }
+
+    /// Gets a reference to the value of the given field, or `None` if it is unset.
+    ///
+    /// Unlike [`get_field`][Self::get_field], this never constructs a default value. A field
+    /// is considered set if [`has_field`][Self::has_field] returns `true`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let foo = message_descriptor.get_field_by_name("foo").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// assert_eq!(dynamic_message.get_field_ref(&foo), None);
+    ///
+    /// dynamic_message.set_field(&foo, Value::I32(5));
+    /// assert_eq!(dynamic_message.get_field_ref(&foo), Some(&Value::I32(5)));
+    /// ```
+    pub fn get_field_ref(&self, field_desc: &FieldDescriptor) -> Option<&Value> {
+        self.fields.get_ref(field_desc)
+    }
+
+    /// Gets the value of the given field, or the default value if it is unset.
+    pub fn get_field_or_default(&self, field_desc: &FieldDescriptor) -> Cow<'_, Value> {
         self.fields.get(field_desc)
     }
 
@@ -187,6 +548,50 @@ impl DynamicMessage {
         self.fields.set(field_desc, value);
     }
 
+    /// Sets the value of the given field, returning an error if the value type is not compatible
+    /// with the field type.
+    ///
+    /// Unlike [`set_field`][Self::set_field], this method never panics.
+    pub fn try_set_field(
+        &mut self,
+        field_desc: &FieldDescriptor,
+        value: Value,
+    ) -> Result<(), SetFieldError> {
+        if value.is_valid_for_field(field_desc) {
+            self.set_field(field_desc, value);
+            Ok(())
+        } else {
+            Err(SetFieldError::InvalidType {
+                field: field_desc.clone(),
+                value,
+            })
+        }
+    }
+
+    /// Sets the value of the given field, packing messages into a `google.protobuf.Any` value if
+    /// required.
+    ///
+    /// If the field type is `google.protobuf.Any` and `value` is a message of any other type (or,
+    /// for repeated fields, a list containing such messages), the message is encoded and wrapped
+    /// in an `Any` message with a type URL of the form `type.googleapis.com/<message name>`.
+    /// Otherwise this behaves the same as [`set_field`][Self::set_field].
+    pub fn set_field_packing_any(&mut self, field_desc: &FieldDescriptor, value: Value) {
+        let value = match field_desc.kind() {
+            Kind::Message(any_desc) if any_desc.full_name() == "google.protobuf.Any" => match value
+            {
+                Value::List(values) if field_desc.is_list() => Value::List(
+                    values
+                        .into_iter()
+                        .map(|value| pack_any(&any_desc, value))
+                        .collect(),
+                ),
+                value => pack_any(&any_desc, value),
+            },
+            _ => value,
+        };
+        self.set_field(field_desc, value);
+    }
+
     /// Clears the given field.
     ///
     /// After calling this method, `has_field` will return false for the field,
@@ -195,6 +600,91 @@ impl DynamicMessage {
         self.fields.clear(field_desc);
     }
 
+    /// Overrides whether the given repeated field is encoded using the packed representation.
+    ///
+    /// By default, a field is packed if [`FieldDescriptor::is_packed`] returns `true`. This method
+    /// can be used to force a particular encoding, for example for compatibility with decoders that
+    /// do not support packed fields. It has no effect for fields which cannot be packed, such as
+    /// strings, messages or non-repeated fields. Decoding always accepts both representations.
+    ///
+    /// The override is stored separately from the field's value, so setting it does not set the
+    /// field. It is kept if the field's value is replaced, for example with
+    /// [`set_field`][Self::set_field], and by [`clear_and_merge`][Self::clear_and_merge]. It is
+    /// removed when the field is cleared with [`clear_field`][Self::clear_field] or when the whole
+    /// message is cleared with [`Message::clear`], and setting it to the field's default encoding
+    /// also removes it. Messages with different overrides do not compare equal.
+    pub fn set_field_packed(&mut self, field_desc: &FieldDescriptor, packed: bool) {
+        if field_desc.is_list() && field_desc.is_packable() {
+            self.fields.set_packed(field_desc, packed);
+        }
+    }
+
+    /// Clears all fields for which the predicate `f` returns `false`.
+    ///
+    /// The predicate is called once for each field which is set (see [`has_field`][Self::has_field]),
+    /// with the field descriptor and its current value. Extension fields and unknown fields are always
+    /// retained. This method does not recurse into nested messages; see
+    /// [`retain_fields_deep`][Self::retain_fields_deep].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_types::FileDescriptorSet;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+    /// dynamic_message.retain_fields(|field, _| field.name() != "foo");
+    /// assert!(!dynamic_message.has_field_by_name("foo"));
+    /// ```
+    pub fn retain_fields<F>(&mut self, f: F)
+    where
+        F: FnMut(&FieldDescriptor, &Value) -> bool,
+    {
+        self.fields.retain(&self.desc, f)
+    }
+
+    /// Clears all fields for which the predicate `f` returns `false`, in this message and in all
+    /// nested messages.
+    ///
+    /// This behaves like [`retain_fields`][Self::retain_fields], but after filtering the fields of this
+    /// message it recurses into any retained message values, including messages contained in lists
+    /// and maps, and those set in extension fields.
+    pub fn retain_fields_deep<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&FieldDescriptor, &Value) -> bool,
+    {
+        self.retain_fields_deep_inner(&mut f)
+    }
+
+    fn retain_fields_deep_inner<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(&FieldDescriptor, &Value) -> bool,
+    {
+        self.retain_fields(&mut *f);
+        for value in self.fields.values_mut() {
+            match value {
+                Value::Message(message) => message.retain_fields_deep_inner(f),
+                Value::List(list) => {
+                    for value in list {
+                        if let Value::Message(message) = value {
+                            message.retain_fields_deep_inner(f);
+                        }
+                    }
+                }
+                Value::Map(map) => {
+                    for value in map.values_mut() {
+                        if let Value::Message(message) = value {
+                            message.retain_fields_deep_inner(f);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
     /// Returns `true` if this message has a field set with the given number.
     ///
     /// See [`has_field`][Self::has_field] for more details.
@@ -292,6 +782,156 @@ impl DynamicMessage {
         }
     }
 
+    /// Sets the value of the field with name `name`, returning an error if no such field exists
+    /// or the value type is not compatible with the field type.
+    ///
+    /// See [`try_set_field`][Self::try_set_field] for more details.
+    pub fn try_set_field_by_name(&mut self, name: &str, value: Value) -> Result<(), SetFieldError> {
+        match self.desc.get_field_by_name(name) {
+            Some(field_desc) => self.try_set_field(&field_desc, value),
+            None => Err(SetFieldError::NotFound {
+                name: name.to_owned(),
+            }),
+        }
+    }
+
+    /// Sets the values of several fields, given as pairs of field name and value.
+    ///
+    /// The fields are set in order, so if multiple fields of the same oneof are given, the last one
+    /// is kept. If any field does not exist or has an incompatible value, the first such error is
+    /// returned and this message is left unchanged.
+    ///
+    /// See [`try_set_field_by_name`][Self::try_set_field_by_name] for more details.
+    pub fn set_fields<I, N>(&mut self, fields: I) -> Result<(), SetFieldError>
+    where
+        I: IntoIterator<Item = (N, Value)>,
+        N: AsRef<str>,
+    {
+        let mut updates = Vec::new();
+        for (name, value) in fields {
+            let field_desc = match self.desc.get_field_by_name(name.as_ref()) {
+                Some(field_desc) => field_desc,
+                None => {
+                    return Err(SetFieldError::NotFound {
+                        name: name.as_ref().to_owned(),
+                    })
+                }
+            };
+            if !value.is_valid_for_field(&field_desc) {
+                return Err(SetFieldError::InvalidType {
+                    field: field_desc,
+                    value,
+                });
+            }
+            updates.push((field_desc, value));
+        }
+
+        for (field_desc, value) in updates {
+            self.set_field(&field_desc, value);
+        }
+        Ok(())
+    }
+
+    /// Appends a value to the repeated field with the given name.
+    ///
+    /// If the field does not exist, or is not a repeated field of a type compatible with `value`,
+    /// an error is returned and this message is left unchanged.
+    pub fn push_field(&mut self, name: &str, value: Value) -> Result<(), SetFieldError> {
+        let field_desc = self.find_field_for_update(name)?;
+        if !field_desc.is_list() || !value.is_valid(&field_desc.kind()) {
+            return Err(SetFieldError::InvalidType {
+                field: field_desc,
+                value,
+            });
+        }
+
+        self.get_field_mut(&field_desc)
+            .as_list_mut()
+            .expect("list field must have a list value")
+            .push(value);
+        Ok(())
+    }
+
+    /// Inserts an entry into the map field with the given name, replacing any existing value
+    /// for `key`.
+    ///
+    /// If the field does not exist, or is not a map field whose key and value types are compatible
+    /// with `key` and `value`, an error is returned and this message is left unchanged. If the key
+    /// is invalid, the error contains the key converted to a [`Value`].
+    pub fn insert_map_entry(
+        &mut self,
+        name: &str,
+        key: MapKey,
+        value: Value,
+    ) -> Result<(), SetFieldError> {
+        let field_desc = self.find_field_for_update(name)?;
+        let (key_kind, value_kind) = match (field_desc.map_key_kind(), field_desc.map_value_kind())
+        {
+            (Some(key_kind), Some(value_kind)) => (key_kind, value_kind),
+            _ => {
+                return Err(SetFieldError::InvalidType {
+                    field: field_desc,
+                    value,
+                })
+            }
+        };
+        if !key.is_valid(&key_kind) {
+            return Err(SetFieldError::InvalidType {
+                field: field_desc,
+                value: key.into(),
+            });
+        }
+        if !value.is_valid(&value_kind) {
+            return Err(SetFieldError::InvalidType {
+                field: field_desc,
+                value,
+            });
+        }
+
+        self.get_field_mut(&field_desc)
+            .as_map_mut()
+            .expect("map field must have a map value")
+            .insert(key, value);
+        Ok(())
+    }
+
+    fn find_field_for_update(&self, name: &str) -> Result<FieldDescriptor, SetFieldError> {
+        self.desc
+            .get_field_by_name(name)
+            .ok_or_else(|| SetFieldError::NotFound {
+                name: name.to_owned(),
+            })
+    }
+
+    /// Copies the value of each field set in `src` to the field with the same name in this message.
+    ///
+    /// This is useful for converting between messages with the same shape but different field
+    /// numbers. Fields are skipped if this message has no field with the same name, or if the field
+    /// types are not compatible. Message and enum fields are only compatible if they have the same
+    /// type, and list and map fields are only compatible with other list and map fields. Extension
+    /// and unknown fields are not copied.
+    ///
+    /// Returns the names of the fields which were copied.
+    pub fn copy_fields_by_name_from(&mut self, src: &DynamicMessage) -> Vec<String> {
+        let mut copied = Vec::new();
+        for src_field in src.desc.fields() {
+            if !src.has_field(&src_field) {
+                continue;
+            }
+
+            let field_desc = match self.desc.get_field_by_name(src_field.name()) {
+                Some(field_desc) if is_same_field_type(&src_field, &field_desc) => field_desc,
+                _ => continue,
+            };
+            let value = src.get_field(&src_field);
+            if value.is_valid_for_field(&field_desc) {
+                self.set_field(&field_desc, value.into_owned());
+                copied.push(src_field.name().to_owned());
+            }
+        }
+        copied
+    }
+
     /// Clears the field with the given name.
     ///
     /// If no field with the given name exists, this method does nothing.
@@ -303,6 +943,35 @@ impl DynamicMessage {
         }
     }
 
+    /// Gets the field which is currently set in the oneof with the given name, or `None` if no field
+    /// in the oneof is set or no such oneof exists.
+    pub fn which_oneof(&self, oneof_name: &str) -> Option<FieldDescriptor> {
+        let oneof = self.desc.get_oneof_by_name(oneof_name)?;
+        self.oneof_field(&oneof).map(|(field, _)| field)
+    }
+
+    /// Gets the field which is currently set in the given oneof, along with its value, or `None` if
+    /// no field in the oneof is set.
+    pub fn oneof_field(&self, oneof: &OneofDescriptor) -> Option<(FieldDescriptor, &Value)> {
+        let field = oneof.fields().find(|field| self.has_field(field))?;
+        let value = self.fields.get_value(field.number())?;
+        Some((field, value))
+    }
+
+    /// Clears whichever field is currently set in the given oneof.
+    ///
+    /// Returns `true` if a field was set and has been cleared, or `false` if no field in the oneof
+    /// was set.
+    pub fn clear_oneof(&mut self, oneof: &OneofDescriptor) -> bool {
+        match self.oneof_field(oneof) {
+            Some((field, _)) => {
+                self.clear_field(&field);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Returns `true` if this message has the given extension field set.
     ///
     /// See [`has_field`][Self::has_field] for more details.
@@ -365,6 +1034,78 @@ impl DynamicMessage {
         T::decode(buf.as_slice())
     }
 
+    /// Creates a new [`DynamicMessage`] with the same type and contents as `message`.
+    ///
+    /// This is equivalent to [`ReflectMessage::transcode_to_dynamic`].
+    pub fn from_msg<T>(message: &T) -> DynamicMessage
+    where
+        T: ReflectMessage,
+    {
+        message.transcode_to_dynamic()
+    }
+
+    /// Converts this dynamic message into the strongly typed message `T`.
+    ///
+    /// Unlike [`transcode_to`][Self::transcode_to], this checks that `T` describes the same message
+    /// type as this message.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` has a different message type to this message, or if this message cannot be
+    /// decoded as `T`.
+    pub fn reflect<T>(&self) -> T
+    where
+        T: ReflectMessage + Default,
+    {
+        let value = T::default();
+        assert_eq!(
+            value.descriptor().full_name(),
+            self.desc.full_name(),
+            "mismatched message type"
+        );
+        self.transcode_to()
+            .expect("error converting from dynamic message")
+    }
+
+    /// Encodes this message into `writer`.
+    ///
+    /// Unlike [`encode_to_vec`][Message::encode_to_vec], this does not buffer the whole message in
+    /// memory. Instead, each top-level field is encoded into a temporary buffer and written to
+    /// `writer` before the next field is encoded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+    /// let mut writer = Vec::new();
+    /// dynamic_message.encode_to_writer(&mut writer).unwrap();
+    /// assert_eq!(writer, b"\x08\x96\x01");
+    /// ```
+    pub fn encode_to_writer<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.encode_to_writer_impl(writer)
+    }
+
+    /// Encodes this message into `writer`, prefixed with its length as a varint.
+    ///
+    /// The output can be read back with [`Message::decode_length_delimited`]. See
+    /// [`encode_to_writer`][Self::encode_to_writer] for more details.
+    pub fn encode_length_delimited_to_writer<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut len = Vec::new();
+        prost::encoding::encode_varint(self.encoded_len() as u64, &mut len);
+        writer.write_all(&len)?;
+        self.encode_to_writer_impl(writer)
+    }
+
     /// Formats this dynamic message using the protobuf text format.
     ///
     /// Output is pretty-printed with each field on a new line, and nested messages indented. This function is equivalent to
@@ -529,6 +1270,122 @@ impl Value {
         )
     }
 
+    /// Creates a [`Value::EnumNumber`] holding `number`, checking that it is valid for the enum type
+    /// `enum_desc`.
+    ///
+    /// If `closed` is `true`, as for enums defined in proto2 files, an error is returned if `number`
+    /// is not defined by the enum. Otherwise, any number is accepted, since open enums such as those
+    /// defined in proto3 files may hold unrecognized values.
+    pub fn enum_value(
+        enum_desc: &EnumDescriptor,
+        number: i32,
+        closed: bool,
+    ) -> Result<Value, EnumValueError> {
+        if closed && !enum_desc.contains_number(number) {
+            Err(EnumValueError {
+                enum_desc: enum_desc.clone(),
+                number,
+            })
+        } else {
+            Ok(Value::EnumNumber(number))
+        }
+    }
+
+    /// Gets a short name for the type of this value, for use in error messages.
+    ///
+    /// The name is one of `bool`, `i32`, `i64`, `u32`, `u64`, `f32`, `f64`, `string`, `bytes`,
+    /// `enum`, `message`, `list` or `map`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::Value;
+    /// assert_eq!(Value::I32(5).type_name(), "i32");
+    /// assert_eq!(Value::List(vec![]).type_name(), "list");
+    /// ```
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Bool(_) => "bool",
+            Value::I32(_) => "i32",
+            Value::I64(_) => "i64",
+            Value::U32(_) => "u32",
+            Value::U64(_) => "u64",
+            Value::F32(_) => "f32",
+            Value::F64(_) => "f64",
+            Value::String(_) => "string",
+            Value::Bytes(_) => "bytes",
+            Value::EnumNumber(_) => "enum",
+            Value::Message(_) => "message",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+        }
+    }
+
+    /// Takes the value out of `self`, leaving an empty value of the same type in its place.
+    ///
+    /// Scalars are replaced with zero, strings, bytes, lists and maps with empty values, enums with
+    /// the number `0`, and messages with an empty message of the same type. This allows moving a
+    /// value out of a `&mut Value`, such as one returned by
+    /// [`get_field_mut`][DynamicMessage::get_field_mut], without cloning it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::Value;
+    /// let mut value = Value::List(vec![Value::I32(1), Value::I32(2)]);
+    /// assert_eq!(value.take(), Value::List(vec![Value::I32(1), Value::I32(2)]));
+    /// assert_eq!(value, Value::List(vec![]));
+    ///
+    /// let mut value = Value::String("hello".to_owned());
+    /// assert_eq!(value.take(), Value::String("hello".to_owned()));
+    /// assert_eq!(value, Value::String(String::new()));
+    /// ```
+    pub fn take(&mut self) -> Value {
+        let placeholder = match self {
+            Value::Bool(_) => Value::Bool(false),
+            Value::I32(_) => Value::I32(0),
+            Value::I64(_) => Value::I64(0),
+            Value::U32(_) => Value::U32(0),
+            Value::U64(_) => Value::U64(0),
+            Value::F32(_) => Value::F32(0.0),
+            Value::F64(_) => Value::F64(0.0),
+            Value::String(_) => Value::String(String::new()),
+            Value::Bytes(_) => Value::Bytes(Bytes::new()),
+            Value::EnumNumber(_) => Value::EnumNumber(0),
+            Value::Message(message) => Value::Message(DynamicMessage::new(message.desc.clone())),
+            Value::List(_) => Value::List(Vec::new()),
+            Value::Map(_) => Value::Map(HashMap::new()),
+        };
+        mem::replace(self, placeholder)
+    }
+
+    /// Compares this value with `other`, which must have the same scalar type.
+    ///
+    /// This is the same as the [`PartialOrd`] implementation, but can be passed to methods such as
+    /// [`slice::sort_by`] to order the values of a repeated field. Floating point values are
+    /// ordered as by [`f64::total_cmp`], so NaN values are also ordered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values cannot be compared, because they have different types, or are
+    /// [`Message`][Value::Message], [`List`][Value::List] or [`Map`][Value::Map] values which
+    /// are not equal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::Value;
+    /// let mut values = vec![Value::F64(f64::NAN), Value::F64(1.0), Value::F64(-2.0)];
+    /// values.sort_by(Value::total_cmp);
+    /// assert_eq!(values, [Value::F64(-2.0), Value::F64(1.0), Value::F64(f64::NAN)]);
+    /// ```
+    pub fn total_cmp(&self, other: &Value) -> Ordering {
+        match self.partial_cmp(other) {
+            Some(ordering) => ordering,
+            None => panic!("cannot compare values {:?} and {:?}", self, other),
+        }
+    }
+
     /// Returns the value if it is a `Value::Bool`, or `None` if it is any other type.
     pub fn as_bool(&self) -> Option<bool> {
         match *self {
@@ -649,6 +1506,17 @@ impl Value {
         }
     }
 
+    /// Returns the [`EnumValueDescriptor`] for this value if it is a `Value::EnumNumber` with a number defined
+    /// in the enum type of `field`, or `None` otherwise.
+    ///
+    /// This returns `None` for unknown enum numbers, which are permitted by open enums.
+    pub fn as_enum_value(&self, field: &FieldDescriptor) -> Option<EnumValueDescriptor> {
+        match (self, field.kind()) {
+            (Value::EnumNumber(number), Kind::Enum(enum_ty)) => enum_ty.get_value(*number),
+            _ => None,
+        }
+    }
+
     /// Returns a mutable reference to the value if it is a `Value::EnumNumber`, or `None` if it is any other type.
     pub fn as_enum_number_mut(&mut self) -> Option<&mut i32> {
         match self {
@@ -714,6 +1582,11 @@ impl Value {
     }
 
     /// Returns a mutable reference to the value if it is a `Value::List`, or `None` if it is any other type.
+    ///
+    /// Combined with [`DynamicMessage::get_field_mut`], this can be used to modify a repeated field
+    /// in place. Elements added through the returned reference are not checked against the type of
+    /// the field, and encoding a message containing values of the wrong type may panic. Use
+    /// [`Value::is_valid_for_field`] to check the field value after modifying it.
     pub fn as_list_mut(&mut self) -> Option<&mut Vec<Value>> {
         match self {
             Value::List(value) => Some(value),
@@ -730,12 +1603,100 @@ impl Value {
     }
 
     /// Returns a mutable reference to the value if it is a `Value::Map`, or `None` if it is any other type.
+    ///
+    /// As with [`as_list_mut`][Value::as_list_mut], entries added through the returned reference
+    /// are not checked against the type of the field.
     pub fn as_map_mut(&mut self) -> Option<&mut HashMap<MapKey, Value>> {
         match self {
             Value::Map(value) => Some(value),
             _ => None,
         }
     }
+
+    /// Returns the entries of the value sorted by key if it is a `Value::Map`, or `None` if it
+    /// is any other type.
+    ///
+    /// This can be used to iterate over a map field in a deterministic order.
+    pub fn map_sorted(&self) -> Option<Vec<(&MapKey, &Value)>> {
+        let mut entries: Vec<_> = self.as_map()?.iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+        Some(entries)
+    }
+
+    /// Returns the wrapped value if this is a `google.protobuf.BoolValue` message, or `None` if it is
+    /// any other type.
+    pub fn as_wrapper_bool(&self) -> Option<bool> {
+        self.as_wrapper(WellKnownType::BoolValue)
+            .map(|value| value.and_then(Value::as_bool).unwrap_or_default())
+    }
+
+    /// Returns the wrapped value if this is a `google.protobuf.Int32Value` message, or `None` if it is
+    /// any other type.
+    pub fn as_wrapper_i32(&self) -> Option<i32> {
+        self.as_wrapper(WellKnownType::Int32Value)
+            .map(|value| value.and_then(Value::as_i32).unwrap_or_default())
+    }
+
+    /// Returns the wrapped value if this is a `google.protobuf.Int64Value` message, or `None` if it is
+    /// any other type.
+    pub fn as_wrapper_i64(&self) -> Option<i64> {
+        self.as_wrapper(WellKnownType::Int64Value)
+            .map(|value| value.and_then(Value::as_i64).unwrap_or_default())
+    }
+
+    /// Returns the wrapped value if this is a `google.protobuf.UInt32Value` message, or `None` if it is
+    /// any other type.
+    pub fn as_wrapper_u32(&self) -> Option<u32> {
+        self.as_wrapper(WellKnownType::UInt32Value)
+            .map(|value| value.and_then(Value::as_u32).unwrap_or_default())
+    }
+
+    /// Returns the wrapped value if this is a `google.protobuf.UInt64Value` message, or `None` if it is
+    /// any other type.
+    pub fn as_wrapper_u64(&self) -> Option<u64> {
+        self.as_wrapper(WellKnownType::UInt64Value)
+            .map(|value| value.and_then(Value::as_u64).unwrap_or_default())
+    }
+
+    /// Returns the wrapped value if this is a `google.protobuf.FloatValue` message, or `None` if it is
+    /// any other type.
+    pub fn as_wrapper_f32(&self) -> Option<f32> {
+        self.as_wrapper(WellKnownType::FloatValue)
+            .map(|value| value.and_then(Value::as_f32).unwrap_or_default())
+    }
+
+    /// Returns the wrapped value if this is a `google.protobuf.DoubleValue` message, or `None` if it is
+    /// any other type.
+    pub fn as_wrapper_f64(&self) -> Option<f64> {
+        self.as_wrapper(WellKnownType::DoubleValue)
+            .map(|value| value.and_then(Value::as_f64).unwrap_or_default())
+    }
+
+    /// Returns the wrapped value if this is a `google.protobuf.StringValue` message, or `None` if
+    /// it is any other type.
+    pub fn as_wrapper_str(&self) -> Option<&str> {
+        self.as_wrapper(WellKnownType::StringValue)
+            .map(|value| value.and_then(Value::as_str).unwrap_or_default())
+    }
+
+    /// Returns the wrapped value if this is a `google.protobuf.BytesValue` message, or `None` if
+    /// it is any other type.
+    pub fn as_wrapper_bytes(&self) -> Option<&Bytes> {
+        static EMPTY: Bytes = Bytes::new();
+        self.as_wrapper(WellKnownType::BytesValue)
+            .map(|value| value.and_then(Value::as_bytes).unwrap_or(&EMPTY))
+    }
+
+    /// If this is a message of the given wrapper type, returns its `value` field, or `None`
+    /// inside of `Some` if the field is unset.
+    fn as_wrapper(&self, ty: WellKnownType) -> Option<Option<&Value>> {
+        match self {
+            Value::Message(message) if message.desc.well_known_type() == Some(ty) => {
+                Some(message.fields.get_value(1))
+            }
+            _ => None,
+        }
+    }
 }
 
 impl MapKey {
@@ -875,6 +1836,271 @@ impl MapKey {
     }
 }
 
+impl PartialEq for DynamicMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.desc == other.desc && self.fields.eq(&other.fields, &self.desc)
+    }
+}
+
+impl Eq for DynamicMessage {}
+
+impl Hash for DynamicMessage {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.desc.full_name().hash(state);
+        self.fields.hash(&self.desc, state);
+    }
+}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Value::Bool(value) => value.hash(state),
+            Value::I32(value) | Value::EnumNumber(value) => value.hash(state),
+            Value::I64(value) => value.hash(state),
+            Value::U32(value) => value.hash(state),
+            Value::U64(value) => value.hash(state),
+            // Positive and negative zero compare equal, so must have the same hash.
+            Value::F32(value) => (value + 0.0).to_bits().hash(state),
+            Value::F64(value) => (value + 0.0).to_bits().hash(state),
+            Value::String(value) => value.hash(state),
+            Value::Bytes(value) => value.hash(state),
+            Value::Message(value) => value.hash(state),
+            Value::List(values) => values.hash(state),
+            Value::Map(values) => {
+                let mut entries: Vec<_> = values.iter().collect();
+                entries.sort_by_key(|&(key, _)| key);
+                entries.hash(state);
+            }
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Bool(l), Value::Bool(r)) => l == r,
+            (Value::I32(l), Value::I32(r)) | (Value::EnumNumber(l), Value::EnumNumber(r)) => l == r,
+            (Value::I64(l), Value::I64(r)) => l == r,
+            (Value::U32(l), Value::U32(r)) => l == r,
+            (Value::U64(l), Value::U64(r)) => l == r,
+            // Compare bit representations so that NaN equals itself, consistent with the Hash impl.
+            (Value::F32(l), Value::F32(r)) => (l + 0.0).to_bits() == (r + 0.0).to_bits(),
+            (Value::F64(l), Value::F64(r)) => (l + 0.0).to_bits() == (r + 0.0).to_bits(),
+            (Value::String(l), Value::String(r)) => l == r,
+            (Value::Bytes(l), Value::Bytes(r)) => l == r,
+            (Value::Message(l), Value::Message(r)) => l == r,
+            (Value::List(l), Value::List(r)) => l == r,
+            (Value::Map(l), Value::Map(r)) => l == r,
+            _ => false,
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Bool(l), Value::Bool(r)) => Some(l.cmp(r)),
+            (Value::I32(l), Value::I32(r)) | (Value::EnumNumber(l), Value::EnumNumber(r)) => {
+                Some(l.cmp(r))
+            }
+            (Value::I64(l), Value::I64(r)) => Some(l.cmp(r)),
+            (Value::U32(l), Value::U32(r)) => Some(l.cmp(r)),
+            (Value::U64(l), Value::U64(r)) => Some(l.cmp(r)),
+            (Value::F32(l), Value::F32(r)) => Some(total_cmp_f32(*l, *r)),
+            (Value::F64(l), Value::F64(r)) => Some(total_cmp_f64(*l, *r)),
+            (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+            (Value::Bytes(l), Value::Bytes(r)) => Some(l.cmp(r)),
+            (l, r) if l == r => Some(Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+// Equivalent to `f32::total_cmp`, which is not available on the minimum supported Rust version.
+// Zero is added first so that negative zero compares equal to positive zero, as for `PartialEq`.
+fn total_cmp_f32(l: f32, r: f32) -> Ordering {
+    fn key(value: f32) -> i32 {
+        let bits = (value + 0.0).to_bits() as i32;
+        bits ^ (((bits >> 31) as u32) >> 1) as i32
+    }
+    key(l).cmp(&key(r))
+}
+
+// Equivalent to `f64::total_cmp`, which is not available on the minimum supported Rust version.
+// Zero is added first so that negative zero compares equal to positive zero, as for `PartialEq`.
+fn total_cmp_f64(l: f64, r: f64) -> Ordering {
+    fn key(value: f64) -> i64 {
+        let bits = (value + 0.0).to_bits() as i64;
+        bits ^ (((bits >> 63) as u64) >> 1) as i64
+    }
+    key(l).cmp(&key(r))
+}
+
+fn pack_any(any_desc: &MessageDescriptor, value: Value) -> Value {
+    match value {
+        Value::Message(message) if message.desc.full_name() != any_desc.full_name() => {
+            let any = prost_types::Any {
+                type_url: format!("type.googleapis.com/{}", message.desc.full_name()),
+                value: message.encode_to_vec(),
+            };
+            let mut packed = DynamicMessage::new(any_desc.clone());
+            packed
+                .transcode_from(&any)
+                .expect("google.protobuf.Any descriptor is invalid");
+            Value::Message(packed)
+        }
+        value => value,
+    }
+}
+
+fn is_same_field_type(l: &FieldDescriptor, r: &FieldDescriptor) -> bool {
+    if l.is_list() != r.is_list() || l.is_map() != r.is_map() {
+        return false;
+    }
+
+    match (l.kind(), r.kind()) {
+        (Kind::Message(l), Kind::Message(r)) if l.is_map_entry() && r.is_map_entry() => {
+            is_same_field_type(&l.map_entry_value_field(), &r.map_entry_value_field())
+        }
+        (Kind::Message(l), Kind::Message(r)) => l.full_name() == r.full_name(),
+        (Kind::Enum(l), Kind::Enum(r)) => l.full_name() == r.full_name(),
+        _ => true,
+    }
+}
+
+impl std::fmt::Display for SetFieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SetFieldError::NotFound { name } => write!(f, "field '{}' not found", name),
+            SetFieldError::InvalidType { field, value } => write!(
+                f,
+                "the value '{}' is not valid for field '{}'",
+                value,
+                field.full_name()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SetFieldError {}
+
+impl EnumValueError {
+    /// Gets the enum type the number was checked against.
+    pub fn enum_descriptor(&self) -> &EnumDescriptor {
+        &self.enum_desc
+    }
+
+    /// Gets the number which is not defined by the enum.
+    pub fn number(&self) -> i32 {
+        self.number
+    }
+}
+
+impl std::fmt::Display for EnumValueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid value {} for enum {}",
+            self.number,
+            self.enum_desc.full_name()
+        )
+    }
+}
+
+impl std::error::Error for EnumValueError {}
+
+impl DecodeOptions {
+    /// Creates a new instance of [`DecodeOptions`], with the default options.
+    pub const fn new() -> Self {
+        DecodeOptions {
+            allow_wire_type_mismatch: false,
+            strict_enums: false,
+            recursion_limit: MAX_RECURSION_LIMIT,
+            allow_trailing_bytes: false,
+            validate_utf8: true,
+        }
+    }
+
+    /// Whether to tolerate known fields which are encoded with an unexpected wire type.
+    ///
+    /// If `false`, decoding such a field returns an error. If `true`, the field is instead stored as an
+    /// unknown field, and decoding continues. If the field also appears with the correct wire type, the
+    /// mismatched occurrences are discarded.
+    ///
+    /// Note that values of repeated scalar fields are always accepted in both packed and unpacked encodings.
+    ///
+    /// The default value is `false`.
+    pub const fn allow_wire_type_mismatch(mut self, yes: bool) -> Self {
+        self.allow_wire_type_mismatch = yes;
+        self
+    }
+
+    /// Whether to reject enum fields whose value is not defined by the enum type.
+    ///
+    /// If `false`, such values are stored as a [`Value::EnumNumber`] like any other value. If `true`,
+    /// decoding returns an error. This applies to singular, repeated and map value fields, as well
+    /// as extensions.
+    ///
+    /// The default value is `false`.
+    pub const fn strict_enums(mut self, yes: bool) -> Self {
+        self.strict_enums = yes;
+        self
+    }
+
+    /// The maximum depth of nested messages to decode.
+    ///
+    /// If a message contains more than `limit` levels of nested messages, decoding returns an
+    /// error instead of recursing further. This protects against stack overflows when decoding
+    /// untrusted input. Since `prost` also enforces a limit of 100 levels, larger values are
+    /// treated as `100`.
+    ///
+    /// The default value is `100`.
+    pub const fn recursion_limit(mut self, limit: u32) -> Self {
+        self.recursion_limit = if limit < MAX_RECURSION_LIMIT {
+            limit
+        } else {
+            MAX_RECURSION_LIMIT
+        };
+        self
+    }
+
+    /// Whether to stop decoding, rather than returning an error, when the buffer ends with bytes
+    /// which do not form a complete field.
+    ///
+    /// The protobuf binary format has no terminator, so a message is only known to end where the
+    /// buffer does. If `true`, decoding instead stops before the first field whose key or value is
+    /// malformed or truncated, or which is an unmatched end-group tag, and the remaining bytes are
+    /// discarded. Errors in the fields before that point, such as an invalid nested message, are
+    /// still returned. Use [`DynamicMessage::decode_prefix`] to find out how many bytes were used.
+    ///
+    /// The default value is `false`.
+    pub const fn allow_trailing_bytes(mut self, yes: bool) -> Self {
+        self.allow_trailing_bytes = yes;
+        self
+    }
+
+    /// Whether to reject `string` fields which are not valid UTF-8.
+    ///
+    /// If `true`, decoding such a field returns an error which includes the offset of the first
+    /// invalid byte within the string. If `false`, invalid sequences are instead replaced with
+    /// [`U+FFFD REPLACEMENT CHARACTER`][std::char::REPLACEMENT_CHARACTER], since a [`Value::String`]
+    /// can only hold valid UTF-8. This applies to singular, repeated and map fields, as well as map
+    /// keys and extensions.
+    ///
+    /// The default value is `true`, as required by the protobuf specification.
+    pub const fn validate_utf8(mut self, yes: bool) -> Self {
+        self.validate_utf8 = yes;
+        self
+    }
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl From<MapKey> for Value {
     fn from(value: MapKey) -> Self {
         match value {
@@ -890,6 +2116,6 @@ impl From<MapKey> for Value {
 
 #[test]
 fn type_sizes() {
-    assert_eq!(std::mem::size_of::<DynamicMessage>(), 40);
+    assert_eq!(std::mem::size_of::<DynamicMessage>(), 48);
     assert_eq!(std::mem::size_of::<Value>(), 56);
 }
@@ -1,7 +1,9 @@
 use std::{borrow::Cow, collections::HashMap, convert::TryInto, fmt, str::FromStr};
 
 use prost::bytes::Bytes;
-use serde::de::{DeserializeSeed, Deserializer, Error, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::de::{
+    Deserialize, DeserializeSeed, Deserializer, Error, IgnoredAny, MapAccess, SeqAccess, Visitor,
+};
 
 use crate::{
     dynamic::{serde::DeserializeOptions, DynamicMessage, MapKey, Value},
@@ -51,6 +53,8 @@ impl<'a, 'de> DeserializeSeed<'de> for KindSeed<'a> {
 
 pub struct ListVisitor<'a>(pub &'a Kind, pub &'a DeserializeOptions);
 pub struct MapVisitor<'a>(pub &'a Kind, pub &'a DeserializeOptions);
+
+pub struct MapKeySeed<'a>(pub &'a Kind);
 pub struct DoubleVisitor;
 pub struct FloatVisitor;
 pub struct Int32Visitor;
@@ -103,27 +107,7 @@ impl<'a, 'de> Visitor<'de> for MapVisitor<'a> {
         let key_kind = map_entry_message.map_entry_key_field().kind();
         let value_desc = map_entry_message.map_entry_value_field();
 
-        while let Some(key_str) = map.next_key::<Cow<str>>()? {
-            let key = match key_kind {
-                Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
-                    MapKey::I32(i32::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
-                    MapKey::I64(i64::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::Uint32 | Kind::Fixed32 => {
-                    MapKey::U32(u32::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::Uint64 | Kind::Fixed64 => {
-                    MapKey::U64(u64::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::Bool => {
-                    MapKey::Bool(bool::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::String => MapKey::String(key_str.into_owned()),
-                _ => unreachable!("invalid type for map key"),
-            };
-
+        while let Some(key) = map.next_key_seed(MapKeySeed(&key_kind))? {
             let value = map.next_value_seed(FieldDescriptorSeed(&value_desc, self.1))?;
 
             result.insert(key, value);
@@ -133,6 +117,35 @@ impl<'a, 'de> Visitor<'de> for MapVisitor<'a> {
     }
 }
 
+impl<'a, 'de> DeserializeSeed<'de> for MapKeySeed<'a> {
+    type Value = MapKey;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let key_str = Cow::<str>::deserialize(deserializer)?;
+        let key = match self.0 {
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+                MapKey::I32(i32::from_str(key_str.as_ref()).map_err(Error::custom)?)
+            }
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+                MapKey::I64(i64::from_str(key_str.as_ref()).map_err(Error::custom)?)
+            }
+            Kind::Uint32 | Kind::Fixed32 => {
+                MapKey::U32(u32::from_str(key_str.as_ref()).map_err(Error::custom)?)
+            }
+            Kind::Uint64 | Kind::Fixed64 => {
+                MapKey::U64(u64::from_str(key_str.as_ref()).map_err(Error::custom)?)
+            }
+            Kind::Bool => MapKey::Bool(bool::from_str(key_str.as_ref()).map_err(Error::custom)?),
+            Kind::String => MapKey::String(key_str.into_owned()),
+            _ => return Err(Error::custom("invalid type for map key")),
+        };
+        Ok(key)
+    }
+}
+
 impl<'de> Visitor<'de> for DoubleVisitor {
     type Value = f64;
 
@@ -198,10 +211,13 @@ impl<'de> Visitor<'de> for FloatVisitor {
     where
         E: Error,
     {
-        if v < (f32::MIN as f64) || v > (f32::MAX as f64) {
+        // The shortest representation of a large `f32` may be slightly outside the range of `f32`
+        // when parsed as an `f64`, so only reject values which do not round to a finite `f32`.
+        let value = v as f32;
+        if value.is_infinite() && v.is_finite() {
             Err(Error::custom("float value out of range"))
         } else {
-            Ok(v as f32)
+            Ok(value)
         }
     }
 
@@ -559,12 +575,16 @@ impl<'a, 'de> Visitor<'de> for MessageVisitorInner<'a> {
                     }
 
                     self.0.set_field(&field, value);
+                } else {
+                    self.0.clear_field(&field);
                 }
             } else if let Some(extension_desc) = desc.get_extension_by_json_name(key.as_ref()) {
                 if let Some(value) =
                     map.next_value_seed(OptionalFieldDescriptorSeed(&extension_desc, self.1))?
                 {
                     self.0.set_extension(&extension_desc, value);
+                } else {
+                    self.0.clear_extension(&extension_desc);
                 }
             } else if self.1.deny_unknown_fields {
                 return Err(Error::custom(format!("unrecognized field name '{}'", key)));
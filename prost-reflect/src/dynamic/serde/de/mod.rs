@@ -7,7 +7,9 @@ use prost::Message;
 use serde::de::{DeserializeSeed, Deserializer, Error, Visitor};
 
 use crate::{
-    dynamic::{fields::FieldDescriptorLike, serde::DeserializeOptions, DynamicMessage, Value},
+    dynamic::{
+        fields::FieldDescriptorLike, serde::DeserializeOptions, DynamicMessage, MapKey, Value,
+    },
     EnumDescriptor, Kind, MessageDescriptor,
 };
 
@@ -21,7 +23,13 @@ where
 {
     match desc.full_name() {
         "google.protobuf.Any" => deserializer
-            .deserialize_any(wkt::GoogleProtobufAnyVisitor(desc.parent_pool(), options))
+            .deserialize_any(wkt::GoogleProtobufAnyVisitor(
+                options
+                    .type_resolver
+                    .as_ref()
+                    .unwrap_or_else(|| desc.parent_pool()),
+                options,
+            ))
             .and_then(|timestamp| make_message(desc, timestamp)),
         "google.protobuf.Timestamp" => deserializer
             .deserialize_str(wkt::GoogleProtobufTimestampVisitor)
@@ -75,6 +83,13 @@ where
     }
 }
 
+pub(super) fn deserialize_map_key<'de, D>(kind: &Kind, deserializer: D) -> Result<MapKey, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    kind::MapKeySeed(kind).deserialize(deserializer)
+}
+
 fn deserialize_enum<'de, D>(desc: &EnumDescriptor, deserializer: D) -> Result<i32, D::Error>
 where
     D: Deserializer<'de>,
@@ -163,19 +178,22 @@ where
     where
         E: Error,
     {
-        if let Kind::Message(message_desc) = self.0.kind() {
-            match message_desc.full_name() {
-                "google.protobuf.Value" => make_message(
+        // An explicit `null` leaves the field unset, except for the well-known types which
+        // represent a JSON `null` value.
+        match self.0.kind() {
+            Kind::Message(message_desc) if message_desc.full_name() == "google.protobuf.Value" => {
+                make_message(
                     &message_desc,
                     prost_types::Value {
                         kind: Some(prost_types::value::Kind::NullValue(0)),
                     },
                 )
-                .map(|v| Some(Value::Message(v))),
-                _ => Ok(None),
+                .map(|v| Some(Value::Message(v)))
             }
-        } else {
-            Ok(Some(self.0.default_value()))
+            Kind::Enum(enum_desc) if enum_desc.full_name() == "google.protobuf.NullValue" => {
+                Ok(Some(self.0.default_value()))
+            }
+            _ => Ok(None),
         }
     }
 
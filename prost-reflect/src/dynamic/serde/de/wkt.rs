@@ -85,7 +85,7 @@ impl<'a, 'de> Visitor<'de> for GoogleProtobufAnyVisitor<'a> {
                                     let _ = map.next_value::<IgnoredAny>()?;
                                 }
                             }
-                            None => return Err(Error::custom("expected '@type' field")),
+                            None => return Err(Error::custom("expected 'value' field")),
                         }
                     },
                 };
@@ -2,18 +2,33 @@ mod case;
 mod de;
 mod ser;
 
+use std::borrow::Borrow;
+
 use serde::{
     de::{DeserializeSeed, Deserializer},
     ser::{Serialize, Serializer},
 };
 
-use crate::{DynamicMessage, MessageDescriptor};
+#[cfg(feature = "reflect-well-known-types")]
+use prost_types::FileDescriptorSet;
+#[cfg(feature = "reflect-well-known-types")]
+use serde::de::Error as _;
+
+#[cfg(feature = "reflect-well-known-types")]
+use crate::ReflectMessage;
+use crate::{DescriptorPool, DynamicMessage, Kind, MapKey, MessageDescriptor, WellKnownType};
 
 /// Options to control serialization of messages.
+///
+/// Finite `float` values are passed to [`Serializer::serialize_f32`] rather than being widened to
+/// `double`, so serializers such as `serde_json` write the shortest representation which parses back
+/// to the same `float`. Non-finite values are written as the strings `"NaN"`, `"Infinity"` and
+/// `"-Infinity"`.
 #[derive(Debug, Clone)]
 #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
 pub struct SerializeOptions {
     stringify_64_bit_integers: bool,
+    int64_as_number: bool,
     use_enum_numbers: bool,
     use_proto_field_name: bool,
     skip_default_fields: bool,
@@ -24,6 +39,7 @@ pub struct SerializeOptions {
 #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
 pub struct DeserializeOptions {
     deny_unknown_fields: bool,
+    type_resolver: Option<DescriptorPool>,
 }
 
 #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
@@ -113,6 +129,44 @@ impl DynamicMessage {
         ser::serialize_message(self, serializer, options)
     }
 
+    /// Serialize a sequence of messages into `serializer` using the encoding specified by
+    /// `options`.
+    ///
+    /// Each message is serialized as it is yielded by `messages`, so when used with a streaming
+    /// serializer such as [`serde_json::Serializer`](https://docs.rs/serde_json/latest/serde_json/struct.Serializer.html),
+    /// the messages do not all need to be held in memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_types::FileDescriptorSet;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value, SerializeOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let messages = (1..=3).map(|foo| {
+    ///     let mut message = DynamicMessage::new(message_descriptor.clone());
+    ///     message.set_field_by_name("foo", Value::I32(foo));
+    ///     message
+    /// });
+    /// let mut serializer = serde_json::Serializer::new(vec![]);
+    /// DynamicMessage::serialize_messages_with_options(messages, &mut serializer, &SerializeOptions::new()).unwrap();
+    /// assert_eq!(serializer.into_inner(), b"[{\"foo\":1},{\"foo\":2},{\"foo\":3}]");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn serialize_messages_with_options<S, I>(
+        messages: I,
+        serializer: S,
+        options: &SerializeOptions,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        I: IntoIterator,
+        I::Item: Borrow<DynamicMessage>,
+    {
+        ser::serialize_messages(messages, serializer, options)
+    }
+
     /// Deserialize an instance of the message type described by `desc` from `deserializer`.
     ///
     /// # Examples
@@ -170,12 +224,124 @@ impl DynamicMessage {
     }
 }
 
+#[cfg(feature = "reflect-well-known-types")]
+impl DescriptorPool {
+    /// Deserializes a `google.protobuf.FileDescriptorSet` in the protobuf JSON format from
+    /// `deserializer`, and uses it to create a new [`DescriptorPool`].
+    ///
+    /// This is the JSON counterpart to [`decode`][DescriptorPool::decode]. The JSON is parsed
+    /// using the bundled descriptor for `google/protobuf/descriptor.proto`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::DescriptorPool;
+    /// # use serde1 as serde;
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// let mut json = Vec::new();
+    /// pool.encode_json(&mut serde_json::Serializer::new(&mut json)).unwrap();
+    ///
+    /// let mut deserializer = serde_json::de::Deserializer::from_slice(&json);
+    /// let decoded = DescriptorPool::decode_json(&mut deserializer).unwrap();
+    /// deserializer.end().unwrap();
+    ///
+    /// assert_eq!(decoded.file_descriptor_set(), pool.file_descriptor_set());
+    /// ```
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(all(feature = "serde", feature = "reflect-well-known-types")))
+    )]
+    pub fn decode_json<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let message =
+            DynamicMessage::deserialize(FileDescriptorSet::default().descriptor(), deserializer)?;
+        let file_descriptor_set = message
+            .transcode_to::<FileDescriptorSet>()
+            .map_err(D::Error::custom)?;
+        DescriptorPool::from_file_descriptor_set(file_descriptor_set).map_err(D::Error::custom)
+    }
+
+    /// Serializes the files in this pool to `serializer` as a `google.protobuf.FileDescriptorSet`
+    /// in the protobuf JSON format.
+    ///
+    /// The output may be passed to [`decode_json`][DescriptorPool::decode_json] to recreate this
+    /// pool.
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(all(feature = "serde", feature = "reflect-well-known-types")))
+    )]
+    pub fn encode_json<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.file_descriptor_set()
+            .transcode_to_dynamic()
+            .serialize(serializer)
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl Serialize for MapKey {
+    /// Serialize this map key into `serializer` as a string, as required by the
+    /// [canonical JSON encoding](https://developers.google.com/protocol-buffers/docs/proto3#json)
+    /// for map keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::MapKey;
+    /// assert_eq!(serde_json::to_string(&MapKey::I32(42)).unwrap(), r#""42""#);
+    /// assert_eq!(serde_json::to_string(&MapKey::Bool(true)).unwrap(), r#""true""#);
+    /// assert_eq!(serde_json::to_string(&MapKey::String("key".to_owned())).unwrap(), r#""key""#);
+    /// ```
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            MapKey::Bool(value) => serializer.collect_str(value),
+            MapKey::I32(value) => serializer.collect_str(value),
+            MapKey::I64(value) => serializer.collect_str(value),
+            MapKey::U32(value) => serializer.collect_str(value),
+            MapKey::U64(value) => serializer.collect_str(value),
+            MapKey::String(value) => serializer.serialize_str(value),
+        }
+    }
+}
+
+impl MapKey {
+    /// Deserialize a map key of type `kind` from its string representation in `deserializer`.
+    ///
+    /// Returns an error if the string cannot be parsed as `kind`, or if `kind` is not a valid map key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{Kind, MapKey};
+    /// let mut deserializer = serde_json::de::Deserializer::from_str(r#""42""#);
+    /// let key = MapKey::deserialize(&Kind::Int32, &mut deserializer).unwrap();
+    /// deserializer.end().unwrap();
+    ///
+    /// assert_eq!(key, MapKey::I32(42));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn deserialize<'de, D>(kind: &Kind, deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        de::deserialize_map_key(kind, deserializer)
+    }
+}
+
 impl DeserializeOptions {
     /// Creates a new instance of [`DeserializeOptions`], with the default options chosen to conform to
     /// the standard JSON mapping.
     pub const fn new() -> Self {
         DeserializeOptions {
             deny_unknown_fields: true,
+            type_resolver: None,
         }
     }
 
@@ -186,6 +352,16 @@ impl DeserializeOptions {
         self.deny_unknown_fields = yes;
         self
     }
+
+    /// The [`DescriptorPool`] used to resolve the message type named by the `@type` field of
+    /// `google.protobuf.Any` messages.
+    ///
+    /// By default, the type is resolved using the parent pool of the `google.protobuf.Any` message
+    /// descriptor. An error is returned if the type cannot be found.
+    pub fn type_resolver(mut self, pool: DescriptorPool) -> Self {
+        self.type_resolver = Some(pool);
+        self
+    }
 }
 
 impl Default for DeserializeOptions {
@@ -200,6 +376,7 @@ impl SerializeOptions {
     pub const fn new() -> Self {
         SerializeOptions {
             stringify_64_bit_integers: true,
+            int64_as_number: false,
             use_enum_numbers: false,
             use_proto_field_name: false,
             skip_default_fields: true,
@@ -218,6 +395,22 @@ impl SerializeOptions {
         self
     }
 
+    /// Whether to encode 64-bit integral types as numbers if they can be represented exactly by a
+    /// double.
+    ///
+    /// If `true`, values of 64-bit integer fields and the `Int64Value` and `UInt64Value` wrapper
+    /// types whose magnitude is less than 2<sup>53</sup> are serialized as numbers, and larger
+    /// values are serialized as strings. Note that this does not conform to the JSON mapping, and
+    /// consumers may still lose precision if they convert the numbers to a narrower type. This
+    /// option has no effect if [`stringify_64_bit_integers`][Self::stringify_64_bit_integers] is
+    /// disabled.
+    ///
+    /// The default value is `false`.
+    pub const fn int64_as_number(mut self, yes: bool) -> Self {
+        self.int64_as_number = yes;
+        self
+    }
+
     /// Whether to encode enum values as their numeric value.
     ///
     /// If `true`, enum values will be serialized as their integer values. Otherwise, they will be
@@ -262,26 +455,7 @@ const MIN_TIMESTAMP_SECONDS: i64 = -62135596800;
 const MAX_TIMESTAMP_SECONDS: i64 = 253402300799;
 
 fn is_well_known_type(full_name: &str) -> bool {
-    matches!(
-        full_name,
-        "google.protobuf.Any"
-            | "google.protobuf.Timestamp"
-            | "google.protobuf.Duration"
-            | "google.protobuf.Struct"
-            | "google.protobuf.FloatValue"
-            | "google.protobuf.DoubleValue"
-            | "google.protobuf.Int32Value"
-            | "google.protobuf.Int64Value"
-            | "google.protobuf.UInt32Value"
-            | "google.protobuf.UInt64Value"
-            | "google.protobuf.BoolValue"
-            | "google.protobuf.StringValue"
-            | "google.protobuf.BytesValue"
-            | "google.protobuf.FieldMask"
-            | "google.protobuf.ListValue"
-            | "google.protobuf.Value"
-            | "google.protobuf.Empty"
-    )
+    WellKnownType::from_full_name(full_name).is_some()
 }
 
 fn check_duration(duration: &prost_types::Duration) -> Result<(), &'static str> {
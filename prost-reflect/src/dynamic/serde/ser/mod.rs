@@ -1,12 +1,14 @@
 mod wkt;
 
+use std::borrow::Borrow;
+
 use base64::display::Base64Display;
 
 use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
 
 use crate::{
     descriptor::Kind,
-    dynamic::{fields::ValueAndDescriptor, serde::SerializeOptions, DynamicMessage, MapKey, Value},
+    dynamic::{fields::ValueAndDescriptor, serde::SerializeOptions, DynamicMessage, Value},
     ReflectMessage,
 };
 
@@ -30,6 +32,39 @@ where
     .serialize(serializer)
 }
 
+pub(super) fn serialize_messages<S, I>(
+    messages: I,
+    serializer: S,
+    options: &SerializeOptions,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    I: IntoIterator,
+    I::Item: Borrow<DynamicMessage>,
+{
+    let mut seq = serializer.serialize_seq(None)?;
+    for message in messages {
+        seq.serialize_element(&SerializeWrapper {
+            value: message.borrow(),
+            options,
+        })?;
+    }
+    seq.end()
+}
+
+/// The largest integer magnitude which can be represented exactly by a double.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+impl SerializeOptions {
+    fn stringify_i64(&self, value: i64) -> bool {
+        self.stringify_u64(value.unsigned_abs())
+    }
+
+    fn stringify_u64(&self, value: u64) -> bool {
+        self.stringify_64_bit_integers && !(self.int64_as_number && value <= MAX_SAFE_INTEGER)
+    }
+}
+
 impl<'a> Serialize for SerializeWrapper<'a, DynamicMessage> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -129,7 +164,7 @@ impl<'a> Serialize for SerializeWrapper<'a, ValueAndKind<'a>> {
             Value::Bool(value) => serializer.serialize_bool(*value),
             Value::I32(value) => serializer.serialize_i32(*value),
             Value::I64(value) => {
-                if self.options.stringify_64_bit_integers {
+                if self.options.stringify_i64(*value) {
                     serializer.collect_str(value)
                 } else {
                     serializer.serialize_i64(*value)
@@ -137,7 +172,7 @@ impl<'a> Serialize for SerializeWrapper<'a, ValueAndKind<'a>> {
             }
             Value::U32(value) => serializer.serialize_u32(*value),
             Value::U64(value) => {
-                if self.options.stringify_64_bit_integers {
+                if self.options.stringify_u64(*value) {
                     serializer.collect_str(value)
                 } else {
                     serializer.serialize_u64(*value)
@@ -218,10 +253,7 @@ impl<'a> Serialize for SerializeWrapper<'a, ValueAndKind<'a>> {
                 let mut map = serializer.serialize_map(Some(values.len()))?;
                 for (key, value) in values {
                     map.serialize_entry(
-                        &SerializeWrapper {
-                            value: key,
-                            options: self.options,
-                        },
+                        key,
                         &SerializeWrapper {
                             value: &ValueAndKind {
                                 value,
@@ -236,19 +268,3 @@ impl<'a> Serialize for SerializeWrapper<'a, ValueAndKind<'a>> {
         }
     }
 }
-
-impl<'a> Serialize for SerializeWrapper<'a, MapKey> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        match self.value {
-            MapKey::Bool(value) => serializer.collect_str(value),
-            MapKey::I32(value) => serializer.collect_str(value),
-            MapKey::I64(value) => serializer.collect_str(value),
-            MapKey::U32(value) => serializer.collect_str(value),
-            MapKey::U64(value) => serializer.collect_str(value),
-            MapKey::String(value) => serializer.serialize_str(value),
-        }
-    }
-}
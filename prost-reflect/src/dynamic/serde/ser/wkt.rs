@@ -174,7 +174,7 @@ where
 {
     let raw: i64 = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    if options.stringify_64_bit_integers {
+    if options.stringify_i64(raw) {
         serializer.collect_str(&raw)
     } else {
         serializer.serialize_i64(raw)
@@ -204,7 +204,7 @@ where
 {
     let raw: u64 = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    if options.stringify_64_bit_integers {
+    if options.stringify_u64(raw) {
         serializer.collect_str(&raw)
     } else {
         serializer.serialize_u64(raw)
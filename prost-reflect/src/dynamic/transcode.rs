@@ -0,0 +1,195 @@
+use std::{collections::HashMap, fmt::Write};
+
+use prost::DecodeError;
+
+use crate::{DynamicMessage, Kind, MapKey, MessageDescriptor, ReflectMessage, Value};
+
+use super::diff::push_path_segment;
+
+/// A report of data which could not be represented when converting a [`DynamicMessage`] into a
+/// strongly-typed message.
+///
+/// See [`DynamicMessage::transcode_to_with_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TranscodeReport {
+    issues: Vec<TranscodeIssue>,
+}
+
+/// A field whose value was lost or changed while transcoding a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscodeIssue {
+    /// The field is not defined by the target message type, so its value was discarded.
+    FieldAbsent {
+        /// The path of the field, for example `nested.int32`.
+        path: String,
+    },
+    /// The value of the field could not be represented by the type of the corresponding field in
+    /// the target message type, so it was changed.
+    ValueOutOfRange {
+        /// The path of the field, for example `nested.int32`.
+        path: String,
+    },
+}
+
+impl DynamicMessage {
+    /// Convert this dynamic message into a strongly typed value, reporting any fields whose values
+    /// could not be represented by the target type.
+    ///
+    /// Like [`transcode_to`][Self::transcode_to], this fails with a [`DecodeError`] if the message
+    /// is not wire-compatible with `T`. Otherwise, the returned [`TranscodeReport`] lists fields
+    /// which are not defined by `T` (including all extension fields), and fields whose values were
+    /// changed, for example because they were truncated to a smaller integer type. Enum values
+    /// which are not defined by the target enum type are not reported, since prost stores enum
+    /// fields as `i32` and preserves undefined values, even for closed enums defined in proto2
+    /// files. Unknown fields are not reported.
+    pub fn transcode_to_with_report<T>(&self) -> Result<(T, TranscodeReport), DecodeError>
+    where
+        T: ReflectMessage + Default,
+    {
+        let value: T = self.transcode_to()?;
+        let target = value.transcode_to_dynamic();
+
+        let mut report = TranscodeReport::default();
+        report.check_message(self, &target, &mut String::new());
+        Ok((value, report))
+    }
+}
+
+impl TranscodeReport {
+    /// Gets the fields which could not be transcoded, in the order they were encountered.
+    pub fn issues(&self) -> &[TranscodeIssue] {
+        &self.issues
+    }
+
+    /// Returns `true` if all fields were transcoded without loss.
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn check_message(
+        &mut self,
+        source: &DynamicMessage,
+        target: &DynamicMessage,
+        path: &mut String,
+    ) {
+        for field in source.desc.fields() {
+            if !source.has_field(&field) {
+                continue;
+            }
+
+            let len = path.len();
+            push_path_segment(path, field.name());
+            match target.desc.get_field(field.number()) {
+                Some(target_field) => self.check_value(
+                    &source.get_field(&field),
+                    &target.get_field(&target_field),
+                    &target_field.kind(),
+                    path,
+                ),
+                None => self
+                    .issues
+                    .push(TranscodeIssue::FieldAbsent { path: path.clone() }),
+            }
+            path.truncate(len);
+        }
+
+        for extension in source.desc.extensions() {
+            if source.has_extension(&extension) {
+                let len = path.len();
+                push_path_segment(path, &format!("[{}]", extension.full_name()));
+                self.issues
+                    .push(TranscodeIssue::FieldAbsent { path: path.clone() });
+                path.truncate(len);
+            }
+        }
+    }
+
+    fn check_value(&mut self, source: &Value, target: &Value, kind: &Kind, path: &mut String) {
+        match (source, target) {
+            (Value::Message(source), Value::Message(target)) => {
+                self.check_message(source, target, path)
+            }
+            (Value::List(source), Value::List(target)) if source.len() == target.len() => {
+                for (index, (source, target)) in source.iter().zip(target).enumerate() {
+                    let len = path.len();
+                    write!(path, "[{}]", index).unwrap();
+                    self.check_value(source, target, kind, path);
+                    path.truncate(len);
+                }
+            }
+            (Value::Map(source), Value::Map(target)) => {
+                let map_entry = match kind {
+                    Kind::Message(map_entry) => map_entry,
+                    _ => return self.value_out_of_range(path),
+                };
+                self.check_map(source, target, map_entry, path)
+            }
+            (source, target) => {
+                if !scalar_eq(source, target) {
+                    self.value_out_of_range(path)
+                }
+            }
+        }
+    }
+
+    fn check_map(
+        &mut self,
+        source: &HashMap<MapKey, Value>,
+        target: &HashMap<MapKey, Value>,
+        map_entry: &MessageDescriptor,
+        path: &mut String,
+    ) {
+        let value_kind = map_entry.map_entry_value_field().kind();
+
+        let mut keys: Vec<&MapKey> = source.keys().collect();
+        keys.sort();
+        for key in keys {
+            let len = path.len();
+            write!(path, "[{}]", Value::from(key.clone())).unwrap();
+            let target_value = target.get(key).or_else(|| {
+                let key = Value::from(key.clone());
+                target
+                    .iter()
+                    .find(|(target_key, _)| scalar_eq(&key, &Value::from((*target_key).clone())))
+                    .map(|(_, value)| value)
+            });
+            match target_value {
+                Some(target_value) => {
+                    self.check_value(&source[key], target_value, &value_kind, path)
+                }
+                None => self.value_out_of_range(path),
+            }
+            path.truncate(len);
+        }
+    }
+
+    fn value_out_of_range(&mut self, path: &str) {
+        self.issues.push(TranscodeIssue::ValueOutOfRange {
+            path: path.to_owned(),
+        });
+    }
+}
+
+fn scalar_eq(source: &Value, target: &Value) -> bool {
+    match (as_integer(source), as_integer(target)) {
+        (Some(source), Some(target)) => source == target,
+        _ => match (source, target) {
+            (Value::String(source), Value::Bytes(target)) => source.as_bytes() == target.as_ref(),
+            (Value::Bytes(source), Value::String(target)) => source.as_ref() == target.as_bytes(),
+            (Value::F32(source), Value::F32(target)) => source.to_bits() == target.to_bits(),
+            (Value::F64(source), Value::F64(target)) => source.to_bits() == target.to_bits(),
+            (source, target) => source == target,
+        },
+    }
+}
+
+fn as_integer(value: &Value) -> Option<i128> {
+    match *value {
+        Value::Bool(value) => Some(value.into()),
+        Value::I32(value) | Value::EnumNumber(value) => Some(value.into()),
+        Value::I64(value) => Some(value.into()),
+        Value::U32(value) => Some(value.into()),
+        Value::U64(value) => Some(value.into()),
+        _ => None,
+    }
+}
@@ -7,7 +7,7 @@ use prost::{
 };
 
 /// An unknown field in a protobuf message.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub(crate) enum UnknownField {
     /// An unknown field with the `Varint` wire type.
     Varint(u64),
@@ -21,7 +21,7 @@ pub(crate) enum UnknownField {
     ThirtyTwoBit([u8; 4]),
 }
 
-#[derive(Debug, Default, Clone, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq, Hash)]
 pub(crate) struct UnknownFieldSet {
     fields: BTreeMap<u32, Vec<UnknownField>>,
 }
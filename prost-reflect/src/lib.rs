@@ -17,19 +17,31 @@ mod descriptor;
 mod dynamic;
 mod reflect;
 
+#[cfg(feature = "conformance")]
+#[cfg_attr(docsrs, doc(cfg(feature = "conformance")))]
+pub mod conformance;
+
 pub use {prost, prost::bytes, prost_types};
 
 pub use self::descriptor::{
-    Cardinality, DescriptorError, DescriptorPool, EnumDescriptor, EnumValueDescriptor,
+    Cardinality, Comments, DescriptorError, DescriptorPool, EnumDescriptor, EnumValueDescriptor,
     ExtensionDescriptor, FieldDescriptor, FileDescriptor, Kind, MessageDescriptor,
-    MethodDescriptor, OneofDescriptor, ServiceDescriptor, Syntax,
+    MethodDescriptor, OneofDescriptor, ParseCardinalityError, ServiceDescriptor, Syntax,
+    WellKnownType,
+};
+pub use self::dynamic::{
+    BuildMessageError, ConversionError, DecodeOptions, DynamicMessage, DynamicMessageBuilder,
+    EncodeReport, EnumValueError, FromFlatMapError, InterpolateError, MapKey, MergeError,
+    SetFieldError, TranscodeIssue, TranscodeReport, TryFromValue, Value,
 };
-pub use self::dynamic::{DynamicMessage, MapKey, Value};
-pub use self::reflect::ReflectMessage;
+pub use self::reflect::{DynReflect, ReflectMessage};
 
 #[cfg(feature = "serde")]
 pub use self::dynamic::{DeserializeOptions, SerializeOptions};
 
+#[cfg(feature = "schema-registry")]
+pub use self::descriptor::SchemaRegistry;
+
 #[cfg(feature = "derive")]
 #[cfg_attr(docsrs, doc(cfg(feature = "derive")))]
 pub use prost_reflect_derive::ReflectMessage;
@@ -35,7 +35,39 @@ where
     }
 }
 
+/// An object-safe extension of [`ReflectMessage`], for working with messages of different types
+/// through a trait object such as `Box<dyn DynReflect>`.
+///
+/// Some methods of [`ReflectMessage`] and [`Message`] require `Self: Sized`, so cannot be called on
+/// a `dyn ReflectMessage`. This trait provides equivalents which can be, and is implemented for
+/// every type implementing [`ReflectMessage`].
+pub trait DynReflect: ReflectMessage {
+    /// Encodes this message to a newly allocated buffer.
+    ///
+    /// This is equivalent to [`Message::encode_to_vec`].
+    fn dyn_encode_to_vec(&self) -> Vec<u8>;
+
+    /// Converts this message into an instance of [`DynamicMessage`].
+    ///
+    /// This is equivalent to [`ReflectMessage::transcode_to_dynamic`].
+    fn dyn_transcode_to_dynamic(&self) -> DynamicMessage;
+}
+
+impl<M> DynReflect for M
+where
+    M: ReflectMessage,
+{
+    fn dyn_encode_to_vec(&self) -> Vec<u8> {
+        self.encode_to_vec()
+    }
+
+    fn dyn_transcode_to_dynamic(&self) -> DynamicMessage {
+        self.transcode_to_dynamic()
+    }
+}
+
 #[test]
 fn assert_object_safe() {
     fn _foo(_: Box<dyn ReflectMessage>) {}
+    fn _bar(_: Box<dyn DynReflect>) {}
 }